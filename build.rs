@@ -3,13 +3,28 @@ use cxx_qt_build::{CxxQtBuilder, QmlModule};
 fn main() {
     println!("cargo:rerun-if-changed=src");
     println!("cargo:rerun-if-changed=build.rs");
-    CxxQtBuilder::new_qml_module(QmlModule::new("org.kourier").qml_files([
+    let builder = CxxQtBuilder::new_qml_module(QmlModule::new("org.kourier").qml_files([
         "src/qml/Main.qml",
         "src/qml/MessageDelegate.qml",
         "src/qml/MediaViewerDialog.qml",
         "src/qml/LoginDialog.qml",
+        "src/qml/ContactPickerDialog.qml",
+        "src/qml/ParticipantsDialog.qml",
+        "src/qml/MessageDetailsDialog.qml",
+        "src/qml/MediaGalleryDialog.qml",
+        "src/qml/MediaExportDialog.qml",
     ]))
-    .files(["src/lib.rs"])
-    .qrc("src/qml/resources.qrc")
-    .build();
+    .files(["src/lib.rs", "src/image_provider.rs", "src/clipboard.rs"])
+    .cpp_file("src/cpp/image_provider.cpp")
+    .cpp_file("src/cpp/clipboard.cpp")
+    .qt_module("Quick")
+    .qrc("src/qml/resources.qrc");
+
+    // SAFETY: only adds the include path for our own C++ headers
+    let builder = unsafe {
+        builder.cc_builder(|cc| {
+            cc.include("src/cpp");
+        })
+    };
+    builder.build();
 }
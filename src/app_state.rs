@@ -1,13 +1,49 @@
 pub mod app_state_impl;
+pub mod audio;
+pub mod auth_storage;
+pub mod avatar_cache;
+pub mod cache;
+pub mod contact_list;
 pub mod conversation_list;
+pub mod generated_avatar;
+pub mod heif;
+pub mod media_export;
+pub mod media_gallery;
+pub mod media_prep;
+pub mod message_details;
 pub mod message_list;
+pub mod participant_list;
+pub mod photo_metadata;
+pub mod rich_content;
 pub mod session_controller;
+pub mod settings;
 pub mod shared;
+pub mod sim_list;
+pub mod thumbnails;
+pub mod uri_handler;
 pub mod utils;
 
 pub use app_state_impl::*;
+pub use audio::*;
+pub use auth_storage::*;
+pub use avatar_cache::*;
+pub use cache::*;
+pub use contact_list::*;
 pub use conversation_list::*;
+pub use generated_avatar::*;
+pub use heif::*;
+pub use media_export::*;
+pub use media_gallery::*;
+pub use media_prep::*;
+pub use message_details::*;
 pub use message_list::*;
+pub use participant_list::*;
+pub use photo_metadata::*;
+pub use rich_content::*;
 pub use session_controller::*;
+pub use settings::*;
 pub use shared::*;
+pub use sim_list::*;
+pub use thumbnails::*;
+pub use uri_handler::*;
 pub use utils::*;
@@ -2,7 +2,7 @@ use crate::ffi;
 use core::pin::Pin;
 use cxx_qt::{CxxQtThread, CxxQtType, Threading};
 use cxx_qt_lib::QString;
-use libgmessages_rs::{auth::AuthData, gmclient::GMClient, store::AuthDataStore};
+use libgmessages_rs::{auth::AuthData, gmclient::GMClient};
 use std::sync::{atomic::AtomicBool, Arc};
 use std::time::Duration;
 
@@ -15,6 +15,10 @@ pub struct AppStateRust {
     pub qr_url: QString,
     pub qr_svg_data_url: QString,
     pub status_message: QString,
+    pub encrypted_cache: bool,
+    pub media_quality: QString,
+    pub keep_photo_metadata: bool,
+    pub link_preview_policy: QString,
     login_stop: Arc<AtomicBool>,
 }
 
@@ -26,6 +30,10 @@ impl Default for AppStateRust {
             qr_url: QString::from(""),
             qr_svg_data_url: QString::from(""),
             status_message: QString::from("Not logged in"),
+            encrypted_cache: settings().encrypted_cache,
+            media_quality: QString::from(settings().media_quality.name()),
+            keep_photo_metadata: settings().keep_photo_metadata,
+            link_preview_policy: QString::from(settings().link_previews.policy.name()),
             login_stop: Arc::new(AtomicBool::new(false)),
         }
     }
@@ -53,9 +61,9 @@ impl crate::ffi::AppState {
         let ui_thread = qt_thread.clone();
 
         spawn(async move {
-            let store = AuthDataStore::default_store();
-
             let result: Result<bool, String> = async {
+                // Refuse to pair when there is nowhere safe to keep the result
+                let store = auth_storage().await?;
                 loop {
                     if stop_flag.load(std::sync::atomic::Ordering::Relaxed) {
                         return Ok(false);
@@ -96,10 +104,10 @@ impl crate::ffi::AppState {
 
                     match paired {
                         Ok(Ok(Some(_))) => {
-                            // Save auth to disk
+                            // Persist auth (keyring, or disk as a fallback)
                             let auth_handle = client.auth();
                             let auth = auth_handle.lock().await;
-                            store.save(&auth).map_err(|e| e.to_string())?;
+                            store.save(&auth).await?;
                             drop(auth);
                             // Store client in shared session
                             set_client(client).await;
@@ -154,46 +162,22 @@ impl crate::ffi::AppState {
 
     pub fn initialize(self: Pin<&mut Self>) {
         let qt_thread: CxxQtThread<ffi::AppState> = self.qt_thread();
+        set_uri_target(qt_thread.clone());
 
         spawn(async move {
-            crate::app_state::utils::cleanup_old_cache_files();
-            let store = AuthDataStore::default_store();
-            let result = store.load();
+            init_cache_key().await;
+            remove_legacy_tmp_caches();
+            enforce_cache_budgets();
+            start_avatar_revalidation();
+            restore_session(&qt_thread).await;
+        });
+    }
 
-            match result {
-                Ok(Some(auth)) => {
-                    // Pre-populate the shared client
-                    let client = GMClient::new(auth);
-                    set_client(client).await;
-                    let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::AppState>| {
-                        qobject.as_mut().set_logged_in(true);
-                        qobject.as_mut().set_login_in_progress(false);
-                        qobject
-                            .as_mut()
-                            .set_status_message(QString::from("Logged in"));
-                        qobject.as_mut().initialized(true);
-                    });
-                }
-                Ok(None) => {
-                    let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::AppState>| {
-                        qobject.as_mut().set_logged_in(false);
-                        qobject.as_mut().set_login_in_progress(false);
-                        qobject
-                            .as_mut()
-                            .set_status_message(QString::from("Not logged in"));
-                        qobject.as_mut().initialized(false);
-                    });
-                }
-                Err(error) => {
-                    let message = format!("Auth load failed: {error}");
-                    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::AppState>| {
-                        qobject.as_mut().set_logged_in(false);
-                        qobject.as_mut().set_login_in_progress(false);
-                        qobject.as_mut().set_status_message(QString::from(&message));
-                        qobject.as_mut().initialized(false);
-                    });
-                }
-            }
+    /// Load the pairing credentials again, after the keyring was locked.
+    pub fn retry_auth_load(self: Pin<&mut Self>) {
+        let qt_thread: CxxQtThread<ffi::AppState> = self.qt_thread();
+        spawn(async move {
+            restore_session(&qt_thread).await;
         });
     }
 
@@ -203,8 +187,7 @@ impl crate::ffi::AppState {
 
         spawn(async move {
             clear_client().await;
-            let store = AuthDataStore::default_store();
-            if let Err(e) = store.delete() {
+            if let Err(e) = delete_auth().await {
                 eprintln!("auth delete failed: {e}");
             }
             let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::AppState>| {
@@ -225,10 +208,115 @@ impl crate::ffi::AppState {
 
     pub fn clear_cache(self: Pin<&mut Self>) {
         spawn(async move {
-            let _ = std::fs::remove_dir_all(std::env::temp_dir().join("kourier_media"));
-            let _ = std::fs::remove_dir_all(std::env::temp_dir().join("kourier_link_previews"));
-            let _ = std::fs::remove_dir_all(std::env::temp_dir().join("kourier_avatars"));
+            clear_all_caches();
+            clear_avatar_records();
             shared().avatars.write().await.clear();
         });
     }
+
+    /// Switch the on-disk caches between plain files and encrypted entries.
+    /// Existing entries are dropped since they are in the other format.
+    pub fn set_cache_encryption(mut self: Pin<&mut Self>, enabled: bool) {
+        if *self.encrypted_cache() == enabled {
+            return;
+        }
+        update_settings(|s| s.encrypted_cache = enabled);
+        self.as_mut().set_encrypted_cache(enabled);
+        self.clear_cache();
+        if enabled {
+            spawn(init_cache_key());
+        }
+    }
+
+    /// Choose how outgoing photos and videos are re-encoded: "original",
+    /// "high" or "data_saver".
+    pub fn set_media_quality_setting(mut self: Pin<&mut Self>, quality: &QString) {
+        let Some(quality) = MediaQuality::from_name(&quality.to_string()) else {
+            eprintln!("set_media_quality_setting: unknown quality {quality}");
+            return;
+        };
+        update_settings(|s| s.media_quality = quality);
+        self.as_mut().set_media_quality(QString::from(quality.name()));
+    }
+
+    /// Whether photos are sent with their EXIF/XMP metadata by default.
+    pub fn set_photo_metadata_kept(mut self: Pin<&mut Self>, keep: bool) {
+        update_settings(|s| s.keep_photo_metadata = keep);
+        self.as_mut().set_keep_photo_metadata(keep);
+    }
+
+    /// Choose whose links are fetched for previews: "off", "own_messages",
+    /// "known_contacts" or "always".
+    pub fn set_link_preview_policy_setting(mut self: Pin<&mut Self>, policy: &QString) {
+        let Some(policy) = LinkPreviewPolicy::from_name(&policy.to_string()) else {
+            eprintln!("set_link_preview_policy_setting: unknown policy {policy}");
+            return;
+        };
+        update_settings(|s| s.link_previews.policy = policy);
+        self.as_mut().set_link_preview_policy(QString::from(policy.name()));
+    }
+
+    /// Per-category cache usage as a JSON array of
+    /// `{category, bytes, files, budget_bytes}` objects.
+    pub fn cache_usage(&self) -> QString {
+        let usage = serde_json::to_string(&cache_usage()).unwrap_or_else(|_| "[]".to_string());
+        QString::from(usage.as_str())
+    }
+
+    /// Change the size limit of one cache category ("media", "avatars",
+    /// "link_previews"), evicting old entries if it shrank.
+    pub fn set_cache_budget(self: Pin<&mut Self>, category: &QString, megabytes: i32) {
+        let Some(category) = CacheCategory::from_name(&category.to_string()) else {
+            eprintln!("set_cache_budget: unknown category {category}");
+            return;
+        };
+        let megabytes = megabytes.max(1) as u64;
+        spawn(async move {
+            category.set_budget_megabytes(megabytes);
+        });
+    }
+}
+
+/// Log in with the stored pairing credentials, if there are any, and emit
+/// `initialized`.
+async fn restore_session(qt_thread: &CxxQtThread<ffi::AppState>) {
+    let result = load_auth().await;
+
+    match result {
+        Ok(Some(auth)) => {
+            // Pre-populate the shared client
+            let client = GMClient::new(auth);
+            set_client(client).await;
+            let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::AppState>| {
+                qobject.as_mut().set_logged_in(true);
+                qobject.as_mut().set_login_in_progress(false);
+                qobject
+                    .as_mut()
+                    .set_status_message(QString::from("Logged in"));
+                qobject.as_mut().initialized(true);
+            });
+        }
+        Ok(None) => {
+            let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::AppState>| {
+                qobject.as_mut().set_logged_in(false);
+                qobject.as_mut().set_login_in_progress(false);
+                qobject
+                    .as_mut()
+                    .set_status_message(QString::from("Not logged in"));
+                qobject.as_mut().initialized(false);
+            });
+        }
+        Err(error) => {
+            let message = format!("Auth load failed: {error}");
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::AppState>| {
+                qobject.as_mut().set_logged_in(false);
+                qobject.as_mut().set_login_in_progress(false);
+                qobject.as_mut().set_status_message(QString::from(&message));
+                qobject.as_mut().initialized(false);
+                qobject
+                    .as_mut()
+                    .auth_storage_error(&QString::from(message.as_str()));
+            });
+        }
+    }
 }
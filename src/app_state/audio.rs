@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use super::*;
+
+// ── Audio messages ───────────────────────────────────────────────
+
+/// Number of bars in a voice message's waveform.
+const WAVEFORM_BARS: usize = 40;
+/// Rate audio is decoded at for analysis; plenty for a level meter.
+const ANALYSIS_RATE: i64 = 8000;
+
+#[derive(Clone, Default)]
+pub struct AudioSummary {
+    pub duration_ms: i64,
+    /// Peak level of each slice of the recording, 0–100.
+    pub waveform: Vec<u8>,
+}
+
+impl AudioSummary {
+    /// Levels joined with commas, as exposed by the `waveform` role.
+    pub fn waveform_string(&self) -> String {
+        self.waveform
+            .iter()
+            .map(|level| level.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn summaries() -> &'static RwLock<HashMap<PathBuf, AudioSummary>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<PathBuf, AudioSummary>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Duration and waveform of an audio file, decoded with ffmpeg on a blocking
+/// thread. Results are kept for the session since the same note is usually
+/// shown repeatedly.
+pub async fn audio_summary(path: PathBuf) -> Option<AudioSummary> {
+    tokio::task::spawn_blocking(move || decode_summary(&path))
+        .await
+        .ok()
+        .flatten()
+}
+
+fn decode_summary(path: &Path) -> Option<AudioSummary> {
+    if let Some(summary) = summaries().read().unwrap().get(path) {
+        return Some(summary.clone());
+    }
+
+    let output = std::process::Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-ac", "1", "-ar", &ANALYSIS_RATE.to_string(), "-f", "s16le", "-"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let samples: Vec<i16> = output
+        .stdout
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    if samples.is_empty() {
+        return None;
+    }
+
+    let slice_len = samples.len().div_ceil(WAVEFORM_BARS);
+    let peaks: Vec<u32> = samples
+        .chunks(slice_len)
+        .map(|slice| {
+            slice
+                .iter()
+                .map(|s| s.unsigned_abs() as u32)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    // Scale to the loudest slice so quiet recordings still show a shape
+    let loudest = peaks.iter().copied().max().unwrap_or(0).max(1);
+    let summary = AudioSummary {
+        duration_ms: samples.len() as i64 * 1000 / ANALYSIS_RATE,
+        waveform: peaks.iter().map(|p| (p * 100 / loudest) as u8).collect(),
+    };
+
+    summaries()
+        .write()
+        .unwrap()
+        .insert(path.to_path_buf(), summary.clone());
+    Some(summary)
+}
+
+/// Summary of a downloaded audio attachment, if it is one.
+pub async fn media_audio_summary(file_name: &str, mime: &str) -> Option<AudioSummary> {
+    if !mime.starts_with("audio/") {
+        return None;
+    }
+    let file_name = file_name.to_string();
+    tokio::task::spawn_blocking(move || {
+        playback_path(CacheCategory::Media, &file_name).and_then(|path| decode_summary(&path))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// A fresh file for the recorder to write a voice message to. AAC in an
+/// MPEG-4 container is what Google Messages itself sends over RCS.
+pub fn voice_note_path() -> PathBuf {
+    runtime_subdir("voice").join(format!("voice-{}.m4a", uuid::Uuid::new_v4()))
+}
+
+/// Delete a recording made by `voice_note_path` once it has been uploaded.
+/// Files the user picked themselves are left alone.
+pub fn remove_voice_note(path: &Path) {
+    if path.parent() == Some(runtime_subdir("voice").as_path()) {
+        let _ = std::fs::remove_file(path);
+        summaries().write().unwrap().remove(path);
+    }
+}
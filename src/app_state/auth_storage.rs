@@ -0,0 +1,196 @@
+use libgmessages_rs::{auth::AuthData, store::AuthDataStore};
+use secret_service::{EncryptionType, SecretService};
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+// ── Auth storage ─────────────────────────────────────────────────
+
+const APPLICATION_ATTRIBUTE: &str = "org.kourier";
+const AUTH_SECRET_KIND: &str = "auth_data";
+
+/// Where the pairing credentials live.
+pub enum AuthStorage {
+    /// Secret Service (KWallet, GNOME Keyring, KeePassXC…) over D-Bus.
+    SecretService,
+    /// Plaintext JSON file from `AuthDataStore::default_store()`.
+    File(AuthDataStore),
+}
+
+/// The storage backend chosen for this run. Probed on first use; an error
+/// (a keyring that stayed locked) isn't remembered, so the next call asks
+/// to unlock it again.
+pub async fn auth_storage() -> Result<&'static AuthStorage, String> {
+    static INSTANCE: OnceCell<AuthStorage> = OnceCell::const_new();
+    INSTANCE.get_or_try_init(AuthStorage::open).await
+}
+
+/// Pairing credentials from this run's storage.
+pub async fn load_auth() -> Result<Option<AuthData>, String> {
+    auth_storage().await?.load().await
+}
+
+pub async fn save_auth(auth: &AuthData) -> Result<(), String> {
+    auth_storage().await?.save(auth).await
+}
+
+pub async fn delete_auth() -> Result<(), String> {
+    auth_storage().await?.delete().await
+}
+
+impl AuthStorage {
+    /// Prefer the Secret Service, falling back to the file store only when no
+    /// keyring daemon is reachable. A keyring that is there but stays locked
+    /// (the unlock prompt was dismissed) is an error rather than a reason to
+    /// write secrets to disk. An existing auth file is moved into the keyring
+    /// the first time one is available.
+    async fn open() -> Result<AuthStorage, String> {
+        let file_store = AuthDataStore::default_store();
+        if let Err(e) = secret_service_reachable().await {
+            eprintln!("secret service unavailable, storing auth in a file: {e}");
+            return Ok(AuthStorage::File(file_store));
+        }
+        let existing = load_secret(AUTH_SECRET_KIND)
+            .await
+            .map_err(|e| format!("The keyring could not be unlocked: {e}"))?;
+        if existing.is_none() {
+            migrate_file_to_keyring(&file_store).await;
+        }
+        Ok(AuthStorage::SecretService)
+    }
+
+    pub async fn load(&self) -> Result<Option<AuthData>, String> {
+        match self {
+            AuthStorage::SecretService => {
+                let Some(bytes) = load_secret(AUTH_SECRET_KIND).await? else {
+                    return Ok(None);
+                };
+                serde_json::from_slice(&bytes)
+                    .map(Some)
+                    .map_err(|e| e.to_string())
+            }
+            AuthStorage::File(store) => store.load().map_err(|e| e.to_string()),
+        }
+    }
+
+    pub async fn save(&self, auth: &AuthData) -> Result<(), String> {
+        match self {
+            AuthStorage::SecretService => {
+                let bytes = serde_json::to_vec(auth).map_err(|e| e.to_string())?;
+                save_secret(AUTH_SECRET_KIND, "Kourier pairing credentials", &bytes).await
+            }
+            AuthStorage::File(store) => store.save(auth).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub async fn delete(&self) -> Result<(), String> {
+        match self {
+            AuthStorage::SecretService => delete_secret(AUTH_SECRET_KIND).await,
+            AuthStorage::File(store) => store.delete().map_err(|e| e.to_string()),
+        }
+    }
+}
+
+async fn migrate_file_to_keyring(file_store: &AuthDataStore) {
+    let auth = match file_store.load() {
+        Ok(Some(auth)) => auth,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("auth migration: could not read auth file: {e}");
+            return;
+        }
+    };
+    let result = async {
+        let bytes = serde_json::to_vec(&auth).map_err(|e| e.to_string())?;
+        save_secret(AUTH_SECRET_KIND, "Kourier pairing credentials", &bytes).await
+    }
+    .await;
+    match result {
+        Ok(()) => {
+            // Only remove the plaintext copy once the keyring holds it
+            if let Err(e) = file_store.delete() {
+                eprintln!("auth migration: failed to delete auth file: {e}");
+            }
+            eprintln!("auth migration: moved pairing credentials into the secret service");
+        }
+        Err(e) => eprintln!("auth migration failed: {e}"),
+    }
+}
+
+// ── Secret Service helpers ───────────────────────────────────────
+
+/// Whether a Secret Service with a default collection answers on D-Bus,
+/// without unlocking anything.
+async fn secret_service_reachable() -> Result<(), String> {
+    let service = SecretService::connect(EncryptionType::Dh)
+        .await
+        .map_err(|e| e.to_string())?;
+    service
+        .get_default_collection()
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn secret_attributes(kind: &str) -> HashMap<&str, &str> {
+    HashMap::from([("application", APPLICATION_ATTRIBUTE), ("type", kind)])
+}
+
+/// Read a secret stored by Kourier. Err means the Secret Service itself is
+/// unusable; Ok(None) means it works but holds nothing of this kind.
+pub async fn load_secret(kind: &str) -> Result<Option<Vec<u8>>, String> {
+    let service = SecretService::connect(EncryptionType::Dh)
+        .await
+        .map_err(|e| e.to_string())?;
+    let collection = service
+        .get_default_collection()
+        .await
+        .map_err(|e| e.to_string())?;
+    if collection.is_locked().await.map_err(|e| e.to_string())? {
+        collection.unlock().await.map_err(|e| e.to_string())?;
+    }
+    let items = collection
+        .search_items(secret_attributes(kind))
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(item) = items.first() else {
+        return Ok(None);
+    };
+    item.get_secret().await.map(Some).map_err(|e| e.to_string())
+}
+
+/// Store (or replace) a secret in the default collection.
+pub async fn save_secret(kind: &str, label: &str, secret: &[u8]) -> Result<(), String> {
+    let service = SecretService::connect(EncryptionType::Dh)
+        .await
+        .map_err(|e| e.to_string())?;
+    let collection = service
+        .get_default_collection()
+        .await
+        .map_err(|e| e.to_string())?;
+    if collection.is_locked().await.map_err(|e| e.to_string())? {
+        collection.unlock().await.map_err(|e| e.to_string())?;
+    }
+    collection
+        .create_item(label, secret_attributes(kind), secret, true, "application/json")
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+pub async fn delete_secret(kind: &str) -> Result<(), String> {
+    let service = SecretService::connect(EncryptionType::Dh)
+        .await
+        .map_err(|e| e.to_string())?;
+    let collection = service
+        .get_default_collection()
+        .await
+        .map_err(|e| e.to_string())?;
+    let items = collection
+        .search_items(secret_attributes(kind))
+        .await
+        .map_err(|e| e.to_string())?;
+    for item in items {
+        item.delete().await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::app_state::cache::{
+    cache_contains, cache_root, now_secs, provider_uri, write_private_file, CacheCategory,
+};
+use crate::app_state::generated_avatar::forget_generated_avatars;
+use crate::app_state::shared::{download_avatars, get_client, spawn};
+use libgmessages_rs::gmclient::GMClient;
+
+// ── Persistent avatar records ────────────────────────────────────
+
+/// Photos older than this are fetched again in the background.
+const REVALIDATE_AFTER_SECS: u64 = 7 * 24 * 60 * 60;
+/// Contacts without a photo are asked about again after this long.
+const NO_PHOTO_TTL_SECS: u64 = 24 * 60 * 60;
+/// How often the background revalidation looks for stale photos.
+const REVALIDATE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct AvatarRecord {
+    /// File in the avatar cache, or None when the contact has no photo.
+    file_name: Option<String>,
+    /// Seconds since the epoch.
+    fetched_at: u64,
+}
+
+pub enum AvatarLookup {
+    /// Cached photo, as an image provider URL.
+    Hit(String),
+    /// Recently confirmed to have no photo.
+    NoPhoto,
+    Miss,
+}
+
+fn records_path() -> PathBuf {
+    cache_root().join("avatars.json")
+}
+
+/// Avatar identifier → cache file, persisted as `avatars.json` next to the caches.
+fn records() -> &'static Mutex<HashMap<String, AvatarRecord>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<String, AvatarRecord>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let loaded = std::fs::read(records_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Mutex::new(loaded)
+    })
+}
+
+fn save_records(records: &HashMap<String, AvatarRecord>) {
+    match serde_json::to_vec(records) {
+        Ok(bytes) => {
+            if let Err(e) = write_private_file(&records_path(), &bytes) {
+                eprintln!("avatar cache: failed to write records: {e}");
+            }
+        }
+        Err(e) => eprintln!("avatar cache: failed to serialise records: {e}"),
+    }
+}
+
+/// Image provider URL of a cached photo. The fetch time is part of it because
+/// QML caches images by URL and would keep showing a replaced photo.
+pub fn avatar_uri(file_name: &str, fetched_at: u64) -> String {
+    format!(
+        "{}?v={fetched_at}",
+        provider_uri(CacheCategory::Avatars, file_name)
+    )
+}
+
+pub fn lookup_avatar(identifier: &str) -> AvatarLookup {
+    let records = records().lock().unwrap();
+    let Some(record) = records.get(identifier) else {
+        return AvatarLookup::Miss;
+    };
+    match &record.file_name {
+        // The file may have been evicted since
+        Some(file_name) if cache_contains(CacheCategory::Avatars, file_name) => {
+            AvatarLookup::Hit(avatar_uri(file_name, record.fetched_at))
+        }
+        Some(_) => AvatarLookup::Miss,
+        None if now_secs().saturating_sub(record.fetched_at) < NO_PHOTO_TTL_SECS => {
+            AvatarLookup::NoPhoto
+        }
+        None => AvatarLookup::Miss,
+    }
+}
+
+/// Cache file holding a contact's photo, if one is cached.
+pub fn cached_avatar_file(identifier: &str) -> Option<String> {
+    let records = records().lock().unwrap();
+    let file_name = records.get(identifier)?.file_name.clone()?;
+    cache_contains(CacheCategory::Avatars, &file_name).then_some(file_name)
+}
+
+/// Record fetch results: a file name for a photo, None for a contact without
+/// one. `fetched_at` is the time used in the photos' `avatar_uri`s.
+pub fn record_avatars(results: Vec<(String, Option<String>)>, fetched_at: u64) {
+    if results.is_empty() {
+        return;
+    }
+    let mut records = records().lock().unwrap();
+    for (identifier, file_name) in results {
+        records.insert(
+            identifier,
+            AvatarRecord {
+                file_name,
+                fetched_at,
+            },
+        );
+    }
+    save_records(&records);
+}
+
+/// URLs for every cached photo, used to seed the in-memory avatar map at startup.
+pub fn cached_avatar_urls() -> HashMap<String, String> {
+    records()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|(identifier, record)| {
+            let file_name = record.file_name.as_ref()?;
+            cache_contains(CacheCategory::Avatars, file_name)
+                .then(|| (identifier.clone(), avatar_uri(file_name, record.fetched_at)))
+        })
+        .collect()
+}
+
+pub fn clear_avatar_records() {
+    let mut records = records().lock().unwrap();
+    records.clear();
+    let _ = std::fs::remove_file(records_path());
+    forget_generated_avatars();
+}
+
+fn stale_avatar_identifiers() -> Vec<String> {
+    let now = now_secs();
+    records()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, record)| {
+            record.file_name.is_some()
+                && now.saturating_sub(record.fetched_at) >= REVALIDATE_AFTER_SECS
+        })
+        .map(|(identifier, _)| identifier.clone())
+        .collect()
+}
+
+/// Periodically refetch photos older than a week so changed contact photos
+/// show up without clearing the cache. Started once per process.
+pub fn start_avatar_revalidation() {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    spawn(async {
+        let mut interval = tokio::time::interval(REVALIDATE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            // The first tick is immediate; the check itself waits for a login
+            interval.tick().await;
+            let client = wait_for_client().await;
+            let stale = stale_avatar_identifiers();
+            if stale.is_empty() {
+                continue;
+            }
+            eprintln!("avatar cache: revalidating {} avatars", stale.len());
+            for chunk in stale.chunks(50) {
+                download_avatars(&client, chunk.to_vec()).await;
+            }
+        }
+    });
+}
+
+/// The client once one is logged in, checking every half minute.
+async fn wait_for_client() -> GMClient {
+    loop {
+        if let Some(client) = get_client().await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    }
+}
@@ -0,0 +1,742 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::app_state::auth_storage::{load_secret, save_secret};
+use crate::app_state::settings::{cache_budgets, encrypted_cache, update_settings};
+use crate::app_state::shared::{get_client, spawn};
+use crate::app_state::utils::mime_to_extension;
+
+// ── On-disk caches ───────────────────────────────────────────────
+
+/// The kinds of files Kourier caches on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheCategory {
+    Media,
+    Avatars,
+    LinkPreviews,
+}
+
+impl CacheCategory {
+    pub const ALL: [CacheCategory; 3] = [
+        CacheCategory::Media,
+        CacheCategory::Avatars,
+        CacheCategory::LinkPreviews,
+    ];
+
+    /// Directory name, also used as the first path segment of image provider ids.
+    pub fn name(self) -> &'static str {
+        match self {
+            CacheCategory::Media => "media",
+            CacheCategory::Avatars => "avatars",
+            CacheCategory::LinkPreviews => "link_previews",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CacheCategory> {
+        CacheCategory::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// Maximum total size of this category before old entries are evicted.
+    pub fn budget_bytes(self) -> u64 {
+        let budgets = cache_budgets();
+        let megabytes = match self {
+            CacheCategory::Media => budgets.media,
+            CacheCategory::Avatars => budgets.avatars,
+            CacheCategory::LinkPreviews => budgets.link_previews,
+        };
+        megabytes * 1024 * 1024
+    }
+
+    pub fn set_budget_megabytes(self, megabytes: u64) {
+        update_settings(|s| match self {
+            CacheCategory::Media => s.cache_budgets.media = megabytes,
+            CacheCategory::Avatars => s.cache_budgets.avatars = megabytes,
+            CacheCategory::LinkPreviews => s.cache_budgets.link_previews = megabytes,
+        });
+        enforce_cache_budgets();
+    }
+}
+
+/// `~/.cache/kourier`. It and its subdirectories are created with 0700
+/// permissions the first time it is asked for.
+pub fn cache_root() -> PathBuf {
+    static ROOT: OnceLock<PathBuf> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let root = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("kourier");
+        create_cache_dirs(&root);
+        root
+    })
+    .clone()
+}
+
+fn create_cache_dirs(root: &Path) {
+    ensure_private_dir(root);
+    for category in CacheCategory::ALL {
+        ensure_private_dir(&root.join(category.name()));
+    }
+    ensure_private_dir(&root.join("state"));
+}
+
+pub fn cache_dir(category: CacheCategory) -> PathBuf {
+    cache_root().join(category.name())
+}
+
+/// Decrypted copies of encrypted videos for the media player, kept on the
+/// per-user runtime tmpfs rather than next to the cache.
+fn playback_dir() -> PathBuf {
+    runtime_subdir("playback")
+}
+
+/// A 0700 directory under `$XDG_RUNTIME_DIR/kourier` for files that must not
+/// outlive the session.
+pub fn runtime_subdir(name: &str) -> PathBuf {
+    let dir = dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("kourier")
+        .join(name);
+    ensure_private_dir(&dir);
+    dir
+}
+
+fn ensure_private_dir(path: &Path) {
+    if let Err(e) = std::fs::create_dir_all(path) {
+        eprintln!("cache: failed to create {}: {e}", path.display());
+        return;
+    }
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700));
+}
+
+/// Turn a media/avatar identifier into something safe to use as a file name.
+pub fn safe_cache_name(id: &str) -> String {
+    id.replace("/", "_")
+        .replace("+", "_")
+        .replace("=", "")
+        .replace("-", "_")
+}
+
+fn encryption_enabled() -> bool {
+    encrypted_cache()
+}
+
+/// On-disk location of a cache entry. Encrypted entries get an `.enc` suffix so
+/// switching modes never mistakes ciphertext for a playable file.
+pub fn cache_path(category: CacheCategory, file_name: &str) -> PathBuf {
+    let dir = cache_dir(category);
+    if encryption_enabled() {
+        dir.join(format!("{file_name}.enc"))
+    } else {
+        dir.join(file_name)
+    }
+}
+
+/// Whether an entry is cached, answered from the index without touching the disk.
+pub fn cache_contains(category: CacheCategory, file_name: &str) -> bool {
+    cache_index().entries(category).contains_key(file_name)
+}
+
+/// Store bytes in the cache and return the URI QML should load them from.
+pub fn write_cache_file(
+    category: CacheCategory,
+    file_name: &str,
+    data: &[u8],
+    mime: &str,
+) -> Result<String, String> {
+    let path = cache_path(category, file_name);
+    let contents = if encryption_enabled() {
+        encrypt(data)?
+    } else {
+        data.to_vec()
+    };
+    write_private_file(&path, &contents)?;
+    {
+        let mut index = cache_index();
+        index.record(category, file_name, contents.len() as u64);
+        index.evict(category, Some(file_name));
+    }
+    Ok(cache_uri(category, file_name, mime))
+}
+
+/// Read a cache entry back, decrypting it if needed. Entries that no longer
+/// decrypt (e.g. the key was lost) are removed and treated as a miss.
+pub fn read_cache_file(category: CacheCategory, file_name: &str) -> Option<Vec<u8>> {
+    if encryption_enabled() && !cache_key_loaded() {
+        // A miss for now; the entry is still readable once the key is in
+        return None;
+    }
+    let path = cache_path(category, file_name);
+    let Ok(contents) = std::fs::read(&path) else {
+        cache_index().forget(category, file_name);
+        return None;
+    };
+    cache_index().touch(category, file_name);
+    if !encryption_enabled() {
+        return Some(contents);
+    }
+    match decrypt(&contents) {
+        Ok(plain) => Some(plain),
+        Err(e) => {
+            eprintln!("cache: dropping undecryptable {}: {e}", path.display());
+            let _ = std::fs::remove_file(&path);
+            cache_index().forget(category, file_name);
+            None
+        }
+    }
+}
+
+/// The URI for an existing cache entry.
+///
+/// Images go through the `image://kourier/<category>/<name>` provider. Other
+/// media (video) needs a real file for the media player: the cache file
+/// itself, or a decrypted copy in the runtime directory when encrypted.
+pub fn cache_uri(category: CacheCategory, file_name: &str, mime: &str) -> String {
+    if mime.starts_with("image/") || category != CacheCategory::Media {
+        return provider_uri(category, file_name);
+    }
+    match playback_path(category, file_name) {
+        Some(path) => format!("file://{}", path.to_string_lossy()),
+        None => String::new(),
+    }
+}
+
+pub fn provider_uri(category: CacheCategory, file_name: &str) -> String {
+    format!("image://kourier/{}/{}", category.name(), file_name)
+}
+
+/// Split an image provider id (`<category>/<name>[?v=…]`) into its parts,
+/// rejecting anything that could escape the cache directory.
+pub fn parse_provider_id(id: &str) -> Option<(CacheCategory, &str)> {
+    // A query only tells QML's image cache apart from older versions
+    let id = id.split_once('?').map_or(id, |(id, _)| id);
+    let (category, file_name) = id.split_once('/')?;
+    let category = CacheCategory::from_name(category)?;
+    if file_name.is_empty() || file_name.contains('/') || file_name.contains("..") {
+        return None;
+    }
+    Some((category, file_name))
+}
+
+/// The bytes behind an `image://kourier/...` URI, downloading media that
+/// isn't cached yet the way the image provider would.
+pub async fn fetch_provider_uri(uri: &str) -> Option<Vec<u8>> {
+    let id = uri.strip_prefix("image://kourier/")?;
+    let (category, file_name) = parse_provider_id(id)?;
+    if category == CacheCategory::Media {
+        return fetch_media(file_name).await;
+    }
+    read_cache_file(category, file_name)
+}
+
+/// A plaintext file for tools that need a real path (video player, ffmpeg).
+/// With encryption off this is the cache file itself.
+pub fn playback_path(category: CacheCategory, file_name: &str) -> Option<PathBuf> {
+    if !encryption_enabled() {
+        let path = cache_dir(category).join(file_name);
+        return path.exists().then_some(path);
+    }
+    let path = playback_dir().join(file_name);
+    if path.exists() {
+        return Some(path);
+    }
+    let data = read_cache_file(category, file_name)?;
+    write_private_file(&path, &data).ok()?;
+    Some(path)
+}
+
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.write_all(contents).map_err(|e| e.to_string())
+}
+
+// ── On-demand media ──────────────────────────────────────────────
+
+/// Where to download a media cache entry from the first time it is requested.
+#[derive(Clone)]
+pub struct MediaSource {
+    pub media_id: String,
+    pub decryption_key: Vec<u8>,
+    pub mime: String,
+}
+
+fn media_sources() -> &'static RwLock<HashMap<String, MediaSource>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<String, MediaSource>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Cache file name for an attachment, falling back to the message id when the
+/// media has no id of its own.
+pub fn media_cache_name(media_id: &str, message_id: &str, mime: &str) -> String {
+    let safe_id = safe_cache_name(media_id);
+    let safe_id = if safe_id.is_empty() {
+        message_id.replace("-", "_")
+    } else {
+        safe_id
+    };
+    format!("{}.{}", safe_id, mime_to_extension(mime))
+}
+
+/// Remember how to fetch an attachment so the image provider can download it lazily.
+pub fn register_media_source(file_name: &str, source: MediaSource) {
+    media_sources()
+        .write()
+        .unwrap()
+        .insert(file_name.to_string(), source);
+}
+
+/// Return a media cache entry, downloading and caching it on a miss.
+pub async fn fetch_media(file_name: &str) -> Option<Vec<u8>> {
+    if let Some(data) = read_cache_file(CacheCategory::Media, file_name) {
+        return Some(data);
+    }
+    let (data, source) = download_source(file_name).await?;
+    if let Err(e) = write_cache_file(CacheCategory::Media, file_name, &data, &source.mime) {
+        eprintln!("fetch_media: failed to cache {file_name}: {e}");
+    }
+    Some(data)
+}
+
+/// Like `fetch_media`, but a download is not added to the cache, so reading
+/// a whole conversation's media doesn't evict everything else.
+pub async fn fetch_media_uncached(file_name: &str) -> Option<Vec<u8>> {
+    if let Some(data) = read_cache_file(CacheCategory::Media, file_name) {
+        return Some(data);
+    }
+    download_source(file_name).await.map(|(data, _)| data)
+}
+
+async fn download_source(file_name: &str) -> Option<(Vec<u8>, MediaSource)> {
+    let source = media_sources().read().unwrap().get(file_name).cloned()?;
+    let client = get_client().await?;
+    match client
+        .download_media(&source.media_id, &source.decryption_key)
+        .await
+    {
+        Ok(data) => Some((data, source)),
+        Err(e) => {
+            eprintln!("fetch_media: download failed for {file_name}: {e}");
+            None
+        }
+    }
+}
+
+// ── Single-file caches ───────────────────────────────────────────
+
+/// Directory for small whole-file caches such as the contact list.
+fn blob_dir() -> PathBuf {
+    cache_root().join("state")
+}
+
+fn blob_path(name: &str) -> PathBuf {
+    if encryption_enabled() {
+        blob_dir().join(format!("{name}.enc"))
+    } else {
+        blob_dir().join(name)
+    }
+}
+
+/// Store a small cache file, encrypted when cache encryption is on. These are
+/// not subject to the per-category budgets.
+pub fn write_cache_blob(name: &str, data: &[u8]) -> Result<(), String> {
+    let contents = if encryption_enabled() {
+        encrypt(data)?
+    } else {
+        data.to_vec()
+    };
+    write_private_file(&blob_path(name), &contents)
+}
+
+pub fn read_cache_blob(name: &str) -> Option<Vec<u8>> {
+    let contents = std::fs::read(blob_path(name)).ok()?;
+    if !encryption_enabled() {
+        return Some(contents);
+    }
+    decrypt(&contents).ok()
+}
+
+/// Make sure a media entry is on disk, downloading it if the index has no record of it.
+pub async fn ensure_media_cached(file_name: &str) -> bool {
+    cache_contains(CacheCategory::Media, file_name) || fetch_media(file_name).await.is_some()
+}
+
+/// Remove every cache category plus decrypted playback copies.
+pub fn clear_all_caches() {
+    let mut index = cache_index();
+    for category in CacheCategory::ALL {
+        let _ = std::fs::remove_dir_all(cache_root().join(category.name()));
+    }
+    let _ = std::fs::remove_dir_all(playback_dir());
+    let _ = std::fs::remove_dir_all(cache_root().join("state"));
+    create_cache_dirs(&cache_root());
+    index.entries.clear();
+    index.dirty = true;
+    drop(index);
+    flush_cache_index();
+}
+
+// ── Cache index and eviction ─────────────────────────────────────
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct IndexEntry {
+    size: u64,
+    /// Seconds since the epoch.
+    last_access: u64,
+}
+
+/// Size and last access of every cache entry, persisted next to the caches as
+/// `index.json` so hits and evictions don't need to stat the directories.
+#[derive(Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: HashMap<String, HashMap<String, IndexEntry>>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// How long changes to the index are collected before it is written out.
+const INDEX_FLUSH_DELAY: Duration = Duration::from_secs(10);
+
+fn cache_index() -> IndexGuard {
+    static INSTANCE: OnceLock<Mutex<CacheIndex>> = OnceLock::new();
+    IndexGuard(Some(
+        INSTANCE
+            .get_or_init(|| Mutex::new(CacheIndex::load()))
+            .lock()
+            .unwrap(),
+    ))
+}
+
+/// The locked index. Unsaved changes schedule a flush once the lock is let
+/// go, since spawning the flush can initialise the runtime and with it the
+/// avatar cache, which reads the index.
+struct IndexGuard(Option<MutexGuard<'static, CacheIndex>>);
+
+impl Deref for IndexGuard {
+    type Target = CacheIndex;
+
+    fn deref(&self) -> &CacheIndex {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for IndexGuard {
+    fn deref_mut(&mut self) -> &mut CacheIndex {
+        self.0.as_mut().unwrap()
+    }
+}
+
+impl Drop for IndexGuard {
+    fn drop(&mut self) {
+        let dirty = self.0.take().is_some_and(|index| index.dirty);
+        if dirty {
+            schedule_index_flush();
+        }
+    }
+}
+
+fn index_path() -> PathBuf {
+    cache_root().join("index.json")
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl CacheIndex {
+    fn load() -> CacheIndex {
+        let loaded = std::fs::read(index_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheIndex>(&bytes).ok());
+        match loaded {
+            Some(index) => index,
+            None => {
+                let mut index = CacheIndex::rebuild();
+                index.dirty = true;
+                if let Some(bytes) = index.take_snapshot() {
+                    write_index(&bytes);
+                }
+                index
+            }
+        }
+    }
+
+    /// Recreate the index from what is on disk, using mtimes as access times.
+    fn rebuild() -> CacheIndex {
+        let encrypted = encryption_enabled();
+        let mut index = CacheIndex::default();
+        for category in CacheCategory::ALL {
+            let Ok(dir) = std::fs::read_dir(cache_dir(category)) else {
+                continue;
+            };
+            for entry in dir.filter_map(|e| e.ok()) {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                // Thumbnails are entries of their own and count too
+                let logical = match (encrypted, name.strip_suffix(".enc")) {
+                    (true, Some(logical)) => logical.to_string(),
+                    (false, None) => name,
+                    _ => continue,
+                };
+                let last_access = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                index.entries(category).insert(
+                    logical,
+                    IndexEntry {
+                        size: metadata.len(),
+                        last_access,
+                    },
+                );
+            }
+        }
+        index
+    }
+
+    /// The index as JSON if it changed since the last snapshot, which the
+    /// caller writes with `write_index` after letting go of the lock.
+    fn take_snapshot(&mut self) -> Option<Vec<u8>> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        serde_json::to_vec(self)
+            .map_err(|e| eprintln!("cache: failed to serialise index: {e}"))
+            .ok()
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    fn entries(&mut self, category: CacheCategory) -> &mut HashMap<String, IndexEntry> {
+        self.entries.entry(category.name().to_string()).or_default()
+    }
+
+    fn record(&mut self, category: CacheCategory, file_name: &str, size: u64) {
+        self.entries(category).insert(
+            file_name.to_string(),
+            IndexEntry {
+                size,
+                last_access: now_secs(),
+            },
+        );
+        self.mark_dirty();
+    }
+
+    fn touch(&mut self, category: CacheCategory, file_name: &str) {
+        if let Some(entry) = self.entries(category).get_mut(file_name) {
+            entry.last_access = now_secs();
+            self.mark_dirty();
+        }
+    }
+
+    fn forget(&mut self, category: CacheCategory, file_name: &str) {
+        if self.entries(category).remove(file_name).is_some() {
+            self.mark_dirty();
+        }
+    }
+
+    /// Drop least recently used entries until the category fits its budget.
+    /// `keep` protects an entry that was just written.
+    fn evict(&mut self, category: CacheCategory, keep: Option<&str>) {
+        let budget = category.budget_bytes();
+        let entries = self.entries(category);
+        let mut total: u64 = entries.values().map(|e| e.size).sum();
+        if total <= budget {
+            return;
+        }
+        let mut by_age: Vec<(String, IndexEntry)> = entries
+            .iter()
+            .filter(|(name, _)| Some(name.as_str()) != keep)
+            .map(|(name, entry)| (name.clone(), *entry))
+            .collect();
+        by_age.sort_by_key(|(_, entry)| entry.last_access);
+        for (name, entry) in by_age {
+            if total <= budget {
+                break;
+            }
+            remove_entry_files(category, &name);
+            entries.remove(&name);
+            total = total.saturating_sub(entry.size);
+        }
+        self.mark_dirty();
+    }
+}
+
+/// Delete a cache entry along with anything derived from it (playback copies,
+/// video thumbnails).
+fn remove_entry_files(category: CacheCategory, file_name: &str) {
+    let plain = cache_dir(category).join(file_name);
+    let playback = playback_dir().join(file_name);
+    for path in [
+        cache_path(category, file_name),
+        plain.with_extension("thumb.jpg"),
+        playback.with_extension("thumb.jpg"),
+        playback,
+    ] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Evict every category down to its budget and persist the index.
+pub fn enforce_cache_budgets() {
+    let mut index = cache_index();
+    for category in CacheCategory::ALL {
+        index.evict(category, None);
+    }
+    drop(index);
+    flush_cache_index();
+}
+
+fn write_index(bytes: &[u8]) {
+    if let Err(e) = write_private_file(&index_path(), bytes) {
+        eprintln!("cache: failed to write index: {e}");
+    }
+}
+
+/// Write the index out now if it has unsaved changes. Called on exit.
+pub fn flush_cache_index() {
+    let snapshot = cache_index().take_snapshot();
+    if let Some(bytes) = snapshot {
+        write_index(&bytes);
+    }
+}
+
+/// Write the index out a little later, so a burst of cache writes and
+/// reads is saved once rather than on every change.
+fn schedule_index_flush() {
+    static SCHEDULED: AtomicBool = AtomicBool::new(false);
+    if SCHEDULED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    spawn(async {
+        tokio::time::sleep(INDEX_FLUSH_DELAY).await;
+        SCHEDULED.store(false, Ordering::Release);
+        flush_cache_index();
+    });
+}
+
+/// Disk usage of one cache category.
+#[derive(Serialize)]
+pub struct CacheUsage {
+    pub category: &'static str,
+    pub bytes: u64,
+    pub files: usize,
+    pub budget_bytes: u64,
+}
+
+pub fn cache_usage() -> Vec<CacheUsage> {
+    let mut index = cache_index();
+    CacheCategory::ALL
+        .into_iter()
+        .map(|category| {
+            let entries = index.entries(category);
+            CacheUsage {
+                category: category.name(),
+                bytes: entries.values().map(|e| e.size).sum(),
+                files: entries.len(),
+                budget_bytes: category.budget_bytes(),
+            }
+        })
+        .collect()
+}
+
+/// Older builds cached everything in world-readable directories under /tmp.
+pub fn remove_legacy_tmp_caches() {
+    for name in ["kourier_media", "kourier_avatars", "kourier_link_previews"] {
+        let _ = std::fs::remove_dir_all(std::env::temp_dir().join(name));
+    }
+}
+
+// ── Cache encryption ─────────────────────────────────────────────
+
+const CACHE_KEY_SECRET_KIND: &str = "cache_key";
+
+static CACHE_KEY: OnceLock<Key<Aes256Gcm>> = OnceLock::new();
+
+/// Load the cache key from the keyring, creating one on first use. Without a
+/// Secret Service the key only lives in memory, so the encrypted cache is
+/// effectively discarded on every restart. Does nothing while encryption is
+/// off, so the keyring is left alone.
+pub async fn init_cache_key() {
+    if CACHE_KEY.get().is_some() || !encryption_enabled() {
+        return;
+    }
+    let key = match load_secret(CACHE_KEY_SECRET_KIND).await {
+        Ok(Some(bytes)) if bytes.len() == 32 => *Key::<Aes256Gcm>::from_slice(&bytes),
+        Ok(_) => {
+            let key = Aes256Gcm::generate_key(OsRng);
+            if let Err(e) = save_secret(CACHE_KEY_SECRET_KIND, "Kourier cache key", &key).await {
+                eprintln!("cache: failed to store key in secret service: {e}");
+            }
+            key
+        }
+        Err(e) => {
+            eprintln!("cache: secret service unavailable, using an ephemeral key: {e}");
+            Aes256Gcm::generate_key(OsRng)
+        }
+    };
+    let _ = CACHE_KEY.set(key);
+}
+
+fn cache_key_loaded() -> bool {
+    CACHE_KEY.get().is_some()
+}
+
+/// The cache cipher. Until `init_cache_key` has finished there is none, and
+/// encrypted entries can be neither read nor written: making up a key here
+/// would lock out everything cached in earlier runs.
+fn cipher() -> Result<Aes256Gcm, String> {
+    CACHE_KEY
+        .get()
+        .map(Aes256Gcm::new)
+        .ok_or_else(|| "cache key not loaded yet".to_string())
+}
+
+/// nonce (12 bytes) || AES-256-GCM ciphertext
+fn encrypt(plain: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, plain)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::with_capacity(nonce.len() + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(contents: &[u8]) -> Result<Vec<u8>, String> {
+    if contents.len() < 12 {
+        return Err("truncated cache file".to_string());
+    }
+    let (nonce, ciphertext) = contents.split_at(12);
+    cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| e.to_string())
+}
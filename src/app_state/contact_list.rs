@@ -0,0 +1,390 @@
+use crate::ffi;
+use core::pin::Pin;
+use cxx_qt::{CxxQtThread, CxxQtType, Threading};
+use cxx_qt_lib::QString;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::ffi::QHash_i32_QByteArray;
+use crate::ffi::QModelIndex;
+use crate::ffi::QVariant;
+
+use super::*;
+use crate::app_state::shared::fetch_avatars_async;
+
+// ── ContactList ──────────────────────────────────────────────────
+
+const CONTACTS_CACHE_NAME: &str = "contacts.json";
+
+/// How many more contacts to ask for each time the phone fills the request.
+const CONTACTS_PAGE: i32 = 350;
+/// Stop growing the request here so a misbehaving phone can't loop forever.
+const MAX_CONTACTS: i32 = 20_000;
+
+/// A contact from the phone's address book. Contacts with several numbers
+/// arrive as one entry per number and are merged by contact id.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Contact {
+    pub contact_id: String,
+    pub participant_id: String,
+    pub name: String,
+    pub numbers: Vec<String>,
+}
+
+impl Contact {
+    pub fn avatar_identifier(&self) -> &str {
+        if self.contact_id.is_empty() {
+            &self.participant_id
+        } else {
+            &self.contact_id
+        }
+    }
+}
+
+/// The last known contact list, shared so other models can resolve names.
+fn contacts() -> &'static RwLock<Vec<Contact>> {
+    static INSTANCE: OnceLock<RwLock<Vec<Contact>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let cached = read_cache_blob(CONTACTS_CACHE_NAME)
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        RwLock::new(cached)
+    })
+}
+
+/// The address book name for a phone number, if the contact is known.
+pub fn contact_name_for_number(number: &str) -> Option<String> {
+    let wanted = normalize_number(number);
+    if wanted.is_empty() {
+        return None;
+    }
+    contacts()
+        .read()
+        .unwrap()
+        .iter()
+        .find(|c| c.numbers.iter().any(|n| numbers_match(n, &wanted)))
+        .map(|c| c.name.clone())
+        .filter(|name| !name.is_empty())
+}
+
+#[derive(Clone)]
+pub struct ContactItem {
+    pub contact: Contact,
+    pub avatar_url: QString,
+}
+
+pub struct ContactListRust {
+    all_items: Vec<ContactItem>,
+    filtered_items: Vec<ContactItem>,
+    filter_text: String,
+    pub loading: bool,
+}
+
+impl Default for ContactListRust {
+    fn default() -> Self {
+        Self {
+            all_items: Vec::new(),
+            filtered_items: Vec::new(),
+            filter_text: String::new(),
+            loading: false,
+        }
+    }
+}
+
+impl crate::ffi::ContactList {
+    pub fn row_count(&self, _parent: &QModelIndex) -> i32 {
+        self.filtered_items.len() as i32
+    }
+
+    pub fn data(&self, index: &QModelIndex, role: i32) -> QVariant {
+        let row = index.row() as usize;
+        if row >= self.filtered_items.len() {
+            return QVariant::default();
+        }
+
+        let item = &self.filtered_items[row];
+        match role {
+            0 => QVariant::from(&QString::from(item.contact.name.as_str())),
+            1 => QVariant::from(&QString::from(item.contact.numbers.join(", ").as_str())),
+            2 => QVariant::from(&QString::from(
+                item.contact
+                    .numbers
+                    .first()
+                    .map(String::as_str)
+                    .unwrap_or(""),
+            )),
+            3 => QVariant::from(&item.avatar_url),
+            4 => QVariant::from(&QString::from(item.contact.contact_id.as_str())),
+            _ => QVariant::default(),
+        }
+    }
+
+    pub fn role_names(&self) -> QHash_i32_QByteArray {
+        let mut roles = QHash_i32_QByteArray::default();
+        roles.insert(0, "name".into());
+        roles.insert(1, "numbers".into());
+        roles.insert(2, "primary_number".into());
+        roles.insert(3, "avatar_url".into());
+        roles.insert(4, "contact_id".into());
+        roles
+    }
+
+    /// Show the cached contacts straight away, then refresh them from the phone.
+    pub fn load(mut self: Pin<&mut Self>) {
+        let cached = contacts().read().unwrap().clone();
+        if !cached.is_empty() && self.rust().all_items.is_empty() {
+            let items = contacts_to_items(cached);
+            self.as_mut().set_items(items);
+        }
+
+        self.as_mut().set_loading(true);
+        let qt_thread: CxxQtThread<ffi::ContactList> = self.qt_thread();
+
+        spawn(async move {
+            let result: Result<Vec<Contact>, String> = async {
+                let client = ensure_client().await?;
+                let handler = make_handler(&client).await?;
+                // ListContacts has no cursor, only a count (the web client
+                // asks for 350). Ask again for more until the phone sends
+                // back fewer than requested.
+                let mut limit = CONTACTS_PAGE;
+                loop {
+                    let request = libgmessages_rs::proto::client::ListContactsRequest {
+                        i1: 1,
+                        i2: limit,
+                        i3: 50,
+                    };
+                    let response: libgmessages_rs::proto::client::ListContactsResponse = handler
+                        .send_request(
+                            libgmessages_rs::proto::rpc::ActionType::ListContacts,
+                            libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                            &request,
+                        )
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    if response.contacts.len() < limit as usize || limit >= MAX_CONTACTS {
+                        break Ok(merge_contacts(response.contacts));
+                    }
+                    limit = (limit + CONTACTS_PAGE).min(MAX_CONTACTS);
+                }
+            }
+            .await;
+
+            let fetched = match result {
+                Ok(fetched) => fetched,
+                Err(error) => {
+                    eprintln!("contact list load failed: {error}");
+                    let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::ContactList>| {
+                        qobject.as_mut().set_loading(false);
+                    });
+                    return;
+                }
+            };
+
+            match serde_json::to_vec(&fetched) {
+                Ok(bytes) => {
+                    if let Err(e) = write_cache_blob(CONTACTS_CACHE_NAME, &bytes) {
+                        eprintln!("contact list: failed to cache contacts: {e}");
+                    }
+                }
+                Err(e) => eprintln!("contact list: failed to serialise contacts: {e}"),
+            }
+            *contacts().write().unwrap() = fetched.clone();
+
+            let items = contacts_to_items(fetched);
+            let identifiers: Vec<String> = items
+                .iter()
+                .map(|item| item.contact.avatar_identifier().to_string())
+                .filter(|id| !id.is_empty())
+                .collect();
+
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::ContactList>| {
+                qobject.as_mut().set_items(items);
+                qobject.as_mut().set_loading(false);
+                qobject.as_mut().loaded();
+            });
+
+            // Fetch photos for contacts that don't have one cached yet
+            let missing: Vec<String> = {
+                let cache = shared().avatars.read().await;
+                identifiers
+                    .into_iter()
+                    .filter(|id| !cache.contains_key(id))
+                    .collect()
+            };
+            let Some(client) = get_client().await else {
+                return;
+            };
+            for chunk in missing.chunks(50) {
+                let new_avatars = fetch_avatars_async(client.clone(), chunk.to_vec()).await;
+                if new_avatars.is_empty() {
+                    continue;
+                }
+                let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::ContactList>| {
+                    qobject.as_mut().apply_avatars(&new_avatars);
+                });
+            }
+        });
+    }
+
+    pub fn apply_filter(mut self: Pin<&mut Self>, filter: &QString) {
+        let filter_text = filter.to_string();
+        self.as_mut().begin_reset_model();
+        let mut rust = self.as_mut().rust_mut();
+        rust.filter_text = filter_text;
+        rust.filtered_items = search_contacts(&rust.all_items, &rust.filter_text);
+        self.as_mut().end_reset_model();
+    }
+
+    /// Comma-separated numbers of the contact at `row`, ready for
+    /// `ConversationList::open_for_numbers`.
+    pub fn numbers_at(&self, row: i32) -> QString {
+        let index = row.max(0) as usize;
+        match self.filtered_items.get(index) {
+            Some(item) => QString::from(item.contact.numbers.join(",").as_str()),
+            None => QString::from(""),
+        }
+    }
+
+    pub fn name_for_number(&self, number: &QString) -> QString {
+        QString::from(
+            contact_name_for_number(&number.to_string())
+                .unwrap_or_default()
+                .as_str(),
+        )
+    }
+
+    fn set_items(mut self: Pin<&mut Self>, items: Vec<ContactItem>) {
+        self.as_mut().begin_reset_model();
+        let mut rust = self.as_mut().rust_mut();
+        rust.all_items = items;
+        rust.filtered_items = search_contacts(&rust.all_items, &rust.filter_text);
+        self.as_mut().end_reset_model();
+    }
+
+    fn apply_avatars(mut self: Pin<&mut Self>, new_avatars: &HashMap<String, String>) {
+        let mut rust = self.as_mut().rust_mut();
+        for item in &mut rust.all_items {
+            if let Some(url) = new_avatars.get(item.contact.avatar_identifier()) {
+                item.avatar_url = QString::from(url.as_str());
+            }
+        }
+        let mut changed = Vec::new();
+        for (row, item) in rust.filtered_items.iter_mut().enumerate() {
+            if let Some(url) = new_avatars.get(item.contact.avatar_identifier()) {
+                item.avatar_url = QString::from(url.as_str());
+                changed.push(row as i32);
+            }
+        }
+        drop(rust);
+        for row in changed {
+            let model_index = self.as_ref().index(row, 0, &QModelIndex::default());
+            self.as_mut().data_changed(&model_index, &model_index);
+        }
+    }
+}
+
+/// Merge the per-number entries from ListContacts into one contact each,
+/// sorted by name.
+fn merge_contacts(raw: Vec<libgmessages_rs::proto::conversations::Contact>) -> Vec<Contact> {
+    let mut merged: Vec<Contact> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for entry in raw {
+        let number = entry
+            .number
+            .as_ref()
+            .map(|n| n.number.clone())
+            .unwrap_or_default();
+        let key = if entry.contact_id.is_empty() {
+            entry.participant_id.clone()
+        } else {
+            entry.contact_id.clone()
+        };
+        let pos = *by_key.entry(key).or_insert_with(|| {
+            merged.push(Contact {
+                contact_id: entry.contact_id.clone(),
+                participant_id: entry.participant_id.clone(),
+                name: entry.name.clone(),
+                numbers: Vec::new(),
+            });
+            merged.len() - 1
+        });
+        if !number.is_empty() && !merged[pos].numbers.contains(&number) {
+            merged[pos].numbers.push(number);
+        }
+    }
+    merged.sort_by_key(|c| c.name.to_lowercase());
+    merged
+}
+
+fn contacts_to_items(contacts: Vec<Contact>) -> Vec<ContactItem> {
+    let avatars = shared().avatars.try_read().ok();
+    contacts
+        .into_iter()
+        .map(|contact| {
+            let identifier = contact.avatar_identifier().to_string();
+            let url = avatars
+                .as_ref()
+                .and_then(|cache| cache.get(&identifier).cloned())
+                .unwrap_or_else(|| generated_avatar_url(&identifier, &contact.name));
+            ContactItem {
+                contact,
+                avatar_url: QString::from(url.as_str()),
+            }
+        })
+        .collect()
+}
+
+/// Contacts matching `query` by name or number, best matches first.
+fn search_contacts(items: &[ContactItem], query: &str) -> Vec<ContactItem> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return items.to_vec();
+    }
+    let query_digits: String = query.chars().filter(|c| c.is_ascii_digit()).collect();
+    let mut scored: Vec<(u32, &ContactItem)> = items
+        .iter()
+        .filter_map(|item| match_score(&item.contact, &query, &query_digits).map(|s| (s, item)))
+        .collect();
+    // Stable sort keeps alphabetical order within a score
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// Higher is better; None when the contact doesn't match at all.
+fn match_score(contact: &Contact, query: &str, query_digits: &str) -> Option<u32> {
+    let name = contact.name.to_lowercase();
+    let mut best = if name.starts_with(query) {
+        Some(100)
+    } else if name.split_whitespace().any(|word| word.starts_with(query)) {
+        Some(80)
+    } else if name.contains(query) {
+        Some(60)
+    } else if is_subsequence(query, &name) {
+        Some(20)
+    } else {
+        None
+    };
+    if query_digits.len() >= 3 {
+        let number_match = contact.numbers.iter().any(|number| {
+            let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+            digits.contains(query_digits)
+        });
+        if number_match {
+            best = best.max(Some(70));
+        }
+    }
+    best
+}
+
+/// Whether all characters of `needle` appear in `haystack` in order, e.g.
+/// "jsm" in "john smith".
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut remaining = haystack.chars();
+    needle
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| remaining.any(|h| h == c))
+}
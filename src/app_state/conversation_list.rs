@@ -19,6 +19,9 @@ pub struct ConversationItem {
     pub preview: QString,
     pub avatar_url: QString,
     pub avatar_identifier: String,
+    pub participant_numbers: Vec<String>,
+    /// Avatar identifier and name of up to four other members, for group tiles.
+    pub members: Vec<(String, String)>,
     pub is_group_chat: bool,
     pub unread: bool,
     pub conversation_id: String,
@@ -27,6 +30,27 @@ pub struct ConversationItem {
     pub last_message_time: QString,
 }
 
+impl ConversationItem {
+    /// Apply freshly fetched photos: the contact's own photo for one-to-one
+    /// chats, a rebuilt tile for groups. Returns whether the avatar changed.
+    fn apply_avatars(&mut self, new_avatars: &HashMap<String, String>) -> bool {
+        if self.is_group_chat && !self.members.is_empty() {
+            if !self.members.iter().any(|(id, _)| new_avatars.contains_key(id)) {
+                return false;
+            }
+            self.avatar_url = QString::from(group_avatar_url(&self.members).as_str());
+            return true;
+        }
+        match new_avatars.get(&self.avatar_identifier) {
+            Some(url) if !url.is_empty() => {
+                self.avatar_url = QString::from(url.as_str());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 pub struct ConversationListRust {
     all_items: Vec<ConversationItem>,
     filtered_items: Vec<ConversationItem>,
@@ -130,6 +154,15 @@ impl crate::ffi::ConversationList {
                 {
                     let cache = shared().avatars.read().await;
                     for item in &mut items {
+                        if item.is_group_chat && !item.members.is_empty() {
+                            // Group tiles are rebuilt once member photos arrive
+                            for (id, _) in &item.members {
+                                if !cache.contains_key(id) && !avatar_identifiers.contains(id) {
+                                    avatar_identifiers.push(id.clone());
+                                }
+                            }
+                            continue;
+                        }
                         if item.avatar_identifier.is_empty() {
                             continue;
                         }
@@ -173,12 +206,7 @@ impl crate::ffi::ConversationList {
                                         rust.avatar_by_identifier.insert(id.clone(), url.clone());
                                     }
                                     for item in &mut rust.all_items {
-                                        if let Some(url) = new_avatars.get(&item.avatar_identifier)
-                                        {
-                                            if !url.is_empty() {
-                                                item.avatar_url = QString::from(url.as_str());
-                                            }
-                                        }
+                                        item.apply_avatars(&new_avatars);
                                     }
                                     rust.filtered_items =
                                         filter_items(&rust.all_items, &rust.filter_text);
@@ -260,6 +288,15 @@ impl crate::ffi::ConversationList {
                 {
                     let cache = shared().avatars.read().await;
                     for item in &mut items {
+                        if item.is_group_chat && !item.members.is_empty() {
+                            // Group tiles are rebuilt once member photos arrive
+                            for (id, _) in &item.members {
+                                if !cache.contains_key(id) && !avatar_identifiers.contains(id) {
+                                    avatar_identifiers.push(id.clone());
+                                }
+                            }
+                            continue;
+                        }
                         if item.avatar_identifier.is_empty() {
                             continue;
                         }
@@ -322,21 +359,12 @@ impl crate::ffi::ConversationList {
                                     // Apply them to all items (avoids QML redraw if we use dataChanged instead of reset)
                                     let mut changed_indices = Vec::new();
                                     for (i, item) in rust.filtered_items.iter_mut().enumerate() {
-                                        if let Some(url) = new_avatars.get(&item.avatar_identifier)
-                                        {
-                                            if !url.is_empty() {
-                                                item.avatar_url = QString::from(url.as_str());
-                                                changed_indices.push(i as i32);
-                                            }
+                                        if item.apply_avatars(&new_avatars) {
+                                            changed_indices.push(i as i32);
                                         }
                                     }
                                     for item in &mut rust.all_items {
-                                        if let Some(url) = new_avatars.get(&item.avatar_identifier)
-                                        {
-                                            if !url.is_empty() {
-                                                item.avatar_url = QString::from(url.as_str());
-                                            }
-                                        }
+                                        item.apply_avatars(&new_avatars);
                                     }
 
                                     drop(rust);
@@ -395,6 +423,257 @@ impl crate::ffi::ConversationList {
         QString::from(self.filtered_items[index].me_participant_id.as_str())
     }
 
+    pub fn conversation_name(&self, row: i32) -> QString {
+        let index = row.max(0) as usize;
+        if index >= self.filtered_items.len() {
+            return QString::from("");
+        }
+        self.filtered_items[index].name.clone()
+    }
+
+    pub fn is_group_chat(&self, row: i32) -> bool {
+        let index = row.max(0) as usize;
+        self.filtered_items
+            .get(index)
+            .map(|item| item.is_group_chat)
+            .unwrap_or(false)
+    }
+
+    pub fn row_for_conversation(&self, conversation_id: &QString) -> i32 {
+        let convo_id = conversation_id.to_string();
+        self.filtered_items
+            .iter()
+            .position(|item| item.conversation_id == convo_id)
+            .map(|pos| pos as i32)
+            .unwrap_or(-1)
+    }
+
+    /// Open the conversation with exactly these participants (comma-separated
+    /// numbers), creating it on the phone if none exists yet. Emits
+    /// conversation_opened with the body to prefill the composer with.
+    pub fn open_for_numbers(mut self: Pin<&mut Self>, numbers: &QString, body: &QString) {
+        let wanted: Vec<String> = numbers
+            .to_string()
+            .split(',')
+            .map(normalize_number)
+            .filter(|n| !n.is_empty())
+            .collect();
+        if wanted.is_empty() {
+            return;
+        }
+        let body = body.to_string();
+
+        let existing = self
+            .rust()
+            .all_items
+            .iter()
+            .find(|item| participants_match(&item.participant_numbers, &wanted))
+            .map(|item| item.conversation_id.clone());
+        if let Some(convo_id) = existing {
+            self.as_mut().conversation_opened(
+                &QString::from(convo_id.as_str()),
+                &QString::from(body.as_str()),
+            );
+            return;
+        }
+
+        let qt_thread: CxxQtThread<ffi::ConversationList> = self.qt_thread();
+        spawn(async move {
+            let result: Result<libgmessages_rs::proto::conversations::Conversation, String> =
+                async {
+                    let client = ensure_client().await?;
+                    let handler = make_handler(&client).await?;
+
+                    let contact_numbers: Vec<libgmessages_rs::proto::conversations::ContactNumber> =
+                        wanted
+                            .iter()
+                            .map(|n| libgmessages_rs::proto::conversations::ContactNumber {
+                                mysterious_int: 7,
+                                number: n.clone(),
+                                number2: n.clone(),
+                                ..Default::default()
+                            })
+                            .collect();
+                    let mut request =
+                        libgmessages_rs::proto::client::GetOrCreateConversationRequest {
+                            numbers: contact_numbers,
+                            ..Default::default()
+                        };
+                    let mut response: libgmessages_rs::proto::client::GetOrCreateConversationResponse =
+                        handler
+                            .send_request(
+                                libgmessages_rs::proto::rpc::ActionType::GetOrCreateConversation,
+                                libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                                &request,
+                            )
+                            .await
+                            .map_err(|e| e.to_string())?;
+
+                    // Group chats with RCS-capable members must be confirmed as RCS groups
+                    if response.status
+                        == libgmessages_rs::proto::client::get_or_create_conversation_response::Status::CreateRcs
+                            as i32
+                    {
+                        request.create_rcs_group = Some(true);
+                        response = handler
+                            .send_request(
+                                libgmessages_rs::proto::rpc::ActionType::GetOrCreateConversation,
+                                libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                                &request,
+                            )
+                            .await
+                            .map_err(|e| e.to_string())?;
+                    }
+
+                    response
+                        .conversation
+                        .ok_or_else(|| "no conversation returned".to_string())
+                }
+                .await;
+
+            match result {
+                Ok(convo) => {
+                    let item = conversation_to_item(&convo);
+                    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::ConversationList>| {
+                        let convo_id = item.conversation_id.clone();
+                        let exists = qobject
+                            .rust()
+                            .all_items
+                            .iter()
+                            .any(|existing| existing.conversation_id == convo_id);
+                        if !exists {
+                            qobject.as_mut().begin_reset_model();
+                            let mut rust = qobject.as_mut().rust_mut();
+                            rust.all_items.insert(0, item);
+                            rust.filtered_items = filter_items(&rust.all_items, &rust.filter_text);
+                            drop(rust);
+                            qobject.as_mut().end_reset_model();
+                        }
+                        qobject.as_mut().conversation_opened(
+                            &QString::from(convo_id.as_str()),
+                            &QString::from(body.as_str()),
+                        );
+                    });
+                }
+                Err(error) => {
+                    eprintln!("open conversation for uri failed: {error}");
+                }
+            }
+        });
+    }
+
+    // ── Group management ─────────────────────────────────────────
+
+    /// Rename a group. The new name arrives through conversation_updated,
+    /// along with an info message in the thread.
+    pub fn rename_group(self: Pin<&mut Self>, conversation_id: &QString, name: &QString) {
+        let convo_id = conversation_id.to_string();
+        let name = name.to_string().trim().to_string();
+        if convo_id.is_empty() || name.is_empty() {
+            return;
+        }
+        let request = libgmessages_rs::proto::client::UpdateConversationRequest {
+            conversation_id: convo_id.clone(),
+            data: Some(libgmessages_rs::proto::client::UpdateConversationData {
+                conversation_id: convo_id.clone(),
+                data: Some(
+                    libgmessages_rs::proto::client::update_conversation_data::Data::Name(name),
+                ),
+            }),
+            ..Default::default()
+        };
+        let action = GroupAction::Rename;
+        spawn_group_action(self.qt_thread(), convo_id, action, move |handler| async move {
+            let _: libgmessages_rs::proto::client::UpdateConversationResponse = handler
+                .send_request(
+                    libgmessages_rs::proto::rpc::ActionType::UpdateConversation,
+                    libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                    &request,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        });
+    }
+
+    /// Add members to a group by comma-separated numbers.
+    pub fn add_participants(self: Pin<&mut Self>, conversation_id: &QString, numbers: &QString) {
+        let convo_id = conversation_id.to_string();
+        let numbers: Vec<libgmessages_rs::proto::conversations::ContactNumber> = numbers
+            .to_string()
+            .split(',')
+            .map(normalize_number)
+            .filter(|n| !n.is_empty())
+            .map(|n| libgmessages_rs::proto::conversations::ContactNumber {
+                mysterious_int: 7,
+                number: n.clone(),
+                number2: n,
+                ..Default::default()
+            })
+            .collect();
+        if convo_id.is_empty() || numbers.is_empty() {
+            return;
+        }
+        let request = libgmessages_rs::proto::client::AddParticipantsRequest {
+            conversation_id: convo_id.clone(),
+            numbers,
+        };
+        let action = GroupAction::AddParticipants;
+        spawn_group_action(self.qt_thread(), convo_id, action, move |handler| async move {
+            // The phone's reply carries nothing we use
+            let _: () = handler
+                .send_request(
+                    libgmessages_rs::proto::rpc::ActionType::AddParticipantToRcsGroup,
+                    libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                    &request,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        });
+    }
+
+    /// Leave a group. The conversation is dropped from the list once the
+    /// phone confirms.
+    pub fn leave_group(self: Pin<&mut Self>, conversation_id: &QString) {
+        let convo_id = conversation_id.to_string();
+        if convo_id.is_empty() {
+            return;
+        }
+        let request = libgmessages_rs::proto::client::LeaveConversationRequest {
+            conversation_id: convo_id.clone(),
+        };
+        let action = GroupAction::Leave;
+        spawn_group_action(self.qt_thread(), convo_id, action, move |handler| async move {
+            let _: () = handler
+                .send_request(
+                    libgmessages_rs::proto::rpc::ActionType::LeaveRcsGroup,
+                    libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                    &request,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        });
+    }
+
+    fn remove_conversation(mut self: Pin<&mut Self>, conversation_id: &str) {
+        if !self
+            .rust()
+            .all_items
+            .iter()
+            .any(|item| item.conversation_id == conversation_id)
+        {
+            return;
+        }
+        self.as_mut().begin_reset_model();
+        let mut rust = self.as_mut().rust_mut();
+        rust.all_items.retain(|item| item.conversation_id != conversation_id);
+        rust.filtered_items = filter_items(&rust.all_items, &rust.filter_text);
+        drop(rust);
+        self.as_mut().end_reset_model();
+    }
+
     /// Called from QML when the session controller emits conversation_updated.
     /// Updates an existing conversation in-place using dataChanged, or inserts it.
     pub fn handle_conversation_event(
@@ -407,12 +686,20 @@ impl crate::ffi::ConversationList {
         is_group_chat: bool,
         status: i32,
         avatar_identifier: &QString,
+        participant_numbers: &QString,
     ) {
         if self.rust().loading {
             return;
         }
 
         let convo_id = conversation_id.to_string();
+        // Comma-separated, like the numbers of an sms: link
+        let numbers: Vec<String> = participant_numbers
+            .to_string()
+            .split(',')
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect();
         let name_str = name.to_string();
         let preview_str = preview.to_string();
         let time_str = format_human_timestamp(last_message_timestamp);
@@ -442,6 +729,9 @@ impl crate::ffi::ConversationList {
                     item.last_message_timestamp = last_message_timestamp;
                     item.last_message_time = QString::from(time_str.as_str());
                     item.is_group_chat = is_group_chat;
+                    if !numbers.is_empty() {
+                        item.participant_numbers = numbers;
+                    }
                 }
             }
 
@@ -462,8 +752,11 @@ impl crate::ffi::ConversationList {
             let new_item = ConversationItem {
                 name: QString::from(name_str.as_str()),
                 preview: QString::from(preview_str.as_str()),
-                avatar_url: QString::from(""), // Fetched below
+                // Replaced by the contact photo if one is fetched below
+                avatar_url: QString::from(generated_avatar_url(&avatar_id, &name_str).as_str()),
                 avatar_identifier: avatar_id,
+                participant_numbers: numbers,
+                members: Vec::new(),
                 is_group_chat,
                 unread,
                 conversation_id: convo_id,
@@ -494,12 +787,7 @@ impl crate::ffi::ConversationList {
                                         rust.avatar_by_identifier.insert(id.clone(), url.clone());
                                     }
                                     for item in &mut rust.all_items {
-                                        if let Some(url) = new_avatars.get(&item.avatar_identifier)
-                                        {
-                                            if !url.is_empty() {
-                                                item.avatar_url = QString::from(url.as_str());
-                                            }
-                                        }
+                                        item.apply_avatars(&new_avatars);
                                     }
                                     rust.filtered_items =
                                         filter_items(&rust.all_items, &rust.filter_text);
@@ -605,22 +893,45 @@ pub fn conversation_to_item(
     let avatar_identifier = convo
         .participants
         .iter()
-        .find_map(|p| {
-            if p.is_me {
-                None
-            } else if !p.contact_id.is_empty() {
-                Some(p.contact_id.clone())
-            } else {
-                p.id.as_ref().map(|id| id.participant_id.clone())
-            }
-        })
+        .filter(|p| !p.is_me)
+        .find_map(participant_avatar_identifier)
         .unwrap_or_default();
+    let members: Vec<(String, String)> = if is_group_chat {
+        convo
+            .participants
+            .iter()
+            .filter(|p| !p.is_me)
+            .filter_map(|p| {
+                let identifier = participant_avatar_identifier(p)?;
+                let name = if p.full_name.is_empty() {
+                    p.formatted_number.clone()
+                } else {
+                    p.full_name.clone()
+                };
+                Some((identifier, name))
+            })
+            .take(4)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Photos replace these once fetched
+    let avatar_url = if !members.is_empty() {
+        group_avatar_url(&members)
+    } else if !avatar_identifier.is_empty() {
+        generated_avatar_url(&avatar_identifier, &convo.name)
+    } else {
+        generated_avatar_url(&conversation_id, &convo.name)
+    };
+    let participant_numbers = participant_numbers(convo);
 
     ConversationItem {
         name,
         preview,
-        avatar_url: QString::from(""),
+        avatar_url: QString::from(avatar_url.as_str()),
         avatar_identifier,
+        participant_numbers,
+        members,
         is_group_chat,
         unread,
         conversation_id,
@@ -630,6 +941,120 @@ pub fn conversation_to_item(
     }
 }
 
+/// A group change sent from the participants dialog. There is no RPC for
+/// removing someone else from a group, so only these three exist.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupAction {
+    Rename,
+    AddParticipants,
+    Leave,
+}
+
+impl GroupAction {
+    /// Name passed to QML through group_action_finished.
+    fn as_str(self) -> &'static str {
+        match self {
+            GroupAction::Rename => "rename",
+            GroupAction::AddParticipants => "add",
+            GroupAction::Leave => "leave",
+        }
+    }
+}
+
+/// Send a group management request and report the outcome through
+/// group_action_finished, with an empty error on success. Membership changes
+/// refresh the stored participants so sender names stay current.
+fn spawn_group_action<F, Fut>(
+    qt_thread: CxxQtThread<ffi::ConversationList>,
+    conversation_id: String,
+    action: GroupAction,
+    request: F,
+) where
+    F: FnOnce(libgmessages_rs::gmclient::SessionHandler) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<(), String>> + Send + 'static,
+{
+    spawn(async move {
+        let result: Result<(), String> = async {
+            let client = ensure_client().await?;
+            let handler = make_handler(&client).await?;
+            request(handler).await
+        }
+        .await;
+
+        let error = match result {
+            Ok(()) => {
+                if action == GroupAction::AddParticipants {
+                    if let Err(error) = fetch_participants(&conversation_id).await {
+                        eprintln!("participant refresh failed: {error}");
+                    }
+                }
+                String::new()
+            }
+            Err(error) => {
+                eprintln!("group {} failed: {error}", action.as_str());
+                error
+            }
+        };
+        let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::ConversationList>| {
+            if action == GroupAction::Leave && error.is_empty() {
+                qobject.as_mut().remove_conversation(&conversation_id);
+            }
+            qobject.as_mut().group_action_finished(
+                &QString::from(conversation_id.as_str()),
+                &QString::from(action.as_str()),
+                &QString::from(error.as_str()),
+            );
+        });
+    });
+}
+
+/// The identifier used to fetch a participant's photo: the contact id when
+/// there is one, otherwise the participant id.
+fn participant_avatar_identifier(
+    participant: &libgmessages_rs::proto::conversations::Participant,
+) -> Option<String> {
+    if !participant.contact_id.is_empty() {
+        Some(participant.contact_id.clone())
+    } else {
+        participant
+            .id
+            .as_ref()
+            .map(|id| id.participant_id.clone())
+            .filter(|id| !id.is_empty())
+    }
+}
+
+/// Phone numbers of everyone in a conversation except us.
+pub fn participant_numbers(
+    convo: &libgmessages_rs::proto::conversations::Conversation,
+) -> Vec<String> {
+    convo
+        .participants
+        .iter()
+        .filter(|p| !p.is_me)
+        .filter_map(|p| {
+            let number =
+                p.id.as_ref()
+                    .map(|id| id.number.clone())
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| p.formatted_number.clone());
+            if number.is_empty() {
+                None
+            } else {
+                Some(number)
+            }
+        })
+        .collect()
+}
+
+/// Whether a conversation's other participants are exactly the given numbers.
+fn participants_match(participant_numbers: &[String], wanted: &[String]) -> bool {
+    participant_numbers.len() == wanted.len()
+        && wanted
+            .iter()
+            .all(|w| participant_numbers.iter().any(|p| numbers_match(p, w)))
+}
+
 fn is_visible_conversation(convo: &libgmessages_rs::proto::conversations::Conversation) -> bool {
     convo.status == libgmessages_rs::proto::conversations::ConversationStatus::Active as i32
 }
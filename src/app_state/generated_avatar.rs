@@ -0,0 +1,192 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::app_state::avatar_cache::cached_avatar_file;
+use crate::app_state::cache::{read_cache_file, CacheCategory};
+
+// ── Generated avatars ────────────────────────────────────────────
+
+/// Background colours for initials, picked by hashing the avatar identifier.
+const PALETTE: [&str; 12] = [
+    "#e53935", "#d81b60", "#8e24aa", "#5e35b1", "#3949ab", "#1e88e5", "#00897b", "#43a047",
+    "#7cb342", "#f4511e", "#6d4c41", "#546e7a",
+];
+
+const SIZE: u32 = 128;
+
+/// FNV-1a, so colours and file names stay the same across runs and Rust
+/// versions (unlike `DefaultHasher`).
+fn stable_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+pub fn avatar_colour(identifier: &str) -> &'static str {
+    PALETTE[(stable_hash(&[identifier]) % PALETTE.len() as u64) as usize]
+}
+
+/// First and last initials of a display name, or `#` for bare phone numbers.
+pub fn initials(name: &str) -> String {
+    let letters: Vec<char> = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    match letters.as_slice() {
+        [] => "#".to_string(),
+        [only] => only.to_uppercase().collect(),
+        [first, .., last] => first.to_uppercase().chain(last.to_uppercase()).collect(),
+    }
+}
+
+fn initials_tile(x: u32, y: u32, w: u32, h: u32, identifier: &str, name: &str) -> String {
+    let font_size = w.min(h) * 2 / 5;
+    // QtSvg has no dominant-baseline, so shift the baseline down by hand
+    let baseline = y + h / 2 + font_size * 7 / 20;
+    format!(
+        r##"<rect x="{x}" y="{y}" width="{w}" height="{h}" fill="{colour}"/><text x="{cx}" y="{baseline}" font-family="sans-serif" font-size="{font_size}" font-weight="bold" fill="#ffffff" text-anchor="middle">{text}</text>"##,
+        colour = avatar_colour(identifier),
+        cx = x + w / 2,
+        text = initials(name),
+    )
+}
+
+fn photo_tile(x: u32, y: u32, w: u32, h: u32, file_name: &str, data: &[u8]) -> String {
+    let ext = file_name.rsplit('.').next().unwrap_or("png");
+    format!(
+        r#"<image x="{x}" y="{y}" width="{w}" height="{h}" preserveAspectRatio="xMidYMid slice" xlink:href="data:image/{ext};base64,{data}"/>"#,
+        data = STANDARD.encode(data),
+    )
+}
+
+fn svg_document(body: &str) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}">{body}</svg>"#
+    )
+}
+
+/// What a generated avatar shows. URLs only name a recipe; the SVG is drawn
+/// when the image provider asks for it, off the Qt thread and never written
+/// to disk.
+enum Recipe {
+    Initials {
+        identifier: String,
+        name: String,
+    },
+    /// Members (identifier, name) with their cached photo files.
+    Group {
+        members: Vec<(String, String)>,
+        photos: Vec<Option<String>>,
+    },
+}
+
+fn recipes() -> &'static Mutex<HashMap<String, Recipe>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<String, Recipe>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn generated_uri(key: String, recipe: impl FnOnce() -> Recipe) -> String {
+    let uri = format!("image://kourier/generated/{key}");
+    recipes().lock().unwrap().entry(key).or_insert_with(recipe);
+    uri
+}
+
+/// Forget every recipe handed out so far, along with the rest of the avatar
+/// state when the cache is cleared.
+pub fn forget_generated_avatars() {
+    recipes().lock().unwrap().clear();
+}
+
+/// SVG for an `image://kourier/generated/<key>` request, or `None` for a key
+/// this run never handed out. Called from the image provider's threads.
+pub fn render_generated_avatar(key: &str) -> Option<Vec<u8>> {
+    let svg = match recipes().lock().unwrap().get(key)? {
+        Recipe::Initials { identifier, name } => {
+            svg_document(&initials_tile(0, 0, SIZE, SIZE, identifier, name))
+        }
+        Recipe::Group { members, photos } => group_svg(members, photos),
+    };
+    Some(svg.into_bytes())
+}
+
+/// Initials on a colour derived from `identifier`, for contacts without a photo.
+pub fn generated_avatar_url(identifier: &str, name: &str) -> String {
+    let key = format!("initials_{:016x}.svg", stable_hash(&[identifier, name]));
+    generated_uri(key, || Recipe::Initials {
+        identifier: identifier.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// Tile positions for one to four members.
+fn group_layout(count: usize) -> &'static [(u32, u32, u32, u32)] {
+    const HALF: u32 = SIZE / 2;
+    match count {
+        0 => &[],
+        1 => &[(0, 0, SIZE, SIZE)],
+        2 => &[(0, 0, HALF, SIZE), (HALF, 0, HALF, SIZE)],
+        3 => &[
+            (0, 0, HALF, SIZE),
+            (HALF, 0, HALF, HALF),
+            (HALF, HALF, HALF, HALF),
+        ],
+        _ => &[
+            (0, 0, HALF, HALF),
+            (HALF, 0, HALF, HALF),
+            (0, HALF, HALF, HALF),
+            (HALF, HALF, HALF, HALF),
+        ],
+    }
+}
+
+/// A tile of up to four members (identifier, name), using their cached
+/// photos where available and initials otherwise.
+pub fn group_avatar_url(members: &[(String, String)]) -> String {
+    let members = &members[..members.len().min(4)];
+    if members.is_empty() {
+        return String::new();
+    }
+
+    let photos: Vec<Option<String>> = members
+        .iter()
+        .map(|(identifier, _)| cached_avatar_file(identifier))
+        .collect();
+
+    // Photos are part of the key so the tile is redrawn when one arrives
+    let mut key: Vec<&str> = Vec::new();
+    for ((identifier, name), photo) in members.iter().zip(&photos) {
+        key.push(identifier);
+        key.push(name);
+        key.push(photo.as_deref().unwrap_or(""));
+    }
+    let key = format!("group_{:016x}.svg", stable_hash(&key));
+
+    generated_uri(key, || Recipe::Group {
+        members: members.to_vec(),
+        photos: photos.clone(),
+    })
+}
+
+fn group_svg(members: &[(String, String)], photos: &[Option<String>]) -> String {
+    let mut body = String::new();
+    for (((identifier, name), photo), &(x, y, w, h)) in
+        members.iter().zip(photos).zip(group_layout(members.len()))
+    {
+        let data = photo
+            .as_ref()
+            .and_then(|file| read_cache_file(CacheCategory::Avatars, file).map(|d| (file, d)));
+        match data {
+            Some((file, data)) => body.push_str(&photo_tile(x, y, w, h, file, &data)),
+            None => body.push_str(&initials_tile(x, y, w, h, identifier, name)),
+        }
+    }
+    svg_document(&body)
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::*;
+
+// ── HEIC/HEIF/AVIF conversion ────────────────────────────────────
+
+/// HEIC photos from iPhones and AVIF images, which Qt can only show with the
+/// optional KImageFormats plugins installed.
+pub fn is_heif_family(mime: &str) -> bool {
+    matches!(
+        mime,
+        "image/heic" | "image/heic-sequence" | "image/heif" | "image/heif-sequence" | "image/avif"
+    )
+}
+
+/// MIME type from the `ftyp` brand of an ISOBMFF file, for the image brands.
+pub fn heif_brand_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    match bytes.get(8..12)? {
+        b"heic" | b"heix" | b"heim" | b"heis" => Some("image/heic"),
+        b"hevc" | b"hevx" => Some("image/heic-sequence"),
+        b"mif1" | b"heif" => Some("image/heif"),
+        b"msf1" => Some("image/heif-sequence"),
+        b"avif" | b"avis" => Some("image/avif"),
+        _ => None,
+    }
+}
+
+/// Decode a HEIF-family image into a JPEG or PNG (chosen by `output`'s
+/// extension). Uses libheif's command line tools when installed and falls
+/// back to ffmpeg, which handles AVIF everywhere and HEIC from 7.1 on.
+pub fn convert_heif(input: &Path, output: &Path) -> bool {
+    let libheif = ["heif-dec", "heif-convert"].into_iter().any(|tool| {
+        Command::new(tool)
+            .arg(input)
+            .arg(output)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    });
+    if libheif && output.exists() {
+        return true;
+    }
+    Command::new("ffmpeg")
+        .args(["-v", "error", "-y", "-i"])
+        .arg(input)
+        .args(["-frames:v", "1"])
+        .arg(output)
+        .status()
+        .is_ok_and(|status| status.success())
+        && output.exists()
+}
+
+/// File extension to convert to: PNG for AVIF, which is often used for
+/// stickers with transparency, JPEG for camera photos.
+fn display_extension(mime: &str) -> &'static str {
+    if mime == "image/avif" {
+        "png"
+    } else {
+        "jpg"
+    }
+}
+
+/// Image data the image provider can decode. HEIF-family attachments are
+/// converted once and the result kept in the media cache next to the
+/// original; anything else, or a failed conversion, is returned as is.
+pub fn displayable_image(file_name: &str, data: Vec<u8>) -> Vec<u8> {
+    let Some(mime) = sniff_mime(&data).filter(|mime| is_heif_family(mime)) else {
+        return data;
+    };
+    let extension = display_extension(mime);
+    let converted_name = format!("{file_name}.display.{extension}");
+    if let Some(converted) = read_cache_file(CacheCategory::Media, &converted_name) {
+        return converted;
+    }
+
+    let work = runtime_subdir("convert");
+    let input = work.join(format!("{}.{}", uuid::Uuid::new_v4(), mime_to_extension(mime)));
+    let output = input.with_extension(extension);
+    let converted = write_private_file(&input, &data)
+        .ok()
+        .filter(|_| convert_heif(&input, &output))
+        .and_then(|_| std::fs::read(&output).ok());
+    let _ = std::fs::remove_file(&input);
+    let _ = std::fs::remove_file(&output);
+
+    match converted {
+        Some(converted) => {
+            let mime = if extension == "png" { "image/png" } else { "image/jpeg" };
+            if let Err(e) = write_cache_file(CacheCategory::Media, &converted_name, &converted, mime)
+            {
+                eprintln!("heif: failed to cache {converted_name}: {e}");
+            }
+            converted
+        }
+        None => {
+            eprintln!("heif: could not convert {file_name}; install libheif or ffmpeg 7.1+");
+            data
+        }
+    }
+}
+
+/// A viewable copy of a local HEIF-family file for previews of photos being
+/// sent, or `None` if it is some other format or can't be converted.
+pub fn heif_preview(path: &Path) -> Option<PathBuf> {
+    let mime = file_mime(path);
+    if !is_heif_family(&mime) {
+        return None;
+    }
+    let output = runtime_subdir("convert").join(format!(
+        "preview-{}.{}",
+        uuid::Uuid::new_v4(),
+        display_extension(&mime)
+    ));
+    convert_heif(path, &output).then_some(output)
+}
+
+/// `heif_preview` on a blocking thread, moved into the media cache so no
+/// converted copy is left in the runtime directory. Returns its image
+/// provider URI.
+pub async fn heif_preview_uri(path: PathBuf) -> Option<String> {
+    tokio::task::spawn_blocking(move || {
+        let preview = heif_preview(&path)?;
+        let mime = file_mime(&preview);
+        let data = std::fs::read(&preview);
+        let _ = std::fs::remove_file(&preview);
+        let file_name = preview.file_name()?.to_string_lossy().into_owned();
+        write_cache_file(CacheCategory::Media, &file_name, &data.ok()?, &mime).ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
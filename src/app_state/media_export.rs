@@ -0,0 +1,296 @@
+use crate::ffi;
+use core::pin::Pin;
+use cxx_qt::{CxxQtThread, CxxQtType, Threading};
+use cxx_qt_lib::QString;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{NaiveDate, TimeZone};
+
+use super::*;
+
+// ── MediaExport ──────────────────────────────────────────────────
+
+/// Records what has been exported to a directory, so running the export
+/// again only adds what is new.
+const MANIFEST_NAME: &str = ".kourier-export.json";
+
+/// Attachment cache names mapped to the file each was exported as.
+fn read_manifest(dir: &Path) -> HashMap<String, String> {
+    std::fs::read(dir.join(MANIFEST_NAME))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+async fn write_manifest(dir: PathBuf, manifest: HashMap<String, String>) {
+    let result = tokio::task::spawn_blocking(move || {
+        let data = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(MANIFEST_NAME), data).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    if let Err(e) = result {
+        eprintln!("media export: failed to write manifest: {e}");
+    }
+}
+
+/// Microseconds since the epoch at local midnight starting `date`
+/// ("YYYY-MM-DD"). `None` for an empty string; an error for anything else
+/// that isn't a date.
+fn parse_day_start(date: &str) -> Result<Option<i64>, String> {
+    let date = date.trim();
+    if date.is_empty() {
+        return Ok(None);
+    }
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("\"{date}\" is not a date like 2024-01-31"))?;
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    chrono::Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|start| Some(start.timestamp_micros()))
+        .ok_or_else(|| format!("\"{date}\" is not a valid local date"))
+}
+
+/// "2024-01-31 18-04-12 Alice - IMG_1234.jpg", or without the original name
+/// when the sender didn't include one.
+fn export_file_name(item: &GalleryItem) -> String {
+    let date = chrono::DateTime::<chrono::Utc>::from_timestamp_micros(item.timestamp_micros)
+        .map(|utc| {
+            utc.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H-%M-%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let sender = sanitize_file_name(&item.sender_name.replace(['/', '\\'], "-"))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let attachment = &item.attachment;
+    match sanitize_file_name(&attachment.file_name) {
+        Some(original) => format!("{date} {sender} - {original}"),
+        None => format!(
+            "{date} {sender}.{}",
+            crate::app_state::utils::mime_to_extension(&attachment.mime_type)
+        ),
+    }
+}
+
+/// Running totals, sent with every progress signal.
+#[derive(Clone, Copy, Default)]
+struct ExportCounts {
+    exported: i32,
+    skipped: i32,
+    failed: i32,
+}
+
+/// Save every attachment in `[since, until)` to `dir`, newest first,
+/// reporting after each one. Returns the totals once history runs out, the
+/// range is passed or `cancel` is set. The manifest is written after each
+/// page of history and once more at the end.
+async fn export_conversation(
+    qt_thread: &CxxQtThread<ffi::MediaExport>,
+    conversation_id: &str,
+    dir: &Path,
+    since: Option<i64>,
+    until: Option<i64>,
+    cancel: &AtomicBool,
+) -> Result<ExportCounts, String> {
+    let target = dir.to_path_buf();
+    let mut manifest = tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&target)
+            .map_err(|e| format!("Could not create {}: {e}", target.display()))?;
+        Ok::<_, String>(read_manifest(&target))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let mut counts = ExportCounts::default();
+    let mut cursor = None;
+    let mut unsaved = false;
+
+    let result = loop {
+        let page = match scan_history(conversation_id, cursor).await {
+            Ok(page) => page,
+            Err(e) => break Err(e),
+        };
+        let mut done = false;
+        for item in &page.items {
+            if cancel.load(Ordering::Relaxed) {
+                done = true;
+                break;
+            }
+            if item.is_link() || until.is_some_and(|until| item.timestamp_micros >= until) {
+                continue;
+            }
+            if since.is_some_and(|since| item.timestamp_micros < since) {
+                done = true;
+                break;
+            }
+
+            let key = &item.attachment.cache_name;
+            match save_item(dir, item, manifest.get(key).cloned()).await {
+                Ok(Some(name)) => {
+                    manifest.insert(key.clone(), name);
+                    unsaved = true;
+                    counts.exported += 1;
+                }
+                Ok(None) => counts.skipped += 1,
+                Err(e) => {
+                    eprintln!("media export: {key}: {e}");
+                    counts.failed += 1;
+                }
+            }
+
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MediaExport>| {
+                qobject
+                    .as_mut()
+                    .progress(counts.exported, counts.skipped, counts.failed);
+            });
+        }
+
+        if done {
+            break Ok(());
+        }
+        if unsaved {
+            write_manifest(dir.to_path_buf(), manifest.clone()).await;
+            unsaved = false;
+        }
+
+        let passed_range = since
+            .zip(page.oldest_micros)
+            .is_some_and(|(since, oldest)| oldest < since);
+        if !page.has_more || passed_range || cancel.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+        cursor = page.cursor;
+    };
+
+    if unsaved {
+        write_manifest(dir.to_path_buf(), manifest).await;
+    }
+    result.map(|()| counts)
+}
+
+/// Download one attachment, without adding it to the media cache, and write
+/// it to `dir`. Returns the name it was saved under, or `None` when the file
+/// an earlier export saved it as (`exported`) is still there.
+async fn save_item(
+    dir: &Path,
+    item: &GalleryItem,
+    exported: Option<String>,
+) -> Result<Option<String>, String> {
+    let previous = exported.map(|name| dir.join(name));
+    let still_there =
+        tokio::task::spawn_blocking(move || previous.is_some_and(|path| path.exists()))
+            .await
+            .map_err(|e| e.to_string())?;
+    if still_there {
+        return Ok(None);
+    }
+
+    let data = fetch_media_uncached(&item.attachment.cache_name)
+        .await
+        .ok_or_else(|| "download failed".to_string())?;
+    let dir = dir.to_path_buf();
+    let name = export_file_name(item);
+    tokio::task::spawn_blocking(move || {
+        let path = unique_path(&dir, &name);
+        std::fs::write(&path, &data)
+            .map_err(|e| format!("write to {} failed: {e}", path.display()))?;
+        Ok(Some(
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        ))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+pub struct MediaExportRust {
+    pub running: bool,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Default for MediaExportRust {
+    fn default() -> Self {
+        Self {
+            running: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl crate::ffi::MediaExport {
+    /// Export every attachment in a conversation to `directory_url` (a path or
+    /// unencoded file:// URL) in the background. `from_date` and `to_date`
+    /// ("YYYY-MM-DD", both inclusive) limit it to a date range; leave either
+    /// empty for no limit.
+    pub fn start(
+        mut self: Pin<&mut Self>,
+        conversation_id: &QString,
+        directory_url: &QString,
+        from_date: &QString,
+        to_date: &QString,
+    ) {
+        if self.rust().running {
+            return;
+        }
+        let conversation_id = conversation_id.to_string();
+        let url = directory_url.to_string();
+        let dir = PathBuf::from(url.strip_prefix("file://").unwrap_or(&url));
+        let range = parse_day_start(&from_date.to_string()).and_then(|since| {
+            // The end date is inclusive, so stop at the midnight after it
+            let until = parse_day_start(&to_date.to_string())?
+                .map(|start| start + chrono::Duration::days(1).num_microseconds().unwrap_or(0));
+            Ok((since, until))
+        });
+        let (since, until) = match range {
+            Ok(range) => range,
+            Err(message) => {
+                self.as_mut().export_error(&QString::from(message.as_str()));
+                return;
+            }
+        };
+        if conversation_id.is_empty() || dir.as_os_str().is_empty() {
+            return;
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.as_mut().rust_mut().cancel = cancel.clone();
+        self.as_mut().set_running(true);
+
+        let qt_thread: CxxQtThread<ffi::MediaExport> = self.qt_thread();
+        spawn(async move {
+            let result =
+                export_conversation(&qt_thread, &conversation_id, &dir, since, until, &cancel)
+                    .await;
+            let cancelled = cancel.load(Ordering::Relaxed);
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MediaExport>| {
+                qobject.as_mut().set_running(false);
+                match result {
+                    Ok(counts) => qobject.as_mut().finished(
+                        counts.exported,
+                        counts.skipped,
+                        counts.failed,
+                        cancelled,
+                    ),
+                    Err(message) => {
+                        eprintln!("media export failed: {message}");
+                        qobject
+                            .as_mut()
+                            .export_error(&QString::from(message.as_str()));
+                    }
+                }
+            });
+        });
+    }
+
+    /// Stop a running export after the file being saved.
+    pub fn cancel(&self) {
+        self.rust().cancel.store(true, Ordering::Relaxed);
+    }
+}
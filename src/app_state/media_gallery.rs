@@ -0,0 +1,443 @@
+use crate::ffi;
+use core::pin::Pin;
+use cxx_qt::{CxxQtThread, CxxQtType, Threading};
+use cxx_qt_lib::QString;
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::ffi::QHash_i32_QByteArray;
+use crate::ffi::QModelIndex;
+use crate::ffi::QVariant;
+
+use super::*;
+
+// ── MediaGallery ─────────────────────────────────────────────────
+
+/// A `load_more` keeps paging until it has found this many new items...
+const MIN_NEW_ITEMS: usize = 24;
+/// ...or has read this many pages, so text-only threads don't page forever.
+const MAX_PAGES_PER_LOAD: usize = 10;
+
+/// One attachment or shared link in a conversation.
+#[derive(Clone, Default)]
+pub struct GalleryItem {
+    pub message_id: String,
+    pub timestamp_micros: i64,
+    pub sender_name: String,
+    /// Empty for links.
+    pub attachment: Attachment,
+    /// Empty for attachments.
+    pub link_url: String,
+    thumbnail_requested: bool,
+}
+
+impl GalleryItem {
+    pub fn is_link(&self) -> bool {
+        !self.link_url.is_empty()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct GalleryPage {
+    pub items: Vec<GalleryItem>,
+    pub cursor: Option<libgmessages_rs::proto::client::Cursor>,
+    pub has_more: bool,
+    /// Timestamp of the oldest message read, media or not.
+    pub oldest_micros: Option<i64>,
+}
+
+/// Media found so far per conversation, so reopening the gallery doesn't
+/// page through the history again.
+fn galleries() -> &'static RwLock<HashMap<String, GalleryPage>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<String, GalleryPage>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Month heading of an item, e.g. "October 2026".
+fn month_label(timestamp_micros: i64) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp_millis(timestamp_micros / 1000)
+        .map(|utc| {
+            utc.with_timezone(&chrono::Local)
+                .format("%B %Y")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Gallery items of one message: each attachment, or the first link in its
+/// text. Media is registered for download but not fetched.
+fn gallery_items(
+    conversation_id: &str,
+    message: &libgmessages_rs::proto::conversations::Message,
+) -> Vec<GalleryItem> {
+    record_message_details(message);
+    let message_id = extract_message_id(message);
+    let sender_name = sender_name(conversation_id, &message.participant_id);
+    let parts = extract_media_parts(message);
+    if parts.is_empty() {
+        let body = extract_message_body(message);
+        return crate::app_state::utils::extract_first_url(&body)
+            .map(|link_url| GalleryItem {
+                message_id,
+                timestamp_micros: message.timestamp,
+                sender_name,
+                link_url,
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+    }
+    // Non-images are downloaded when their row is shown, not straight away
+    let mut downloads = Vec::new();
+    attachments_for(&message_id, &parts, &mut downloads)
+        .into_iter()
+        .map(|attachment| GalleryItem {
+            message_id: message_id.clone(),
+            timestamp_micros: message.timestamp,
+            sender_name: sender_name.clone(),
+            attachment,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Seed the gallery of a conversation with the messages MessageList just
+/// loaded, so opening it pages on from there instead of asking the phone for
+/// the same messages again. `cursor` continues from the oldest of them.
+pub fn seed_gallery(
+    conversation_id: &str,
+    messages: &[libgmessages_rs::proto::conversations::Message],
+    cursor: Option<libgmessages_rs::proto::client::Cursor>,
+) {
+    if galleries().read().unwrap().contains_key(conversation_id) {
+        return;
+    }
+    let mut messages: Vec<_> = messages.iter().collect();
+    messages.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let page = GalleryPage {
+        items: messages
+            .iter()
+            .flat_map(|message| gallery_items(conversation_id, message))
+            .collect(),
+        has_more: cursor.is_some(),
+        cursor,
+        oldest_micros: messages.last().map(|message| message.timestamp),
+    };
+    galleries()
+        .write()
+        .unwrap()
+        .entry(conversation_id.to_string())
+        .or_insert(page);
+}
+
+/// Drop a conversation's gallery snapshot when a message arrives with media
+/// or a link it doesn't have yet, so the next load picks it up.
+pub fn invalidate_gallery(message: &libgmessages_rs::proto::conversations::Message) {
+    let message_id = extract_message_id(message);
+    let mut store = galleries().write().unwrap();
+    let Some(page) = store.get(&message.conversation_id) else {
+        return;
+    };
+    if page.items.iter().any(|item| item.message_id == message_id) {
+        return;
+    }
+    let has_media = !extract_media_parts(message).is_empty()
+        || crate::app_state::utils::extract_first_url(&extract_message_body(message)).is_some();
+    if has_media {
+        store.remove(&message.conversation_id);
+    }
+}
+
+/// Page backwards from `cursor` until enough media has been found, returning
+/// the new items, the cursor to continue from and whether there is more.
+pub async fn scan_history(
+    conversation_id: &str,
+    mut cursor: Option<libgmessages_rs::proto::client::Cursor>,
+) -> Result<GalleryPage, String> {
+    let client = ensure_client().await?;
+    let handler = make_handler(&client).await?;
+    if participants_for(conversation_id).is_none() {
+        // Only needed for sender names; the gallery works without them
+        let _ = fetch_participants(conversation_id).await;
+    }
+
+    let mut page = GalleryPage {
+        has_more: true,
+        ..Default::default()
+    };
+    for _ in 0..MAX_PAGES_PER_LOAD {
+        let request = libgmessages_rs::proto::client::ListMessagesRequest {
+            conversation_id: conversation_id.to_string(),
+            // Twice MessageList's page size, since most messages are skipped
+            count: 100,
+            cursor: cursor.clone(),
+        };
+        let response = handler
+            .send_request::<libgmessages_rs::proto::client::ListMessagesResponse>(
+                libgmessages_rs::proto::rpc::ActionType::ListMessages,
+                libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+                &request,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut messages = response.messages;
+        messages.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        let exhausted = messages.is_empty() || response.cursor.is_none();
+        if let Some(oldest) = messages.last() {
+            page.oldest_micros = Some(oldest.timestamp);
+        }
+        page.items.extend(
+            messages
+                .iter()
+                .flat_map(|message| gallery_items(conversation_id, message)),
+        );
+        cursor = response.cursor;
+        if exhausted {
+            page.has_more = false;
+            break;
+        }
+        if page.items.len() >= MIN_NEW_ITEMS {
+            break;
+        }
+    }
+    page.cursor = cursor;
+    Ok(page)
+}
+
+#[derive(Default)]
+pub struct MediaGalleryRust {
+    pub loading: bool,
+    pub has_more: bool,
+    items: Vec<GalleryItem>,
+    conversation_id: String,
+    cursor: Option<libgmessages_rs::proto::client::Cursor>,
+    /// Bumped by every `load`, so pages fetched for an earlier one are dropped.
+    generation: u64,
+}
+
+impl crate::ffi::MediaGallery {
+    pub fn row_count(&self, _parent: &QModelIndex) -> i32 {
+        self.items.len() as i32
+    }
+
+    pub fn data(&self, index: &QModelIndex, role: i32) -> QVariant {
+        let row = index.row() as usize;
+        if row >= self.items.len() {
+            return QVariant::default();
+        }
+
+        let item = &self.items[row];
+        let attachment = &item.attachment;
+        match role {
+            0 => QVariant::from(&QString::from(item.message_id.as_str())),
+            1 => QVariant::from(&QString::from(attachment.media_url.as_str())),
+            2 => QVariant::from(&QString::from(attachment.thumbnail_url.as_str())),
+            3 => QVariant::from(&QString::from(attachment.mime_type.as_str())),
+            4 => QVariant::from(&QString::from(attachment.file_name.as_str())),
+            5 => QVariant::from(&attachment.file_size),
+            6 => QVariant::from(&QString::from(item.link_url.as_str())),
+            7 => QVariant::from(&item.is_link()),
+            8 => QVariant::from(&QString::from(month_label(item.timestamp_micros).as_str())),
+            9 => QVariant::from(&QString::from(
+                format_section_date(item.timestamp_micros).as_str(),
+            )),
+            10 => QVariant::from(&QString::from(item.sender_name.as_str())),
+            11 => QVariant::from(&item.timestamp_micros),
+            _ => QVariant::default(),
+        }
+    }
+
+    pub fn role_names(&self) -> QHash_i32_QByteArray {
+        let mut roles = QHash_i32_QByteArray::default();
+        roles.insert(0, "message_id".into());
+        roles.insert(1, "media_url".into());
+        roles.insert(2, "thumbnail_url".into());
+        roles.insert(3, "mime_type".into());
+        roles.insert(4, "file_name".into());
+        roles.insert(5, "file_size".into());
+        roles.insert(6, "link_url".into());
+        roles.insert(7, "is_link".into());
+        roles.insert(8, "month".into());
+        roles.insert(9, "date".into());
+        roles.insert(10, "sender_name".into());
+        roles.insert(11, "timestamp_micros".into());
+        roles
+    }
+
+    /// Show the media of a conversation, starting from what was found the
+    /// last time the gallery was opened.
+    pub fn load(mut self: Pin<&mut Self>, conversation_id: &QString) {
+        let conversation_id = conversation_id.to_string();
+        let cached = galleries().read().unwrap().get(&conversation_id).cloned();
+
+        self.as_mut().begin_reset_model();
+        let mut rust = self.as_mut().rust_mut();
+        rust.conversation_id = conversation_id;
+        rust.items = Vec::new();
+        rust.cursor = None;
+        rust.generation += 1;
+        drop(rust);
+        self.as_mut().end_reset_model();
+        // A page still loading for the previous load will be dropped
+        self.as_mut().set_loading(false);
+
+        match cached {
+            Some(page) => {
+                // A page seeded from MessageList may hold only a few items
+                let fill = page.has_more && page.items.len() < MIN_NEW_ITEMS;
+                self.as_mut().set_has_more(page.has_more);
+                self.as_mut().append_page(page);
+                if fill {
+                    self.fetch_page();
+                }
+            }
+            None => {
+                self.as_mut().set_has_more(true);
+                self.fetch_page();
+            }
+        }
+    }
+
+    /// Page further back through the history for older media.
+    pub fn load_more(self: Pin<&mut Self>) {
+        if self.rust().loading || !self.rust().has_more {
+            return;
+        }
+        self.fetch_page();
+    }
+
+    fn fetch_page(mut self: Pin<&mut Self>) {
+        let conversation_id = self.rust().conversation_id.clone();
+        if conversation_id.is_empty() {
+            return;
+        }
+        let cursor = self.rust().cursor.clone();
+        let generation = self.rust().generation;
+        self.as_mut().set_loading(true);
+
+        let qt_thread: CxxQtThread<ffi::MediaGallery> = self.qt_thread();
+        spawn(async move {
+            let result = scan_history(&conversation_id, cursor).await;
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MediaGallery>| {
+                // A page for a conversation since switched away from or reloaded
+                if qobject.rust().generation != generation {
+                    return;
+                }
+                qobject.as_mut().set_loading(false);
+                match result {
+                    Ok(page) => {
+                        qobject.as_mut().set_has_more(page.has_more);
+                        qobject.as_mut().append_page(page);
+                        let rust = qobject.rust();
+                        // Thumbnails still loading are asked for again after reopening
+                        let items = rust
+                            .items
+                            .iter()
+                            .map(|item| GalleryItem {
+                                thumbnail_requested: false,
+                                ..item.clone()
+                            })
+                            .collect();
+                        galleries().write().unwrap().insert(
+                            conversation_id,
+                            GalleryPage {
+                                items,
+                                cursor: rust.cursor.clone(),
+                                has_more: rust.has_more,
+                                oldest_micros: None,
+                            },
+                        );
+                    }
+                    Err(error) => eprintln!("media gallery load failed: {error}"),
+                }
+            });
+        });
+    }
+
+    fn append_page(mut self: Pin<&mut Self>, page: GalleryPage) {
+        let start = self.rust().items.len() as i32;
+        self.as_mut().rust_mut().cursor = page.cursor;
+        if page.items.is_empty() {
+            return;
+        }
+        let end = start + page.items.len() as i32 - 1;
+        self.as_mut()
+            .begin_insert_rows(&QModelIndex::default(), start, end);
+        self.as_mut().rust_mut().items.extend(page.items);
+        self.as_mut().end_insert_rows();
+    }
+
+    /// Fetch the thumbnail of a row, and for media the image provider can't
+    /// serve, the file itself. Called by QML as rows come into view.
+    pub fn request_thumbnail(mut self: Pin<&mut Self>, row: i32) {
+        let mut rust = self.as_mut().rust_mut();
+        let Some(item) = usize::try_from(row)
+            .ok()
+            .and_then(|row| rust.items.get_mut(row))
+        else {
+            return;
+        };
+        if item.is_link() || item.thumbnail_requested {
+            return;
+        }
+        item.thumbnail_requested = true;
+        let cache_name = item.attachment.cache_name.clone();
+        let mime = item.attachment.mime_type.clone();
+        drop(rust);
+
+        let qt_thread: CxxQtThread<ffi::MediaGallery> = self.qt_thread();
+        spawn(async move {
+            let thumbnail_url = if mime.starts_with("image/") || mime.starts_with("video/") {
+                thumbnail_uri(ThumbnailSource::Media(cache_name.clone()), &mime).await
+            } else {
+                None
+            };
+            let media_url = (!mime.starts_with("image/") && ensure_media_cached(&cache_name).await)
+                .then(|| cache_uri(CacheCategory::Media, &cache_name, &mime));
+
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MediaGallery>| {
+                let mut rust = qobject.as_mut().rust_mut();
+                let Some(pos) = rust
+                    .items
+                    .iter()
+                    .position(|item| item.attachment.cache_name == cache_name)
+                else {
+                    return;
+                };
+                let attachment = &mut rust.items[pos].attachment;
+                if let Some(url) = thumbnail_url {
+                    attachment.thumbnail_url = url;
+                }
+                if let Some(url) = media_url {
+                    attachment.media_url = url;
+                }
+                drop(rust);
+                let model_index = qobject
+                    .as_ref()
+                    .index(pos as i32, 0, &QModelIndex::default());
+                qobject.as_mut().data_changed(&model_index, &model_index);
+            });
+        });
+    }
+
+    /// The viewer's view of a row as JSON (`media_url`, `mime_type`,
+    /// `file_name`, `link_url`), or "{}" for rows that don't exist.
+    pub fn item_at(&self, row: i32) -> QString {
+        let json = usize::try_from(row)
+            .ok()
+            .and_then(|row| self.items.get(row))
+            .map(|item| {
+                serde_json::json!({
+                    "media_url": item.attachment.media_url,
+                    "mime_type": item.attachment.mime_type,
+                    "file_name": item.attachment.file_name,
+                    "link_url": item.link_url,
+                })
+                .to_string()
+            })
+            .unwrap_or_else(|| "{}".to_string());
+        QString::from(json.as_str())
+    }
+}
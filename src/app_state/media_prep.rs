@@ -0,0 +1,384 @@
+use std::io::{BufRead, BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageReader};
+
+use super::*;
+
+// ── Media preparation ────────────────────────────────────────────
+
+/// Most carriers accept MMS up to 1 MB or a little more; staying under it
+/// leaves room for the text part and MIME headers.
+const MMS_MAX_BYTES: u64 = 950 * 1024;
+/// Attachment limit of RCS.
+const RCS_MAX_BYTES: u64 = 100 * 1024 * 1024;
+/// Audio bitrate of re-encoded videos, in kbit/s.
+const VIDEO_AUDIO_KBPS: u64 = 64;
+
+/// What an attachment has to fit in for the transport it is sent over.
+#[derive(Clone, Copy, Debug)]
+pub struct MediaLimits {
+    pub max_bytes: u64,
+    /// Longest side of photos and videos, `None` to keep the original size.
+    pub max_dimension: Option<u32>,
+    pub jpeg_quality: u8,
+    /// x264 CRF for videos that are re-encoded without a size target.
+    pub video_crf: u8,
+    /// Re-encode videos even when they already fit.
+    pub always_transcode_video: bool,
+    /// Convert HEIC/HEIF/AVIF photos to JPEG, which MMS recipients can open.
+    pub heif_to_jpeg: bool,
+}
+
+pub fn media_limits(mms: bool, quality: MediaQuality) -> MediaLimits {
+    if mms {
+        return MediaLimits {
+            max_bytes: MMS_MAX_BYTES,
+            max_dimension: Some(1280),
+            jpeg_quality: 80,
+            video_crf: 30,
+            always_transcode_video: true,
+            heif_to_jpeg: true,
+        };
+    }
+    match quality {
+        MediaQuality::Original => MediaLimits {
+            max_bytes: RCS_MAX_BYTES,
+            max_dimension: None,
+            jpeg_quality: 92,
+            video_crf: 20,
+            always_transcode_video: false,
+            heif_to_jpeg: false,
+        },
+        MediaQuality::High => MediaLimits {
+            max_bytes: RCS_MAX_BYTES,
+            max_dimension: Some(2560),
+            jpeg_quality: 88,
+            video_crf: 23,
+            always_transcode_video: false,
+            heif_to_jpeg: false,
+        },
+        MediaQuality::DataSaver => MediaLimits {
+            max_bytes: RCS_MAX_BYTES,
+            max_dimension: Some(1280),
+            jpeg_quality: 75,
+            video_crf: 28,
+            always_transcode_video: true,
+            heif_to_jpeg: false,
+        },
+    }
+}
+
+/// The file to upload in place of the one the user picked.
+pub struct PreparedMedia {
+    pub path: PathBuf,
+    pub mime: String,
+    pub file_name: String,
+    /// True if `path` is a temporary file to delete after sending.
+    pub is_temporary: bool,
+}
+
+impl PreparedMedia {
+    fn unchanged(path: &Path, mime: &str, file_name: &str) -> PreparedMedia {
+        PreparedMedia {
+            path: path.to_path_buf(),
+            mime: mime.to_string(),
+            file_name: file_name.to_string(),
+            is_temporary: false,
+        }
+    }
+}
+
+/// Shrink a photo or video to `limits`, reporting progress from 0 to 1 while
+/// it is re-encoded. Other attachments, and media that already fit, are
+/// returned unchanged without reporting any.
+/// Blocks for as long as ffmpeg runs; call it from a blocking task.
+pub fn prepare_media(
+    path: &Path,
+    mime: &str,
+    file_name: &str,
+    limits: MediaLimits,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(f32),
+) -> Result<PreparedMedia, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let prepared = match mime {
+        "image/jpeg" | "image/png" | "image/webp" => prepare_image(path, file_name, size, limits)?,
+        _ if is_heif_family(mime) && limits.heif_to_jpeg => {
+            Some(prepare_heif(path, file_name, limits)?)
+        }
+        _ if mime.starts_with("video/") => {
+            prepare_video(path, file_name, size, limits, cancel, &mut on_progress)?
+        }
+        _ => None,
+    };
+    let prepared = prepared.unwrap_or_else(|| PreparedMedia::unchanged(path, mime, file_name));
+
+    let prepared_size = std::fs::metadata(&prepared.path)
+        .map_err(|e| e.to_string())?
+        .len();
+    if prepared_size > limits.max_bytes {
+        return Err(format!(
+            "attachment is {:.1} MB, the limit is {:.1} MB",
+            prepared_size as f64 / 1_048_576.0,
+            limits.max_bytes as f64 / 1_048_576.0
+        ));
+    }
+    // Unchanged files skip the preparing part of the progress bar
+    if prepared.is_temporary {
+        on_progress(1.0);
+    }
+    Ok(prepared)
+}
+
+fn output_path(extension: &str) -> PathBuf {
+    runtime_subdir("outgoing").join(format!("{}.{extension}", uuid::Uuid::new_v4()))
+}
+
+fn renamed(file_name: &str, extension: &str) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    format!("{stem}.{extension}")
+}
+
+fn prepare_image(
+    path: &Path,
+    file_name: &str,
+    size: u64,
+    limits: MediaLimits,
+) -> Result<Option<PreparedMedia>, String> {
+    let (width, height) = image::image_dimensions(path).map_err(|e| e.to_string())?;
+    let too_large = limits
+        .max_dimension
+        .is_some_and(|max| width.max(height) > max);
+    if !too_large && size <= limits.max_bytes {
+        return Ok(None);
+    }
+
+    let original = open_upright(path)?;
+    let mut max_dimension = limits.max_dimension.unwrap_or(width.max(height));
+    let mut quality = limits.jpeg_quality;
+
+    // Transparent images stay PNG if that is small enough
+    if original.color().has_alpha() {
+        let bytes = encode_png(&fit_within(&original, max_dimension))?;
+        if bytes.len() as u64 <= limits.max_bytes {
+            return write_prepared(&bytes, "png", "image/png", file_name).map(Some);
+        }
+    }
+
+    // Step quality and then size down until the JPEG fits
+    loop {
+        let bytes = encode_jpeg(&fit_within(&original, max_dimension), quality)?;
+        if bytes.len() as u64 <= limits.max_bytes || max_dimension <= 320 {
+            return write_prepared(&bytes, "jpg", "image/jpeg", file_name).map(Some);
+        }
+        if quality > 60 {
+            quality -= 10;
+        } else {
+            max_dimension = max_dimension * 3 / 4;
+        }
+    }
+}
+
+/// Decode a photo and turn it upright, since re-encoding drops the EXIF
+/// orientation that viewers would otherwise apply.
+fn open_upright(path: &Path) -> Result<DynamicImage, String> {
+    let mut decoder = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
+
+/// Convert a HEIF-family photo to JPEG, then shrink that like any other photo.
+fn prepare_heif(
+    path: &Path,
+    file_name: &str,
+    limits: MediaLimits,
+) -> Result<PreparedMedia, String> {
+    let jpeg = output_path("jpg");
+    if !convert_heif(path, &jpeg) {
+        let _ = std::fs::remove_file(&jpeg);
+        return Err("could not convert HEIC photo to JPEG".to_string());
+    }
+    let converted_name = renamed(file_name, "jpg");
+    let size = std::fs::metadata(&jpeg).map_err(|e| e.to_string())?.len();
+    match prepare_image(&jpeg, &converted_name, size, limits) {
+        Ok(Some(shrunk)) => {
+            let _ = std::fs::remove_file(&jpeg);
+            Ok(shrunk)
+        }
+        Ok(None) => Ok(PreparedMedia {
+            path: jpeg,
+            mime: "image/jpeg".to_string(),
+            file_name: converted_name,
+            is_temporary: true,
+        }),
+        Err(e) => {
+            let _ = std::fs::remove_file(&jpeg);
+            Err(e)
+        }
+    }
+}
+
+fn fit_within(image: &DynamicImage, max_dimension: u32) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    if width.max(height) <= max_dimension {
+        image.clone()
+    } else {
+        image.resize(max_dimension, max_dimension, FilterType::Lanczos3)
+    }
+}
+
+fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality);
+    image
+        .to_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes.into_inner())
+}
+
+fn write_prepared(
+    bytes: &[u8],
+    extension: &str,
+    mime: &str,
+    file_name: &str,
+) -> Result<PreparedMedia, String> {
+    let path = output_path(extension);
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    Ok(PreparedMedia {
+        path,
+        mime: mime.to_string(),
+        file_name: renamed(file_name, extension),
+        is_temporary: true,
+    })
+}
+
+/// Length of a video in seconds according to ffprobe.
+fn video_duration(path: &Path) -> Option<f64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()
+        .filter(|d: &f64| *d > 0.0)
+}
+
+/// Re-encode a video to H.264/AAC MP4. Videos with a byte limit they exceed
+/// get a bitrate computed from their length; the rest use `limits.video_crf`.
+fn prepare_video(
+    path: &Path,
+    file_name: &str,
+    size: u64,
+    limits: MediaLimits,
+    cancel: &AtomicBool,
+    on_progress: &mut impl FnMut(f32),
+) -> Result<Option<PreparedMedia>, String> {
+    if !limits.always_transcode_video && size <= limits.max_bytes {
+        return Ok(None);
+    }
+    let duration = video_duration(path);
+    let output = output_path("mp4");
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-v", "error", "-nostats", "-progress", "pipe:1", "-y", "-i"])
+        .arg(path)
+        .args(["-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p"])
+        .args(["-c:a", "aac", "-b:a", &format!("{VIDEO_AUDIO_KBPS}k")])
+        .args(["-movflags", "+faststart"]);
+    if let Some(max) = limits.max_dimension {
+        // Fit the longest side, keeping both sides even for x264
+        command.args([
+            "-vf",
+            &format!(
+                "scale='if(gt(iw,ih),min(iw,{max}),-2)':'if(gt(iw,ih),-2,min(ih,{max}))'"
+            ),
+        ]);
+    }
+    match duration {
+        Some(seconds) if size > limits.max_bytes || limits.max_bytes < RCS_MAX_BYTES => {
+            // 90% of the budget, leaving room for the container
+            let total_kbps = (limits.max_bytes as f64 * 8.0 * 0.9 / 1000.0 / seconds) as u64;
+            let video_kbps = total_kbps.saturating_sub(VIDEO_AUDIO_KBPS).max(50);
+            command.args([
+                "-b:v",
+                &format!("{video_kbps}k"),
+                "-maxrate",
+                &format!("{video_kbps}k"),
+                "-bufsize",
+                &format!("{}k", video_kbps * 2),
+            ]);
+        }
+        _ => {
+            command.args(["-crf", &limits.video_crf.to_string()]);
+        }
+    }
+    command.arg(&output).stdout(Stdio::piped()).stderr(Stdio::null());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        // Without ffmpeg a video that already fits is still better sent as is
+        Err(e) if size <= limits.max_bytes => {
+            eprintln!("ffmpeg: {e}, sending the video unchanged");
+            return Ok(None);
+        }
+        Err(e) => return Err(format!("ffmpeg: {e}")),
+    };
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if cancel.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                break;
+            }
+            let (Some(seconds), Some(micros)) = (duration, line.strip_prefix("out_time_us=")) else {
+                continue;
+            };
+            if let Ok(micros) = micros.parse::<f64>() {
+                on_progress((micros / 1_000_000.0 / seconds).clamp(0.0, 1.0) as f32);
+            }
+        }
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+
+    if cancel.load(Ordering::Relaxed) {
+        let _ = std::fs::remove_file(&output);
+        return Err("cancelled".to_string());
+    }
+    if !status.success() {
+        let _ = std::fs::remove_file(&output);
+        return Err(format!("ffmpeg exited with {status}"));
+    }
+    Ok(Some(PreparedMedia {
+        path: output,
+        mime: "video/mp4".to_string(),
+        file_name: renamed(file_name, "mp4"),
+        is_temporary: true,
+    }))
+}
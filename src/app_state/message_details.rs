@@ -0,0 +1,181 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use super::*;
+
+// ── Message details ──────────────────────────────────────────────
+
+#[derive(Clone, Default)]
+struct StoredDetails {
+    conversation_id: String,
+    participant_id: String,
+    status_code: i32,
+    transport_type: i64,
+    timestamp_micros: i64,
+    sim_number: i32,
+    media_parts: Vec<MediaPart>,
+    rich_content: RichContent,
+}
+
+/// Delivery details of messages in loaded conversations, keyed by message id.
+fn details_by_message() -> &'static RwLock<HashMap<String, StoredDetails>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<String, StoredDetails>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Remember the delivery state of a message from a load or an update event.
+pub fn record_message_details(message: &libgmessages_rs::proto::conversations::Message) {
+    let message_id = extract_message_id(message);
+    if message_id.is_empty() {
+        return;
+    }
+    let status_code = message
+        .message_status
+        .as_ref()
+        .map(|s| s.status)
+        .unwrap_or(0);
+
+    let mut store = details_by_message().write().unwrap();
+    let entry = store.entry(message_id).or_default();
+    entry.conversation_id = message.conversation_id.clone();
+    entry.participant_id = message.participant_id.clone();
+    entry.status_code = status_code;
+    entry.transport_type = message.r#type;
+    entry.timestamp_micros = message.timestamp;
+    entry.sim_number = message_sim_number(message);
+    let media_parts = extract_media_parts(message);
+    if !media_parts.is_empty() {
+        entry.media_parts = media_parts;
+    }
+    let rich_content = extract_rich_content(message);
+    if !rich_content.is_empty() {
+        entry.rich_content = rich_content;
+    }
+}
+
+/// Drop the details of a conversation that is no longer loaded.
+pub fn forget_message_details(conversation_id: &str) {
+    details_by_message()
+        .write()
+        .unwrap()
+        .retain(|_, details| details.conversation_id != conversation_id);
+}
+
+/// SIM number recorded for a message, 0 when unknown.
+pub fn recorded_sim_number(message_id: &str) -> i32 {
+    details_by_message()
+        .read()
+        .unwrap()
+        .get(message_id)
+        .map(|details| details.sim_number)
+        .unwrap_or(0)
+}
+
+/// Original file name and size recorded for a message's attachment.
+pub fn recorded_attachment(message_id: &str) -> (String, i64) {
+    details_by_message()
+        .read()
+        .unwrap()
+        .get(message_id)
+        .and_then(|details| details.media_parts.first())
+        .map(|part| (part.file_name.clone(), part.size))
+        .unwrap_or_default()
+}
+
+/// Every media part recorded for a message, in the order they were sent.
+pub fn recorded_media_parts(message_id: &str) -> Vec<MediaPart> {
+    details_by_message()
+        .read()
+        .unwrap()
+        .get(message_id)
+        .map(|details| details.media_parts.clone())
+        .unwrap_or_default()
+}
+
+/// Location, cards and suggestions recorded for a message, empty if none.
+pub fn recorded_rich_content(message_id: &str) -> RichContent {
+    details_by_message()
+        .read()
+        .unwrap()
+        .get(message_id)
+        .map(|details| details.rich_content.clone())
+        .unwrap_or_default()
+}
+
+/// Human-readable transport of a message from its `transport_type`.
+pub fn transport_name(transport_type: i64) -> &'static str {
+    match transport_type {
+        1 => "SMS",
+        2 | 3 => "MMS",
+        _ => "RCS",
+    }
+}
+
+fn format_exact_timestamp(timestamp_micros: i64) -> String {
+    if timestamp_micros <= 0 {
+        return String::new();
+    }
+    chrono::DateTime::<chrono::Utc>::from_timestamp_millis(timestamp_micros / 1000)
+        .map(|utc| {
+            utc.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Serialize)]
+pub struct RecipientDetails {
+    pub participant_id: String,
+    pub name: String,
+    pub number: String,
+}
+
+#[derive(Serialize)]
+pub struct MessageDetails {
+    pub message_id: String,
+    pub transport: &'static str,
+    pub status: &'static str,
+    pub status_code: i32,
+    pub timestamp_micros: i64,
+    pub time: String,
+    pub from_me: bool,
+    /// Other members of the conversation, for outgoing messages only. They
+    /// share `status`; there are no receipts per member.
+    pub recipients: Vec<RecipientDetails>,
+}
+
+/// Delivery details of a message. The phone reports a single status per
+/// message, so recipients are listed without one of their own.
+pub fn message_details(
+    conversation_id: &str,
+    message_id: &str,
+    me_participant_id: &str,
+) -> Option<MessageDetails> {
+    let stored = details_by_message().read().unwrap().get(message_id).cloned()?;
+    let from_me = !me_participant_id.is_empty() && stored.participant_id == me_participant_id;
+
+    let mut recipients = Vec::new();
+    if from_me {
+        let members = participants_for(conversation_id).unwrap_or_default();
+        for member in members.iter().filter(|p| !p.is_me) {
+            recipients.push(RecipientDetails {
+                participant_id: member.participant_id.clone(),
+                name: member.name.clone(),
+                number: member.number.clone(),
+            });
+        }
+    }
+
+    Some(MessageDetails {
+        message_id: message_id.to_string(),
+        transport: transport_name(stored.transport_type),
+        status: map_message_status(stored.status_code, from_me),
+        status_code: stored.status_code,
+        timestamp_micros: stored.timestamp_micros,
+        time: format_exact_timestamp(stored.timestamp_micros),
+        from_me,
+        recipients,
+    })
+}
@@ -4,9 +4,9 @@ use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use core::pin::Pin;
 use cxx_qt::{CxxQtThread, CxxQtType, Threading};
-use cxx_qt_lib::QString;
+use cxx_qt_lib::{QList, QString, QStringList};
 
-use libgmessages_rs::store::AuthDataStore;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -34,6 +34,8 @@ pub struct MessageItem {
     pub avatar_url: QString,
     pub is_info: bool,
     pub participant_id: String,
+    /// Display name of the sender, empty for my own messages.
+    pub sender_name: QString,
     pub mime_type: QString,
     pub thumbnail_url: QString,
     pub upload_progress: f32,
@@ -42,6 +44,89 @@ pub struct MessageItem {
     pub link_image_url: QString,
     pub media_width: i64,
     pub media_height: i64,
+    /// SIM the message went through, 0 when unknown or the phone's default.
+    pub sim_number: i32,
+    /// Length of an audio attachment, 0 until it has been analysed.
+    pub audio_duration_ms: i64,
+    /// Comma-separated 0–100 levels of an audio attachment.
+    pub waveform: QString,
+    /// Original name of an attachment as sent, empty when unknown.
+    pub file_name: QString,
+    /// Attachment size in bytes, 0 when unknown.
+    pub file_size: i64,
+    /// Every media part of the message, the first mirrored in the fields above.
+    pub attachments: Vec<Attachment>,
+    /// Shared location, business message cards and suggestion chips.
+    pub rich_content: RichContent,
+}
+
+/// One media part of a message, as listed by the `attachments` role.
+#[derive(Clone, Default, Serialize)]
+pub struct Attachment {
+    pub media_url: String,
+    pub thumbnail_url: String,
+    pub mime_type: String,
+    pub file_name: String,
+    pub file_size: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Media cache file the part is downloaded to.
+    #[serde(skip)]
+    pub cache_name: String,
+}
+
+impl MessageItem {
+    fn apply_audio_summary(&mut self, summary: &AudioSummary) {
+        self.audio_duration_ms = summary.duration_ms;
+        self.waveform = QString::from(summary.waveform_string().as_str());
+    }
+
+    /// Copy the first attachment into the single-media fields older delegates use.
+    fn sync_primary_attachment(&mut self) {
+        let Some(primary) = self.attachments.first() else {
+            return;
+        };
+        self.media_url = QString::from(primary.media_url.as_str());
+        self.thumbnail_url = QString::from(primary.thumbnail_url.as_str());
+        self.mime_type = QString::from(primary.mime_type.as_str());
+    }
+
+    /// Point the attachment cached as `cache_name` at its downloaded file.
+    fn apply_download(
+        &mut self,
+        cache_name: &str,
+        uri: &str,
+        thumb_uri: &str,
+        audio: Option<&AudioSummary>,
+    ) {
+        let index = self
+            .attachments
+            .iter()
+            .position(|attachment| attachment.cache_name == cache_name);
+        if let Some(attachment) = index.and_then(|i| self.attachments.get_mut(i)) {
+            attachment.media_url = uri.to_string();
+            if !thumb_uri.is_empty() {
+                attachment.thumbnail_url = thumb_uri.to_string();
+            }
+        }
+        // Only the first part, or a message that never listed its parts, is primary
+        if index == Some(0) || self.attachments.is_empty() {
+            self.media_url = QString::from(uri);
+            if !thumb_uri.is_empty() {
+                self.thumbnail_url = QString::from(thumb_uri);
+            }
+            if let Some(summary) = audio {
+                self.apply_audio_summary(summary);
+            }
+        }
+    }
+
+    /// The `attachments` role: every part as a JSON list. Delegates only
+    /// lay out an album for two or more; single attachments also fill the
+    /// plain media roles.
+    fn attachments_json(&self) -> String {
+        serde_json::to_string(&self.attachments).unwrap_or_else(|_| "[]".to_string())
+    }
 }
 
 pub struct MessageListRust {
@@ -60,6 +145,8 @@ pub struct MessageListRust {
     next_cursor: Option<libgmessages_rs::proto::client::Cursor>,
     pub loading_more: bool,
     upload_cancellations: HashMap<String, Arc<AtomicBool>>,
+    /// Files of failed sends kept for the bubble, keyed by its tmp id.
+    failed_send_files: HashMap<String, Vec<PathBuf>>,
 }
 
 impl Default for MessageListRust {
@@ -73,6 +160,7 @@ impl Default for MessageListRust {
             next_cursor: None,
             loading_more: false,
             upload_cancellations: HashMap::new(),
+            failed_send_files: HashMap::new(),
         }
     }
 }
@@ -123,6 +211,64 @@ impl crate::ffi::MessageList {
             18 => QVariant::from(&item.link_image_url),
             19 => QVariant::from(&item.media_width),
             20 => QVariant::from(&item.media_height),
+            21 => QVariant::from(&item.sender_name),
+            // Rows run newest first, so the chronologically previous message is row + 1
+            22 => {
+                let is_first = !matches!(
+                    self.messages.get(row + 1),
+                    Some(previous) if same_sender_group(previous, item)
+                );
+                QVariant::from(&is_first)
+            }
+            23 => {
+                let is_last = row == 0 || !same_sender_group(item, &self.messages[row - 1]);
+                QVariant::from(&is_last)
+            }
+            24 => {
+                let label = if item.from_me {
+                    sim_label(item.sim_number)
+                } else {
+                    String::new()
+                };
+                QVariant::from(&QString::from(label.as_str()))
+            }
+            25 => QVariant::from(&QString::from(transport_name(item.transport_type))),
+            26 => QVariant::from(&item.audio_duration_ms),
+            27 => QVariant::from(&item.waveform),
+            28 => QVariant::from(&item.file_name),
+            29 => QVariant::from(&item.file_size),
+            30 => QVariant::from(&QString::from(item.attachments_json().as_str())),
+            31 => QVariant::from(&item.rich_content.location.is_some()),
+            32 => QVariant::from(
+                &item
+                    .rich_content
+                    .location
+                    .as_ref()
+                    .map_or(0.0, |l| l.latitude),
+            ),
+            33 => QVariant::from(
+                &item
+                    .rich_content
+                    .location
+                    .as_ref()
+                    .map_or(0.0, |l| l.longitude),
+            ),
+            34 => QVariant::from(&QString::from(
+                item.rich_content
+                    .location
+                    .as_ref()
+                    .map_or("", |l| l.label.as_str()),
+            )),
+            35 => QVariant::from(&QString::from(
+                item.rich_content
+                    .location
+                    .as_ref()
+                    .map_or("", |l| l.url.as_str()),
+            )),
+            36 => QVariant::from(&QString::from(item.rich_content.cards_json().as_str())),
+            37 => QVariant::from(&QString::from(
+                item.rich_content.suggestions_json().as_str(),
+            )),
             _ => QVariant::default(),
         }
     }
@@ -150,9 +296,39 @@ impl crate::ffi::MessageList {
         roles.insert(18, "link_image_url".into());
         roles.insert(19, "media_width".into());
         roles.insert(20, "media_height".into());
+        roles.insert(21, "sender_name".into());
+        roles.insert(22, "is_first_in_group".into());
+        roles.insert(23, "is_last_in_group".into());
+        roles.insert(24, "sim_label".into());
+        roles.insert(25, "transport_label".into());
+        roles.insert(26, "audio_duration_ms".into());
+        roles.insert(27, "waveform".into());
+        roles.insert(28, "file_name".into());
+        roles.insert(29, "file_size".into());
+        roles.insert(30, "attachments".into());
+        roles.insert(31, "has_location".into());
+        roles.insert(32, "latitude".into());
+        roles.insert(33, "longitude".into());
+        roles.insert(34, "location_label".into());
+        roles.insert(35, "location_url".into());
+        roles.insert(36, "rich_cards".into());
+        roles.insert(37, "suggestions".into());
         roles
     }
 
+    /// Refresh the rows `above` and `below` an insert or removal, whose
+    /// `is_first_in_group`/`is_last_in_group` may have changed. Rows outside
+    /// the list are ignored.
+    fn refresh_group_edges(mut self: Pin<&mut Self>, above: i32, below: i32) {
+        let row_count = self.rust().messages.len() as i32;
+        for row in [above, below] {
+            if row >= 0 && row < row_count {
+                let model_index = self.as_ref().index(row, 0, &QModelIndex::default());
+                self.as_mut().data_changed(&model_index, &model_index);
+            }
+        }
+    }
+
     pub fn load(mut self: Pin<&mut Self>, conversation_id: &QString) {
         let conversation_id = conversation_id.to_string();
         if conversation_id.is_empty() {
@@ -192,7 +368,7 @@ impl crate::ffi::MessageList {
                 (
                     Vec<MessageItem>,
                     String,
-                    Vec<(String, String, Vec<u8>, String)>,
+                    Vec<(String, String, String)>,
                     Vec<String>,
                     Vec<(String, String)>,
                     Option<libgmessages_rs::proto::client::Cursor>,
@@ -238,8 +414,17 @@ impl crate::ffi::MessageList {
 
                 let mut me_participant_id = String::new();
                 let mut avatar_identifiers: Vec<(String, String)> = Vec::new();
+                let mut avatar_by_participant_id: HashMap<String, String> = HashMap::new();
+                let mut name_by_participant_id: HashMap<String, String> = HashMap::new();
 
                 if let Some(convo) = convo_response.conversation.as_ref() {
+                    let participants: Vec<Participant> =
+                        convo.participants.iter().map(Participant::from_proto).collect();
+                    for member in &participants {
+                        name_by_participant_id
+                            .insert(member.participant_id.clone(), member.name.clone());
+                    }
+                    store_participants(&conversation_id, participants);
                     for p in &convo.participants {
                         let pid =
                             p.id.as_ref()
@@ -254,13 +439,20 @@ impl crate::ffi::MessageList {
                                 pid.clone()
                             };
                             if !identifier.is_empty() {
+                                // Initials until the photo (if any) is fetched
+                                let name = if p.full_name.is_empty() {
+                                    &p.formatted_number
+                                } else {
+                                    &p.full_name
+                                };
+                                avatar_by_participant_id
+                                    .insert(pid.clone(), generated_avatar_url(&identifier, name));
                                 avatar_identifiers.push((pid, identifier));
                             }
                         }
                     }
                 }
 
-                let mut avatar_by_participant_id: HashMap<String, String> = HashMap::new();
                 let mut identifiers_to_fetch = Vec::new();
 
                 {
@@ -276,14 +468,22 @@ impl crate::ffi::MessageList {
                     }
                 }
 
+                seed_gallery(
+                    &conversation_id,
+                    &response.messages,
+                    response.cursor.clone(),
+                );
+
                 let mut media_downloads = Vec::new();
                 let mut messages: Vec<MessageItem> = response
                     .messages
                     .into_iter()
                     .filter_map(|message| {
+                        record_message_details(&message);
                         let body = extract_message_body(&message);
-                        let media = extract_message_media(&message);
-                        if body.is_empty() && media.is_none() {
+                        let parts = extract_media_parts(&message);
+                        let rich_content = extract_rich_content(&message);
+                        if body.is_empty() && parts.is_empty() && rich_content.is_empty() {
                             return None;
                         }
                         let from_me = !me_participant_id.is_empty()
@@ -296,15 +496,10 @@ impl crate::ffi::MessageList {
                         let status = map_message_status(status_code, from_me);
                         let message_id = extract_message_id(&message);
 
-                        let is_media = media.is_some();
-                        let (media_mime, media_width, media_height) =
-                            if let Some((id, key, ref mime, w, h)) = media {
-                                let m = mime.clone();
-                                media_downloads.push((message_id.clone(), id, key, mime.clone()));
-                                (m, w, h)
-                            } else {
-                                (String::new(), 0, 0)
-                            };
+                        let is_media = !parts.is_empty();
+                        let attachments =
+                            attachments_for(&message_id, &parts, &mut media_downloads);
+                        let primary = attachments.first().cloned().unwrap_or_default();
 
                         let mut avatar_url = String::new();
                         if !from_me && !message.participant_id.is_empty() {
@@ -321,19 +516,34 @@ impl crate::ffi::MessageList {
                             timestamp_micros: message.timestamp,
                             message_id,
                             status: QString::from(status),
-                            media_url: QString::from(""),
+                            media_url: QString::from(primary.media_url.as_str()),
                             is_media,
                             avatar_url: QString::from(avatar_url.as_str()),
                             is_info: status_code >= 200,
                             participant_id: message.participant_id.clone(),
-                            mime_type: QString::from(media_mime.as_str()),
+                            sender_name: QString::from(if from_me {
+                                ""
+                            } else {
+                                name_by_participant_id
+                                    .get(&message.participant_id)
+                                    .map(String::as_str)
+                                    .unwrap_or("")
+                            }),
+                            mime_type: QString::from(primary.mime_type.as_str()),
                             thumbnail_url: QString::from(""),
                             upload_progress: 1.0,
                             link_url: QString::from(""),
                             link_title: QString::from(""),
                             link_image_url: QString::from(""),
-                            media_width,
-                            media_height,
+                            media_width: primary.width,
+                            media_height: primary.height,
+                            sim_number: message_sim_number(&message),
+                            audio_duration_ms: 0,
+                            waveform: QString::from(""),
+                            file_name: QString::from(primary.file_name.as_str()),
+                            file_size: primary.file_size,
+                            attachments,
+                            rich_content,
                         })
                     })
                     .collect();
@@ -375,15 +585,22 @@ impl crate::ffi::MessageList {
                     let convo_id = conversation_id.clone();
 
                     // Collect link targets BEFORE new_messages is moved into the closure
-                    let link_targets: Vec<(String, String)> = new_messages
+                    let link_targets: Vec<(String, String, bool, String)> = new_messages
                         .iter()
                         .filter(|m| !m.is_info && !m.is_media)
                         .filter_map(|m| {
                             let body = m.body.to_string();
-                            crate::app_state::utils::extract_first_url(&body)
-                                .map(|url| (m.message_id.clone(), url))
+                            crate::app_state::utils::extract_first_url(&body).map(|url| {
+                                (
+                                    m.message_id.clone(),
+                                    url,
+                                    m.from_me,
+                                    m.participant_id.clone(),
+                                )
+                            })
                         })
                         .collect();
+                    let card_targets = card_image_targets(&new_messages);
 
                     let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
                         let mut rust = qobject.as_mut().rust_mut();
@@ -427,6 +644,7 @@ impl crate::ffi::MessageList {
                                     );
                                     qobject.as_mut().rust_mut().messages.remove(i);
                                     qobject.as_mut().end_remove_rows();
+                                    qobject.as_mut().refresh_group_edges(i as i32 - 1, i as i32);
                                 }
 
                                 // 2. Diff updates and inserts
@@ -479,6 +697,9 @@ impl crate::ffi::MessageList {
                                         rust.messages.insert(pos, new_msg.clone());
                                         drop(rust);
                                         qobject.as_mut().end_insert_rows();
+                                        qobject
+                                            .as_mut()
+                                            .refresh_group_edges(pos as i32 - 1, pos as i32 + 1);
                                     }
                                 }
 
@@ -489,6 +710,7 @@ impl crate::ffi::MessageList {
                                 if rust.cache.len() >= 10 && !rust.cache.contains_key(&convo_id) {
                                     let key_to_remove = rust.cache.keys().next().cloned().unwrap();
                                     rust.cache.remove(&key_to_remove);
+                                    forget_message_details(&key_to_remove);
                                 }
                                 rust.cache.insert(
                                     convo_id.clone(),
@@ -499,6 +721,7 @@ impl crate::ffi::MessageList {
                             if rust.cache.len() >= 10 && !rust.cache.contains_key(&convo_id) {
                                 let key_to_remove = rust.cache.keys().next().cloned().unwrap();
                                 rust.cache.remove(&key_to_remove);
+                                forget_message_details(&key_to_remove);
                             }
                             rust.cache.insert(
                                 convo_id.clone(),
@@ -508,76 +731,7 @@ impl crate::ffi::MessageList {
                     });
 
                     // Start background download of media
-                    if !media_downloads.is_empty() {
-                        let ui_for_media = qt_thread.clone();
-                        spawn(async move {
-                            if let Some(client) = get_client().await {
-                                for (msg_id, media_id, key, mime) in media_downloads {
-                                    let ext = crate::app_state::utils::mime_to_extension(&mime);
-                                    let safe_id = media_id
-                                        .replace("/", "_")
-                                        .replace("+", "_")
-                                        .replace("=", "")
-                                        .replace("-", "_");
-                                    let safe_id = if safe_id.is_empty() {
-                                        msg_id.replace("-", "_")
-                                    } else {
-                                        safe_id
-                                    };
-
-                                    let tmp_dir = std::env::temp_dir().join("kourier_media");
-                                    let _ = std::fs::create_dir_all(&tmp_dir);
-                                    let path = tmp_dir.join(format!("{}.{}", safe_id, ext));
-
-                                    if !path.exists() {
-                                        if let Ok(data) =
-                                            client.download_media(&media_id, &key).await
-                                        {
-                                            let _ = crate::app_state::utils::media_data_to_uri(
-                                                &data, &mime, &safe_id,
-                                            );
-                                        }
-                                    }
-
-                                    if path.exists() {
-                                        let uri = format!("file://{}", path.to_string_lossy());
-                                        let thumb_uri = if mime.starts_with("video/") {
-                                            crate::app_state::utils::generate_video_thumbnail(&path)
-                                                .unwrap_or_default()
-                                        } else {
-                                            String::new()
-                                        };
-                                        let _ = ui_for_media.queue(
-                                            move |mut qobject: Pin<&mut ffi::MessageList>| {
-                                                let mut rust = qobject.as_mut().rust_mut();
-                                                if let Some(pos) = rust
-                                                    .messages
-                                                    .iter()
-                                                    .position(|m| m.message_id == msg_id)
-                                                {
-                                                    rust.messages[pos].media_url =
-                                                        QString::from(uri.as_str());
-                                                    if !thumb_uri.is_empty() {
-                                                        rust.messages[pos].thumbnail_url =
-                                                            QString::from(thumb_uri.as_str());
-                                                    }
-                                                    drop(rust);
-                                                    let model_index = qobject.as_ref().index(
-                                                        pos as i32,
-                                                        0,
-                                                        &QModelIndex::default(),
-                                                    );
-                                                    qobject
-                                                        .as_mut()
-                                                        .data_changed(&model_index, &model_index);
-                                                }
-                                            },
-                                        );
-                                    }
-                                }
-                            }
-                        });
-                    }
+                    download_attachments(qt_thread.clone(), media_downloads);
 
                     // Start background avatar load
                     if !identifiers_to_fetch.is_empty() {
@@ -649,55 +803,26 @@ impl crate::ffi::MessageList {
 
                         if !link_targets.is_empty() {
                             spawn(async move {
-                                for (msg_id, url) in link_targets {
+                                for (msg_id, url, from_me, participant_id) in link_targets {
+                                    let allowed = link_preview_allowed_fetching(
+                                        &url,
+                                        from_me,
+                                        &convo_id_for_links,
+                                        &participant_id,
+                                    )
+                                    .await;
+                                    if !allowed {
+                                        continue;
+                                    }
                                     let og = crate::app_state::utils::fetch_og_metadata(&url).await;
                                     let Some(og) = og else { continue };
 
                                     // Download the OG image to disk cache if available
-                                    let image_file_url = if !og.image.is_empty() {
-                                        let cache_dir =
-                                            std::env::temp_dir().join("kourier_link_previews");
-                                        let _ = std::fs::create_dir_all(&cache_dir);
-                                        // Create a stable filename from the image URL hash
-                                        let hash = {
-                                            use std::collections::hash_map::DefaultHasher;
-                                            use std::hash::{Hash, Hasher};
-                                            let mut h = DefaultHasher::new();
-                                            og.image.hash(&mut h);
-                                            h.finish()
-                                        };
-                                        let ext = if og.image.contains(".png") {
-                                            "png"
-                                        } else if og.image.contains(".webp") {
-                                            "webp"
-                                        } else {
-                                            "jpg"
-                                        };
-                                        let path = cache_dir.join(format!("{:016x}.{}", hash, ext));
-
-                                        if path.exists() {
-                                            format!("file://{}", path.to_string_lossy())
-                                        } else {
-                                            // Download the image
-                                            match reqwest::get(&og.image).await {
-                                                Ok(resp) if resp.status().is_success() => {
-                                                    match resp.bytes().await {
-                                                        Ok(bytes) if !bytes.is_empty() => {
-                                                            let _ = std::fs::write(&path, &bytes);
-                                                            format!(
-                                                                "file://{}",
-                                                                path.to_string_lossy()
-                                                            )
-                                                        }
-                                                        _ => String::new(),
-                                                    }
-                                                }
-                                                _ => String::new(),
-                                            }
-                                        }
-                                    } else {
-                                        String::new()
-                                    };
+                                    let image_file_url =
+                                        crate::app_state::utils::cache_link_preview_image(
+                                            &og.image,
+                                        )
+                                        .await;
 
                                     let title = og.title;
                                     let link = og.url;
@@ -735,6 +860,13 @@ impl crate::ffi::MessageList {
                             });
                         }
                     }
+
+                    // Queued after the messages above, so their rows exist by then
+                    spawn_card_image_fetch(
+                        qt_thread.clone(),
+                        conversation_id.clone(),
+                        card_targets,
+                    );
                 }
                 Err(error) => {
                     let is_auth_error = error.contains("authentication credential")
@@ -774,7 +906,7 @@ impl crate::ffi::MessageList {
             let result: Result<
                 (
                     Vec<MessageItem>,
-                    Vec<(String, String, Vec<u8>, String)>,
+                    Vec<(String, String, String)>,
                     Option<libgmessages_rs::proto::client::Cursor>,
                 ),
                 String,
@@ -803,9 +935,11 @@ impl crate::ffi::MessageList {
                     .messages
                     .into_iter()
                     .filter_map(|message| {
+                        record_message_details(&message);
                         let body = extract_message_body(&message);
-                        let media = extract_message_media(&message);
-                        if body.is_empty() && media.is_none() {
+                        let parts = extract_media_parts(&message);
+                        let rich_content = extract_rich_content(&message);
+                        if body.is_empty() && parts.is_empty() && rich_content.is_empty() {
                             return None;
                         }
                         let from_me = !me_id.is_empty() && message.participant_id == me_id;
@@ -817,15 +951,10 @@ impl crate::ffi::MessageList {
                         let status = map_message_status(status_code, from_me);
                         let message_id = extract_message_id(&message);
 
-                        let is_media = media.is_some();
-                        let (media_mime, media_width, media_height) =
-                            if let Some((id, key, ref mime, w, h)) = media {
-                                let m = mime.clone();
-                                media_downloads.push((message_id.clone(), id, key, mime.clone()));
-                                (m, w, h)
-                            } else {
-                                (String::new(), 0, 0)
-                            };
+                        let is_media = !parts.is_empty();
+                        let attachments =
+                            attachments_for(&message_id, &parts, &mut media_downloads);
+                        let primary = attachments.first().cloned().unwrap_or_default();
 
                         Some(MessageItem {
                             body: cxx_qt_lib::QString::from(body),
@@ -834,19 +963,31 @@ impl crate::ffi::MessageList {
                             timestamp_micros: message.timestamp,
                             message_id,
                             status: cxx_qt_lib::QString::from(status),
-                            media_url: cxx_qt_lib::QString::from(""),
+                            media_url: cxx_qt_lib::QString::from(primary.media_url.as_str()),
                             is_media,
                             avatar_url: cxx_qt_lib::QString::from(""),
                             is_info: status_code >= 200,
                             participant_id: message.participant_id.clone(),
-                            mime_type: cxx_qt_lib::QString::from(media_mime.as_str()),
+                            sender_name: cxx_qt_lib::QString::from(if from_me {
+                                String::new()
+                            } else {
+                                sender_name(&conversation_id, &message.participant_id)
+                            }),
+                            mime_type: cxx_qt_lib::QString::from(primary.mime_type.as_str()),
                             thumbnail_url: cxx_qt_lib::QString::from(""),
                             upload_progress: 1.0,
                             link_url: cxx_qt_lib::QString::from(""),
                             link_title: cxx_qt_lib::QString::from(""),
                             link_image_url: cxx_qt_lib::QString::from(""),
-                            media_width,
-                            media_height,
+                            media_width: primary.width,
+                            media_height: primary.height,
+                            sim_number: message_sim_number(&message),
+                            audio_duration_ms: 0,
+                            waveform: QString::from(""),
+                            file_name: QString::from(primary.file_name.as_str()),
+                            file_size: primary.file_size,
+                            attachments,
+                            rich_content,
                         })
                     })
                     .collect();
@@ -861,6 +1002,7 @@ impl crate::ffi::MessageList {
                 Ok((new_messages, media_downloads, new_cursor)) => {
                     let convo_id = conversation_id.clone();
                     let qt_thread_clone = qt_thread.clone();
+                    let card_targets = card_image_targets(&new_messages);
                     let _ = qt_thread.queue(
                         move |mut qobject: core::pin::Pin<&mut ffi::MessageList>| {
                             qobject.as_mut().set_loading_more(false);
@@ -911,6 +1053,10 @@ impl crate::ffi::MessageList {
                                     rust.cache.insert(selected, (msgs, me_p, cur));
                                     drop(rust);
                                     qobject.as_mut().end_insert_rows();
+                                    // Only the old last row borders the new ones
+                                    qobject
+                                        .as_mut()
+                                        .refresh_group_edges(insert_pos as i32 - 1, -1);
                                 } else {
                                     let mut rust = qobject.as_mut().rust_mut();
                                     let msgs = rust.messages.clone();
@@ -923,80 +1069,8 @@ impl crate::ffi::MessageList {
                         },
                     );
 
-                    if !media_downloads.is_empty() {
-                        let ui_for_media = qt_thread_clone.clone();
-                        crate::app_state::shared::spawn(async move {
-                            if let Some(client) = crate::app_state::shared::get_client().await {
-                                for (msg_id, media_id, key, mime) in media_downloads {
-                                    let ext = crate::app_state::utils::mime_to_extension(&mime);
-                                    let safe_id = media_id
-                                        .replace("/", "_")
-                                        .replace("+", "_")
-                                        .replace("=", "")
-                                        .replace("-", "_");
-                                    let safe_id = if safe_id.is_empty() {
-                                        msg_id.replace("-", "_")
-                                    } else {
-                                        safe_id
-                                    };
-
-                                    let tmp_dir = std::env::temp_dir().join("kourier_media");
-                                    let _ = std::fs::create_dir_all(&tmp_dir);
-                                    let path = tmp_dir.join(format!("{}.{}", safe_id, ext));
-
-                                    if !path.exists() {
-                                        if let Ok(data) =
-                                            client.download_media(&media_id, &key).await
-                                        {
-                                            let _ = crate::app_state::utils::media_data_to_uri(
-                                                &data, &mime, &safe_id,
-                                            );
-                                        }
-                                    }
-
-                                    if path.exists() {
-                                        let uri = format!("file://{}", path.to_string_lossy());
-                                        let thumb_uri = if mime.starts_with("video/") {
-                                            crate::app_state::utils::generate_video_thumbnail(&path)
-                                                .unwrap_or_default()
-                                        } else {
-                                            String::new()
-                                        };
-                                        let _ = ui_for_media.queue(
-                                            move |mut qobject: core::pin::Pin<
-                                                &mut ffi::MessageList,
-                                            >| {
-                                                let mut rust = qobject.as_mut().rust_mut();
-                                                if let Some(pos) = rust
-                                                    .messages
-                                                    .iter()
-                                                    .position(|m| m.message_id == msg_id)
-                                                {
-                                                    rust.messages[pos].media_url =
-                                                        cxx_qt_lib::QString::from(uri.as_str());
-                                                    if !thumb_uri.is_empty() {
-                                                        rust.messages[pos].thumbnail_url =
-                                                            cxx_qt_lib::QString::from(
-                                                                thumb_uri.as_str(),
-                                                            );
-                                                    }
-                                                    drop(rust);
-                                                    let model_index = qobject.as_ref().index(
-                                                        pos as i32,
-                                                        0,
-                                                        &crate::ffi::QModelIndex::default(),
-                                                    );
-                                                    qobject
-                                                        .as_mut()
-                                                        .data_changed(&model_index, &model_index);
-                                                }
-                                            },
-                                        );
-                                    }
-                                }
-                            }
-                        });
-                    }
+                    download_attachments(qt_thread_clone.clone(), media_downloads);
+                    spawn_card_image_fetch(qt_thread_clone, conversation_id, card_targets);
                 }
                 Err(_error) => {
                     let _ = qt_thread.queue(
@@ -1009,7 +1083,20 @@ impl crate::ffi::MessageList {
         });
     }
 
-    pub fn send_message(mut self: Pin<&mut Self>, text: &QString) {
+    /// Send a text message. `options` is a JSON object of per-send
+    /// overrides, see `SendOptions`; empty uses what is remembered for the
+    /// conversation.
+    pub fn send_message(self: Pin<&mut Self>, text: &QString, options: &QString) {
+        let options = SendOptions::parse(options, &self.rust().selected_conversation_id);
+        self.send_text(text, options);
+    }
+
+    fn send_text(mut self: Pin<&mut Self>, text: &QString, options: SendOptions) {
+        let SendOptions {
+            sim_number,
+            transport,
+            ..
+        } = options;
         let body = text.to_string().trim().to_string();
         if body.is_empty() {
             return;
@@ -1022,6 +1109,7 @@ impl crate::ffi::MessageList {
 
         let now_micros = chrono::Utc::now().timestamp_micros();
         let tmp_id = Uuid::new_v4().to_string().to_lowercase();
+        let transport_type = optimistic_transport_type(&self.rust().messages, transport, false);
 
         // Optimistic insert
         let insert_pos = 0;
@@ -1033,7 +1121,7 @@ impl crate::ffi::MessageList {
             MessageItem {
                 body: QString::from(body.clone()),
                 from_me: true,
-                transport_type: 4,
+                transport_type,
                 timestamp_micros: now_micros,
                 message_id: tmp_id.clone(),
                 status: QString::from("sending"),
@@ -1042,6 +1130,7 @@ impl crate::ffi::MessageList {
                 avatar_url: QString::from(""),
                 is_info: false,
                 participant_id: String::new(),
+                sender_name: QString::from(""),
                 mime_type: QString::from(""),
                 thumbnail_url: QString::from(""),
                 upload_progress: 1.0,
@@ -1050,20 +1139,35 @@ impl crate::ffi::MessageList {
                 link_image_url: QString::from(""),
                 media_width: 0,
                 media_height: 0,
+                sim_number,
+                audio_duration_ms: 0,
+                waveform: QString::from(""),
+                file_name: QString::from(""),
+                file_size: 0,
+                attachments: Vec::new(),
+                rich_content: RichContent::default(),
             },
         );
         // We do not sort here because the new message naturally belongs at the beginning (index 0).
         // It prevents scroll position reset issues.
         drop(rust);
         self.as_mut().end_insert_rows();
+        self.as_mut().refresh_group_edges(-1, insert_pos + 1);
 
         let qt_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
         let tmp_id_for_fail = tmp_id.clone();
 
         spawn(async move {
             let result: Result<(), String> = async {
+                let sim_payload = sim_payload(sim_number).await;
                 let client = ensure_client().await?;
                 let handler = make_handler(&client).await?;
+                // A remembered SMS preference already set the send mode
+                let sms_override = transport == TransportPreference::Sms
+                    && conversation_transport(&conversation_id) != TransportPreference::Sms;
+                if sms_override {
+                    set_sms_send_mode(&handler, &conversation_id, true).await?;
+                }
 
                 let message_info = libgmessages_rs::proto::conversations::MessageInfo {
                     action_message_id: None,
@@ -1094,9 +1198,9 @@ impl crate::ffi::MessageList {
                 let request = libgmessages_rs::proto::client::SendMessageRequest {
                     conversation_id: conversation_id.clone(),
                     message_payload: Some(payload),
-                    sim_payload: None,
+                    sim_payload,
                     tmp_id,
-                    force_rcs: false,
+                    force_rcs: transport == TransportPreference::Rcs,
                     reply: None,
                 };
 
@@ -1120,8 +1224,7 @@ impl crate::ffi::MessageList {
                     || error.contains("403");
                 if is_auth_error {
                     clear_client().await;
-                    let store = AuthDataStore::default_store();
-                    if let Err(e) = store.delete() {
+                    if let Err(e) = delete_auth().await {
                         eprintln!("auth delete failed: {e}");
                     }
                 }
@@ -1144,106 +1247,141 @@ impl crate::ffi::MessageList {
         });
     }
 
-    pub fn send_media(mut self: Pin<&mut Self>, file_url: &QString, text: &QString) {
-        let file_path = file_url.to_string();
-        let path = if file_path.starts_with("file://") {
-            file_path[7..].to_string()
-        } else {
-            file_path
-        };
-
-        if path.is_empty() {
-            return;
-        }
+    /// Send one or more files and a caption as one message, shown as an
+    /// album when there are several. `options` is as for `send_message`.
+    pub fn send_media(
+        self: Pin<&mut Self>,
+        file_urls: &QStringList,
+        text: &QString,
+        options: &QString,
+    ) {
+        let file_urls: Vec<String> = QList::<QString>::from(file_urls)
+            .iter()
+            .map(|url| url.to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        let options = SendOptions::parse(options, &self.rust().selected_conversation_id);
+        self.send_attachment(&file_urls, text, options);
+    }
 
+    fn send_attachment(
+        self: Pin<&mut Self>,
+        file_urls: &[String],
+        text: &QString,
+        options: SendOptions,
+    ) {
         let conversation_id = self.rust().selected_conversation_id.clone();
         if conversation_id.is_empty() {
             return;
         }
 
-        let now_micros = chrono::Utc::now().timestamp_micros();
-        let tmp_id = Uuid::new_v4().to_string().to_lowercase();
+        let file_urls = file_urls.to_vec();
         let caption = text.to_string().trim().to_string();
+        let qt_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
+        spawn(async move {
+            // Stat and sniff the files off the UI thread
+            let files = tokio::task::spawn_blocking(move || {
+                file_urls
+                    .iter()
+                    .map(|url| outgoing_file(url))
+                    .collect::<Result<Vec<_>, String>>()
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                match files {
+                    Ok(files) if files.is_empty() => {}
+                    Ok(files) => {
+                        qobject.start_attachment_send(conversation_id, files, caption, options)
+                    }
+                    // QML has already cleared the composer, so say why nothing was sent
+                    Err(error) => {
+                        eprintln!("send_media: {error}");
+                        qobject.as_mut().send_failed(&QString::from(error.as_str()));
+                    }
+                }
+            });
+        });
+    }
 
-        // Reject files over 100 MB (RCS limit) — metadata is instant, no file read
-        const MAX_BYTES: u64 = 100 * 1024 * 1024;
-        match std::fs::metadata(&path) {
-            Ok(meta) if meta.len() > MAX_BYTES => {
-                eprintln!(
-                    "send_media: file too large ({:.1} MB), RCS limit is 100 MB",
-                    meta.len() as f64 / 1_048_576.0
-                );
-                return;
-            }
-            Err(e) => {
-                eprintln!("send_media: cannot stat file: {e}");
-                return;
-            }
-            _ => {}
-        }
-
-        // Lightweight: determine MIME and file name from extension only (no I/O)
-        let path_obj = std::path::Path::new(&path);
-        let file_name = path_obj
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let ext = path_obj
-            .extension()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_lowercase();
-        let mime_type = match ext.as_str() {
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "mp4" => "video/mp4",
-            "webm" => "video/webm",
-            "webp" => "image/webp",
-            _ => "application/octet-stream",
-        }
-        .to_string();
+    /// Show the sending bubble for resolved files and upload them.
+    fn start_attachment_send(
+        mut self: Pin<&mut Self>,
+        conversation_id: String,
+        files: Vec<OutgoingFile>,
+        caption: String,
+        options: SendOptions,
+    ) {
+        let SendOptions {
+            sim_number,
+            transport,
+            strip_metadata,
+        } = options;
+        let now_micros = chrono::Utc::now().timestamp_micros();
+        let tmp_id = Uuid::new_v4().to_string().to_lowercase();
 
-        // Use the original file path as preview — no need to copy gigabytes on the UI thread
-        let preview_uri = format!("file://{}", path);
+        // Use the original file paths as previews — no need to copy gigabytes on the UI thread
+        let attachments: Vec<Attachment> = files
+            .iter()
+            .map(|file| Attachment {
+                media_url: format!("file://{}", file.path),
+                mime_type: file.mime.clone(),
+                file_name: file.file_name.clone(),
+                file_size: file.size as i64,
+                ..Default::default()
+            })
+            .collect();
+        let primary = attachments[0].clone();
 
         let body_text = if caption.is_empty() {
             String::new()
         } else {
             caption.clone()
         };
+        let transport_type = optimistic_transport_type(&self.rust().messages, transport, true);
 
+        // Sent even if another conversation was opened while the files were read
         let insert_pos = 0;
-        self.as_mut()
-            .begin_insert_rows(&QModelIndex::default(), insert_pos, insert_pos);
-        let mut rust = self.as_mut().rust_mut();
-        rust.messages.insert(
-            0,
-            MessageItem {
-                body: QString::from(body_text.as_str()),
-                from_me: true,
-                transport_type: 4,
-                timestamp_micros: now_micros,
-                message_id: tmp_id.clone(),
-                status: QString::from("sending"),
-                media_url: QString::from(preview_uri.as_str()),
-                is_media: true,
-                avatar_url: QString::from(""),
-                is_info: false,
-                participant_id: String::new(),
-                mime_type: QString::from(mime_type.as_str()),
-                thumbnail_url: QString::from(""),
-                upload_progress: 0.0,
-                link_url: QString::from(""),
-                link_title: QString::from(""),
-                link_image_url: QString::from(""),
-                media_width: 0,
-                media_height: 0,
-            },
-        );
-        drop(rust);
-        self.as_mut().end_insert_rows();
+        if self.rust().selected_conversation_id == conversation_id {
+            self.as_mut()
+                .begin_insert_rows(&QModelIndex::default(), insert_pos, insert_pos);
+            let mut rust = self.as_mut().rust_mut();
+            rust.messages.insert(
+                0,
+                MessageItem {
+                    body: QString::from(body_text.as_str()),
+                    from_me: true,
+                    transport_type,
+                    timestamp_micros: now_micros,
+                    message_id: tmp_id.clone(),
+                    status: QString::from("sending"),
+                    media_url: QString::from(primary.media_url.as_str()),
+                    is_media: true,
+                    avatar_url: QString::from(""),
+                    is_info: false,
+                    participant_id: String::new(),
+                    sender_name: QString::from(""),
+                    mime_type: QString::from(primary.mime_type.as_str()),
+                    thumbnail_url: QString::from(""),
+                    upload_progress: 0.0,
+                    link_url: QString::from(""),
+                    link_title: QString::from(""),
+                    link_image_url: QString::from(""),
+                    media_width: 0,
+                    media_height: 0,
+                    sim_number,
+                    audio_duration_ms: 0,
+                    waveform: QString::from(""),
+                    file_name: QString::from(primary.file_name.as_str()),
+                    file_size: primary.file_size,
+                    attachments,
+                    rich_content: RichContent::default(),
+                },
+            );
+            drop(rust);
+            self.as_mut().end_insert_rows();
+            self.as_mut().refresh_group_edges(-1, insert_pos + 1);
+        }
 
         let qt_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
         let tmp_id_for_fail = tmp_id.clone();
@@ -1256,19 +1394,19 @@ impl crate::ffi::MessageList {
             .insert(tmp_id.clone(), cancel_flag.clone());
 
         spawn(async move {
-            // Generate video thumbnail in the background (quick: ffmpeg only extracts one frame)
-            if mime_type.starts_with("video/") {
-                let thumb_path = path.clone();
-                let thumb_tmp = tmp_id.clone();
-                let ui_for_thumb = qt_thread.clone();
-                let p = std::path::Path::new(&thumb_path);
-                if let Some(thumb_uri) = crate::app_state::utils::generate_video_thumbnail(p) {
-                    let _ = ui_for_thumb.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+            let send_files: Vec<PathBuf> =
+                files.iter().map(|file| PathBuf::from(&file.path)).collect();
+
+            // Voice messages show their length and waveform while uploading
+            if files.len() == 1 && files[0].mime.starts_with("audio/") {
+                if let Some(audio) = audio_summary(PathBuf::from(&files[0].path)).await {
+                    let audio_tmp = tmp_id.clone();
+                    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
                         let mut rust = qobject.as_mut().rust_mut();
                         if let Some(pos) =
-                            rust.messages.iter().position(|m| m.message_id == thumb_tmp)
+                            rust.messages.iter().position(|m| m.message_id == audio_tmp)
                         {
-                            rust.messages[pos].thumbnail_url = QString::from(thumb_uri.as_str());
+                            rust.messages[pos].apply_audio_summary(&audio);
                             drop(rust);
                             let model_index =
                                 qobject
@@ -1280,73 +1418,74 @@ impl crate::ffi::MessageList {
                 }
             }
 
-            // Read the file in the background — this is the operation that was freezing the UI
-            let bytes = tokio::task::spawn_blocking(move || std::fs::read(&path))
-                .await
-                .unwrap_or_else(|_| {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "task join failed",
-                    ))
-                });
-            let bytes = match bytes {
-                Ok(b) if !b.is_empty() => b,
-                Ok(_) => {
-                    eprintln!("Failed to read media file (empty)");
-                    return;
+            for (index, file) in files.iter().enumerate() {
+                let path = std::path::Path::new(&file.path);
+                // Qt can't show HEIC/AVIF without extra plugins, so preview a converted copy
+                if is_heif_family(&file.mime) {
+                    if let Some(preview_uri) = heif_preview_uri(path.to_path_buf()).await {
+                        queue_attachment_change(&qt_thread, &tmp_id, index, move |attachment| {
+                            attachment.media_url = preview_uri;
+                        });
+                    }
                 }
-                Err(e) => {
-                    eprintln!("Failed to read media file: {e}");
-                    return;
+                if file.mime.starts_with("video/") {
+                    let source = ThumbnailSource::File(path.to_path_buf());
+                    if let Some(thumb_uri) = thumbnail_uri(source, &file.mime).await {
+                        queue_attachment_change(&qt_thread, &tmp_id, index, move |attachment| {
+                            attachment.thumbnail_url = thumb_uri;
+                        });
+                    }
                 }
-            };
+            }
+
             let result: Result<(), String> = async {
+                let sim_payload = sim_payload(sim_number).await;
                 let client = ensure_client().await?;
                 let handler = make_handler(&client).await?;
+                // A remembered SMS preference already set the send mode
+                let sms_override = transport == TransportPreference::Sms
+                    && conversation_transport(&conversation_id) != TransportPreference::Sms;
+                if sms_override {
+                    set_sms_send_mode(&handler, &conversation_id, true).await?;
+                }
 
-                let ui_for_progress = qt_thread.clone();
-                let tmp_for_prog = tmp_id.clone();
-                let cancel_for_prog = cancel_flag.clone();
-                let uploaded_bytes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
-                let uploaded_for_prog = uploaded_bytes.clone();
-                let on_progress = move |uploaded: usize, total: usize| {
-                    uploaded_for_prog.store(uploaded, Ordering::Relaxed);
-                    let prog = if total > 0 { uploaded as f32 / total as f32 } else { 0.0 };
-                    let tmp = tmp_for_prog.clone();
-                    let cancelled = cancel_for_prog.load(Ordering::Relaxed);
-                    let _ = ui_for_progress.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
-                        let mut rust = qobject.as_mut().rust_mut();
-                        if let Some(pos) = rust.messages.iter().position(|m| m.message_id == tmp) {
-                            rust.messages[pos].upload_progress = prog;
-                            drop(rust);
-                            let model_index = qobject.as_ref().index(pos as i32, 0, &QModelIndex::default());
-                            qobject.as_mut().data_changed(&model_index, &model_index);
-                        }
-                    });
-                    if cancelled {
-                        // The cancellation is handled in the stream by checking the flag
-                    }
-                };
+                // Shrink photos and videos to what the transport and quality setting allow
+                let is_mms = sends_as_mms(&handler, &conversation_id, transport).await;
+                let mut limits = media_limits(is_mms, settings().media_quality);
+                // An MMS carries every part in one message, so they share its size limit
+                if is_mms {
+                    limits.max_bytes /= files.len().max(1) as u64;
+                }
 
-                let file_size = bytes.len();
-                // Upload the media first
-                let media = client.upload_media_with_progress(&bytes, &mime_type, &file_name, Some(on_progress), Some(cancel_flag.clone()))
-                    .await.map_err(|e| {
-                        let uploaded = uploaded_bytes.load(Ordering::Relaxed);
-                        eprintln!("media upload failed after {:.2} MB of {:.2} MB ({:.1}%)",
-                            uploaded as f64 / 1_048_576.0,
-                            file_size as f64 / 1_048_576.0,
-                            if file_size > 0 { uploaded as f64 / file_size as f64 * 100.0 } else { 0.0 });
-                        e.to_string()
-                    })?;
+                // Upload every file first; each gets an equal share of the progress bar
+                let count = files.len() as f32;
+                let mut message_info = Vec::new();
+                for (index, file) in files.into_iter().enumerate() {
+                    let ui_for_progress = qt_thread.clone();
+                    let tmp_for_prog = tmp_id.clone();
+                    let base = index as f32 / count;
+                    let on_progress = move |progress: f32| {
+                        let overall = base + progress / count;
+                        queue_upload_progress(&ui_for_progress, &tmp_for_prog, overall);
+                    };
+                    let media = upload_outgoing_file(
+                        &client,
+                        file,
+                        limits,
+                        strip_metadata,
+                        cancel_flag.clone(),
+                        on_progress,
+                    )
+                    .await?;
+                    message_info.push(libgmessages_rs::proto::conversations::MessageInfo {
+                        action_message_id: None,
+                        data: Some(
+                            libgmessages_rs::proto::conversations::message_info::Data::MediaContent(media),
+                        ),
+                    });
+                }
 
                 // Build the send request with OUR tmp_id so the echo event matches
-                let mut message_info = vec![libgmessages_rs::proto::conversations::MessageInfo {
-                    action_message_id: None,
-                    data: Some(
-                        libgmessages_rs::proto::conversations::message_info::Data::MediaContent(media),
-                    ),
-                }];
 
                 // Add text as additional MessageInfo if present
                 let message_payload_content = if !caption.is_empty() {
@@ -1382,9 +1521,9 @@ impl crate::ffi::MessageList {
                 let request = libgmessages_rs::proto::client::SendMessageRequest {
                     conversation_id: conversation_id.clone(),
                     message_payload: Some(payload),
-                    sim_payload: None,
+                    sim_payload,
                     tmp_id,
-                    force_rcs: false,
+                    force_rcs: transport == TransportPreference::Rcs,
                     reply: None,
                 };
 
@@ -1418,14 +1557,14 @@ impl crate::ffi::MessageList {
                     || error.contains("403");
                 if is_auth_error {
                     clear_client().await;
-                    let store = AuthDataStore::default_store();
-                    let _ = store.delete();
+                    let _ = delete_auth().await;
                 }
 
                 // Check if this was a cancellation
                 let was_cancelled = cancel_flag.load(Ordering::Relaxed);
                 if was_cancelled {
                     eprintln!("media upload was cancelled by user for {}", tmp_id_for_fail);
+                    discard_send_files(&send_files);
                     // Clean up the cancellation token
                     let tmp_cleanup = tmp_id_for_fail.clone();
                     let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
@@ -1458,12 +1597,15 @@ impl crate::ffi::MessageList {
                     {
                         item.status = QString::from("failed");
                     }
+                    // Kept until the failed bubble is deleted
+                    rust.failed_send_files.insert(tmp_id_for_fail, send_files);
                     qobject.as_mut().end_reset_model();
                     if is_auth_error {
                         qobject.as_mut().auth_error(&QString::from(error.as_str()));
                     }
                 });
             } else {
+                discard_send_files(&send_files);
                 // Success: clean up the cancellation token
                 let tmp_cleanup = tmp_id_for_fail;
                 let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
@@ -1477,16 +1619,48 @@ impl crate::ffi::MessageList {
         });
     }
 
-    pub fn get_video_thumbnail(&self, file_url: &QString) -> QString {
-        let file_path = file_url.to_string();
-        let path = if file_path.starts_with("file://") {
-            file_path[7..].to_string()
-        } else {
-            file_path
-        };
-        let p = std::path::Path::new(&path);
-        let ret = crate::app_state::utils::generate_video_thumbnail(p).unwrap_or_default();
-        QString::from(ret.as_str())
+    /// Make a thumbnail for a staged attachment in the background; announced
+    /// with `thumbnail_ready`, which isn't emitted if none can be made.
+    pub fn request_thumbnail(self: Pin<&mut Self>, file_url: &QString) {
+        let file_url = file_url.to_string();
+        let path = PathBuf::from(file_url.strip_prefix("file://").unwrap_or(&file_url));
+        let qt_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
+        spawn(async move {
+            let sniff_path = path.clone();
+            let Ok(mime) = tokio::task::spawn_blocking(move || file_mime(&sniff_path)).await else {
+                return;
+            };
+            if !mime.starts_with("image/") && !mime.starts_with("video/") {
+                return;
+            }
+            let Some(uri) = thumbnail_uri(ThumbnailSource::File(path), &mime).await else {
+                return;
+            };
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                qobject.as_mut().thumbnail_ready(
+                    &QString::from(file_url.as_str()),
+                    &QString::from(uri.as_str()),
+                );
+            });
+        });
+    }
+
+    /// Attachments pasted into the composer as newline-separated file:// URLs
+    /// to stage, or "" when the clipboard holds no image or files.
+    pub fn paste_attachments(&self) -> QString {
+        QString::from(crate::clipboard::pasted_attachments().join("\n").as_str())
+    }
+
+    /// A staged attachment was removed from the composer without being sent.
+    pub fn discard_attachment(&self, file_url: &QString) {
+        let file_url = file_url.to_string();
+        let path = PathBuf::from(file_url.strip_prefix("file://").unwrap_or(&file_url));
+        crate::clipboard::discard_pasted(&path);
+    }
+
+    /// A new file for the voice recorder to write to, sent with send_media.
+    pub fn new_voice_note_path(&self) -> QString {
+        QString::from(voice_note_path().to_string_lossy().as_ref())
     }
 
     pub fn get_file_size(&self, file_url: &QString) -> i64 {
@@ -1598,6 +1772,8 @@ impl crate::ffi::MessageList {
                         .insert(convo_id, (msgs_clone, me_id, cache_cursor));
                     drop(rust);
                     self.as_mut().end_remove_rows();
+                    self.as_mut()
+                        .refresh_group_edges(index as i32 - 1, index as i32);
                     return;
                 }
 
@@ -1607,6 +1783,7 @@ impl crate::ffi::MessageList {
                 let mut msg_id_for_preview = String::new();
 
                 let mut url_for_preview = String::new();
+                let mut sender_for_preview = (false, String::new());
                 let mut needs_preview = false;
 
                 let mut rust = self.as_mut().rust_mut();
@@ -1617,6 +1794,10 @@ impl crate::ffi::MessageList {
                     if !message_id.is_empty() && item.message_id != message_id {
                         item.message_id = message_id.to_string();
                     }
+                    let sim_number = recorded_sim_number(&item.message_id);
+                    if sim_number > 0 {
+                        item.sim_number = sim_number;
+                    }
 
                     if !item.is_media
                         && item.link_url.is_empty()
@@ -1627,7 +1808,7 @@ impl crate::ffi::MessageList {
                         {
                             needs_preview = true;
                             msg_id_for_preview = item.message_id.clone();
-
+                            sender_for_preview = (item.from_me, item.participant_id.clone());
                             url_for_preview = url;
                         }
                     }
@@ -1636,7 +1817,15 @@ impl crate::ffi::MessageList {
 
                 if needs_preview {
                     let qt_thread = self.qt_thread();
-                    spawn_link_preview_fetch(qt_thread, url_for_preview, msg_id_for_preview, convo);
+                    let (from_me, participant_id) = sender_for_preview;
+                    spawn_link_preview_fetch(
+                        qt_thread,
+                        url_for_preview,
+                        msg_id_for_preview,
+                        convo,
+                        from_me,
+                        participant_id,
+                    );
                 }
 
                 // Emit dataChanged for just this row
@@ -1648,7 +1837,12 @@ impl crate::ffi::MessageList {
             }
         }
 
-        if !is_media && body.trim().is_empty() {
+        let rich_content = recorded_rich_content(if message_id.is_empty() {
+            &tmp_id
+        } else {
+            &message_id
+        });
+        if !is_media && body.trim().is_empty() && rich_content.is_empty() {
             return;
         }
 
@@ -1656,8 +1850,21 @@ impl crate::ffi::MessageList {
         let status = map_message_status(status_code, from_me);
         let body_str = body.clone();
         let msg_id_str = message_id.clone();
+        let (file_name, file_size) = recorded_attachment(&msg_id_str);
+        // QML downloads the first part through queue_media_download; fetch the rest here
+        let mut extra_downloads = Vec::new();
+        let attachments = attachments_for(
+            &msg_id_str,
+            &recorded_media_parts(&msg_id_str),
+            &mut extra_downloads,
+        );
+        if let Some(primary) = attachments.first() {
+            extra_downloads.retain(|(_, cache_name, _)| *cache_name != primary.cache_name);
+        }
         let mut avatar_url = String::new();
+        let mut sender = String::new();
         if !from_me && !participant_id.is_empty() {
+            sender = sender_name(&conversation_id, &participant_id);
             if let Some(existing) = self
                 .rust()
                 .messages
@@ -1665,6 +1872,20 @@ impl crate::ffi::MessageList {
                 .find(|m| m.participant_id == participant_id && !m.avatar_url.is_empty())
             {
                 avatar_url = existing.avatar_url.to_string();
+            } else if let Some(member) = participants_for(&conversation_id).and_then(|members| {
+                members
+                    .into_iter()
+                    .find(|p| p.participant_id == participant_id)
+            }) {
+                // First message from this member in the loaded history
+                avatar_url = shared()
+                    .avatars
+                    .try_read()
+                    .ok()
+                    .and_then(|cache| cache.get(&member.avatar_identifier).cloned())
+                    .unwrap_or_else(|| {
+                        generated_avatar_url(&member.avatar_identifier, &member.name)
+                    });
             }
         }
 
@@ -1680,6 +1901,7 @@ impl crate::ffi::MessageList {
             avatar_url: QString::from(avatar_url.as_str()),
             is_info: status_code >= 200,
             participant_id: participant_id.clone(),
+            sender_name: QString::from(sender.as_str()),
             mime_type: QString::from(""),
             thumbnail_url: QString::from(""),
             upload_progress: 1.0,
@@ -1688,6 +1910,13 @@ impl crate::ffi::MessageList {
             link_image_url: QString::from(""),
             media_width,
             media_height,
+            sim_number: recorded_sim_number(&msg_id_str),
+            audio_duration_ms: 0,
+            waveform: QString::from(""),
+            file_name: QString::from(file_name.as_str()),
+            file_size,
+            attachments,
+            rich_content,
         };
 
         // Find insertion index (sorted by timestamp ascending)
@@ -1704,6 +1933,8 @@ impl crate::ffi::MessageList {
             .messages
             .insert(insert_pos, new_item);
         self.as_mut().end_insert_rows();
+        self.as_mut()
+            .refresh_group_edges(insert_pos_i32 - 1, insert_pos_i32 + 1);
 
         let selected = self.rust().selected_conversation_id.clone();
         if !selected.is_empty() {
@@ -1715,13 +1946,18 @@ impl crate::ffi::MessageList {
                 .insert(selected.clone(), (msgs, me_id, cache_cursor));
         }
 
+        download_attachments(self.qt_thread(), extra_downloads);
+        let card_targets =
+            card_image_targets(std::slice::from_ref(&self.rust().messages[insert_pos]));
+        spawn_card_image_fetch(self.qt_thread(), selected.clone(), card_targets);
+
         // Kick off link preview fetch for the new message if it contains a URL
         if !is_media && !body_str.is_empty() {
             if let Some(url) = crate::app_state::utils::extract_first_url(&body_str) {
                 let qt_thread = self.qt_thread();
                 let msg_id = msg_id_str;
                 let convo = selected;
-                spawn_link_preview_fetch(qt_thread, url, msg_id, convo);
+                spawn_link_preview_fetch(qt_thread, url, msg_id, convo, from_me, participant_id);
             }
         }
     }
@@ -1741,72 +1977,115 @@ impl crate::ffi::MessageList {
         let ui_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
 
         spawn(async move {
-            if let Some(client) = get_client().await {
-                let key_bytes = STANDARD.decode(&key).unwrap_or_default();
-                let safe_id = med_id
-                    .replace("/", "_")
-                    .replace("+", "_")
-                    .replace("=", "")
-                    .replace("-", "_");
-                let safe_id = if safe_id.is_empty() {
-                    msg_id.clone().replace("-", "_")
-                } else {
-                    safe_id
-                };
+            let file_name = media_cache_name(&med_id, &msg_id, &mime);
+            register_media_source(
+                &file_name,
+                MediaSource {
+                    media_id: med_id,
+                    decryption_key: STANDARD.decode(&key).unwrap_or_default(),
+                    mime: mime.clone(),
+                },
+            );
 
-                let ext = crate::app_state::utils::mime_to_extension(&mime);
-                let tmp_dir = std::env::temp_dir().join("kourier_media");
-                let path = tmp_dir.join(format!("{}.{}", safe_id, ext));
-
-                let uri = if !path.exists() {
-                    if let Ok(data) = client.download_media(&med_id, &key_bytes).await {
-                        Some(crate::app_state::utils::media_data_to_uri(
-                            &data, &mime, &safe_id,
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    Some(format!("file://{}", path.to_string_lossy()))
-                };
+            // Images are downloaded by the image provider once QML shows them
+            let uri = if mime.starts_with("image/") {
+                Some(provider_uri(CacheCategory::Media, &file_name))
+            } else {
+                ensure_media_cached(&file_name)
+                    .await
+                    .then(|| cache_uri(CacheCategory::Media, &file_name, &mime))
+            };
 
-                let thumb_uri = if path.exists() && mime.starts_with("video/") {
-                    crate::app_state::utils::generate_video_thumbnail(&path)
-                } else {
-                    None
-                };
+            let thumb_uri = if mime.starts_with("video/") {
+                thumbnail_uri(ThumbnailSource::Media(file_name.clone()), &mime).await
+            } else {
+                None
+            };
+            let audio = media_audio_summary(&file_name, &mime).await;
 
-                if let Some(uri) = uri {
-                    let _ = ui_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
-                        let mut rust = qobject.as_mut().rust_mut();
-                        if let Some(pos) = rust.messages.iter().position(|m| m.message_id == msg_id)
-                        {
-                            rust.messages[pos].media_url = QString::from(uri.as_str());
-                            if let Some(t) = &thumb_uri {
-                                rust.messages[pos].thumbnail_url = QString::from(t.as_str());
-                            }
-                            drop(rust);
-                            let model_index =
-                                qobject
-                                    .as_ref()
-                                    .index(pos as i32, 0, &QModelIndex::default());
-                            qobject.as_mut().data_changed(&model_index, &model_index);
+            if let Some(uri) = uri {
+                let _ = ui_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                    let mut rust = qobject.as_mut().rust_mut();
+                    if let Some(pos) = rust.messages.iter().position(|m| m.message_id == msg_id) {
+                        let thumb_uri = thumb_uri.unwrap_or_default();
+                        rust.messages[pos].apply_download(
+                            &file_name,
+                            &uri,
+                            &thumb_uri,
+                            audio.as_ref(),
+                        );
+                        if rust.messages[pos].mime_type.is_empty() {
+                            rust.messages[pos].mime_type = QString::from(mime.as_str());
+                        }
+                        drop(rust);
+                        let model_index =
+                            qobject
+                                .as_ref()
+                                .index(pos as i32, 0, &QModelIndex::default());
+                        qobject.as_mut().data_changed(&model_index, &model_index);
 
-                            let mut rust = qobject.as_mut().rust_mut();
-                            let selected = rust.selected_conversation_id.clone();
-                            if !selected.is_empty() {
-                                let msgs = rust.messages.clone();
-                                let me_id = rust.me_participant_id.clone();
-                                let cache_cursor = rust.next_cursor.clone();
-                                rust.cache.insert(selected, (msgs, me_id, cache_cursor));
-                            }
+                        let mut rust = qobject.as_mut().rust_mut();
+                        let selected = rust.selected_conversation_id.clone();
+                        if !selected.is_empty() {
+                            let msgs = rust.messages.clone();
+                            let me_id = rust.me_participant_id.clone();
+                            let cache_cursor = rust.next_cursor.clone();
+                            rust.cache.insert(selected, (msgs, me_id, cache_cursor));
                         }
-                    });
-                }
+                    }
+                });
             }
         });
     }
 
+    /// Delivery details of a message as JSON: transport, overall status and,
+    /// for my messages, the delivery and read state of each recipient.
+    /// Returns "{}" if the message hasn't been seen this session.
+    pub fn message_details(&self, message_id: &QString) -> QString {
+        let details = message_details(
+            &self.selected_conversation_id,
+            &message_id.to_string(),
+            &self.me_participant_id,
+        );
+        let json = details
+            .and_then(|details| serde_json::to_string(&details).ok())
+            .unwrap_or_else(|| "{}".to_string());
+        QString::from(json.as_str())
+    }
+
+    /// Transport preference of the open conversation: "auto", "rcs" or "sms".
+    pub fn conversation_transport(&self) -> QString {
+        QString::from(conversation_transport(&self.selected_conversation_id).name())
+    }
+
+    pub fn set_conversation_transport(&self, transport: &QString) {
+        if self.selected_conversation_id.is_empty() {
+            return;
+        }
+        let Some(transport) = TransportPreference::from_name(&transport.to_string()) else {
+            eprintln!("set_conversation_transport: unknown transport {transport}");
+            return;
+        };
+        let conversation_id = self.selected_conversation_id.clone();
+        let was_sms = conversation_transport(&conversation_id) == TransportPreference::Sms;
+        let sms = transport == TransportPreference::Sms;
+        set_conversation_transport(&conversation_id, transport);
+        // The phone keeps the send mode, so it only changes along with the preference
+        if was_sms != sms {
+            spawn(async move {
+                let result: Result<(), String> = async {
+                    let client = ensure_client().await?;
+                    let handler = make_handler(&client).await?;
+                    set_sms_send_mode(&handler, &conversation_id, sms).await
+                }
+                .await;
+                if let Err(e) = result {
+                    eprintln!("failed to update send mode: {e}");
+                }
+            });
+        }
+    }
+
     pub fn delete_message(mut self: Pin<&mut Self>, message_id: &QString) {
         let msg_id = message_id.to_string();
 
@@ -1823,6 +2102,7 @@ impl crate::ffi::MessageList {
                 .begin_remove_rows(&QModelIndex::default(), pos_i32, pos_i32);
             self.as_mut().rust_mut().messages.remove(pos);
             self.as_mut().end_remove_rows();
+            self.as_mut().refresh_group_edges(pos_i32 - 1, pos_i32);
 
             // Update cache
             let selected = self.rust().selected_conversation_id.clone();
@@ -1843,6 +2123,9 @@ impl crate::ffi::MessageList {
                 cancel.store(true, Ordering::Relaxed);
                 eprintln!("delete_message: cancelling active upload for {}", msg_id);
             }
+            if let Some(files) = rust.failed_send_files.remove(&msg_id) {
+                discard_send_files(&files);
+            }
         }
 
         spawn(async move {
@@ -1858,65 +2141,569 @@ impl crate::ffi::MessageList {
             }
         });
     }
-    pub fn save_media(self: Pin<&mut Self>, source_url: &QString, mime_type: &QString) -> QString {
+    /// Save an attachment to the Downloads folder under its original name when
+    /// known, adding " (1)", " (2)"… rather than overwriting an existing file.
+    /// Media that was never shown is downloaded first. Announced with
+    /// `media_saved`, whose path is empty if it failed.
+    pub fn save_media(
+        self: Pin<&mut Self>,
+        source_url: &QString,
+        mime_type: &QString,
+        file_name: &QString,
+    ) {
         let url = source_url.to_string();
         let mime = mime_type.to_string();
+        let file_name = file_name.to_string();
+        let qt_thread: CxxQtThread<ffi::MessageList> = self.qt_thread();
 
-        // Read the source data — either from a data: URI or a file:// path
-        let data_bytes = if let Some(rest) = url.strip_prefix("data:") {
-            // data:<mime>;base64,<data>
-            rest.find(";base64,").and_then(|pos| {
-                let b64 = &rest[pos + 8..];
-                STANDARD.decode(b64).ok()
+        spawn(async move {
+            // The image provider's media may not have been downloaded yet
+            let provider_data = match url.strip_prefix("image://kourier/") {
+                Some(_) => fetch_provider_uri(&url).await,
+                None => None,
+            };
+            let saved = tokio::task::spawn_blocking(move || {
+                save_to_downloads(&url, provider_data, mime, &file_name)
             })
-        } else if let Some(path) = url.strip_prefix("file://") {
-            // file:///path/to/file
-            std::fs::read(path).ok()
-        } else {
-            None
-        };
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                qobject.as_mut().media_saved(&QString::from(saved.as_str()));
+            });
+        });
+    }
+}
 
-        let Some(data_bytes) = data_bytes else {
-            eprintln!(
-                "save_media: could not read media from: {}",
-                &url[..url.len().min(80)]
-            );
-            return QString::from("");
-        };
+/// Write media to the Downloads folder and return the path it was saved at.
+/// `provider_data` holds the bytes of an `image://kourier/` source.
+fn save_to_downloads(
+    url: &str,
+    provider_data: Option<Vec<u8>>,
+    mut mime: String,
+    file_name: &str,
+) -> Option<String> {
+    // Read the source data — from a data: URI, a file:// path or the image provider
+    let data_bytes = if let Some(rest) = url.strip_prefix("data:") {
+        // data:<mime>;base64,<data>
+        rest.find(";base64,").and_then(|pos| {
+            let b64 = &rest[pos + 8..];
+            STANDARD.decode(b64).ok()
+        })
+    } else if let Some(path) = url.strip_prefix("file://") {
+        // file:///path/to/file
+        std::fs::read(path).ok()
+    } else {
+        provider_data
+    };
+
+    let Some(data_bytes) = data_bytes else {
+        eprintln!(
+            "save_media: could not read media from: {}",
+            &url[..url.len().min(80)]
+        );
+        return None;
+    };
 
-        let ext = crate::app_state::utils::mime_to_extension(&mime);
+    if mime.is_empty() || mime == "application/octet-stream" {
+        if let Some(sniffed) = sniff_mime(&data_bytes) {
+            mime = sniffed.to_string();
+        }
+    }
+    let ext = crate::app_state::utils::mime_to_extension(&mime);
 
-        // Get the Downloads directory
-        let downloads_dir = dirs::download_dir().unwrap_or_else(|| {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("/tmp"))
-                .join("Downloads")
-        });
+    // Get the Downloads directory
+    let downloads_dir = dirs::download_dir().unwrap_or_else(|| {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("Downloads")
+    });
 
-        // Generate a unique filename
+    let filename = sanitize_file_name(file_name).unwrap_or_else(|| {
         let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
         let short_id = &Uuid::new_v4().to_string()[..8];
-        let filename = format!("gmessages_{timestamp}_{short_id}.{ext}");
-        let full_path = downloads_dir.join(&filename);
+        format!("gmessages_{timestamp}_{short_id}.{ext}")
+    });
+    let full_path = unique_path(&downloads_dir, &filename);
+
+    // Ensure downloads directory exists
+    if let Err(e) = std::fs::create_dir_all(&downloads_dir) {
+        eprintln!("save_media: failed to create downloads dir: {e}");
+        return None;
+    }
+
+    match std::fs::write(&full_path, &data_bytes) {
+        Ok(()) => {
+            let path_str = full_path.to_string_lossy().to_string();
+            eprintln!("save_media: saved to {path_str}");
+            Some(path_str)
+        }
+        Err(e) => {
+            eprintln!("save_media: write failed: {e}");
+            None
+        }
+    }
+}
+
+/// Part of an attachment's progress bar given to re-encoding it before upload.
+const PREPARE_SHARE: f32 = 0.3;
+
+/// How one message is sent, resolved from what QML asked for and the
+/// conversation's remembered choices.
+struct SendOptions {
+    /// 0 for the phone's default SIM.
+    sim_number: i32,
+    transport: TransportPreference,
+    /// Remove location and camera metadata from photos.
+    strip_metadata: bool,
+}
+
+impl SendOptions {
+    /// Parse `{"sim_number": 2, "transport": "sms", "keep_metadata": true}`,
+    /// where every key is optional. Keys left out use the SIM and transport
+    /// remembered for the conversation and the photo metadata setting.
+    fn parse(options: &QString, conversation_id: &str) -> SendOptions {
+        #[derive(Default, Deserialize)]
+        #[serde(default)]
+        struct Overrides {
+            sim_number: Option<i32>,
+            transport: Option<String>,
+            keep_metadata: Option<bool>,
+        }
+
+        let options = options.to_string();
+        let overrides = if options.trim().is_empty() {
+            Overrides::default()
+        } else {
+            serde_json::from_str(&options).unwrap_or_else(|e| {
+                eprintln!("send: ignoring invalid options {options}: {e}");
+                Overrides::default()
+            })
+        };
+        SendOptions {
+            sim_number: overrides
+                .sim_number
+                .unwrap_or_else(|| conversation_sim(conversation_id)),
+            transport: overrides
+                .transport
+                .as_deref()
+                .and_then(TransportPreference::from_name)
+                .unwrap_or_else(|| conversation_transport(conversation_id)),
+            strip_metadata: !overrides
+                .keep_metadata
+                .unwrap_or_else(|| settings().keep_photo_metadata),
+        }
+    }
+}
 
-        // Ensure downloads directory exists
-        if let Err(e) = std::fs::create_dir_all(&downloads_dir) {
-            eprintln!("save_media: failed to create downloads dir: {e}");
-            return QString::from("");
+/// Delete the files Kourier made for a send (voice notes, pasted images)
+/// once the message is sent or dismissed. Files the user picked are left
+/// alone.
+fn discard_send_files(paths: &[PathBuf]) {
+    for path in paths {
+        remove_voice_note(path);
+        crate::clipboard::discard_pasted(path);
+    }
+}
+
+/// A local file picked for sending.
+struct OutgoingFile {
+    path: String,
+    file_name: String,
+    mime: String,
+    size: u64,
+}
+
+/// Stat and sniff a file to send, or why it can't be sent.
+fn outgoing_file(file_url: &str) -> Result<OutgoingFile, String> {
+    let path = file_url.strip_prefix("file://").unwrap_or(file_url);
+    if path.is_empty() {
+        return Err("No file to send".to_string());
+    }
+
+    // Sniff the MIME from the file's first few KiB, falling back to the extension
+    let path_obj = std::path::Path::new(path);
+    let file_name = path_obj
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mime = file_mime(path_obj);
+    let shrinkable = mime.starts_with("image/") || mime.starts_with("video/");
+
+    // Reject files over 100 MB (RCS limit) unless they can be re-encoded to fit
+    const MAX_BYTES: u64 = 100 * 1024 * 1024;
+    let size = match std::fs::metadata(path) {
+        Ok(meta) if meta.len() > MAX_BYTES && !shrinkable => {
+            return Err(format!(
+                "{file_name} is too large: {:.1} MB (RCS limit is 100 MB)",
+                meta.len() as f64 / 1_048_576.0
+            ));
         }
+        Err(e) => return Err(format!("Could not read {file_name}: {e}")),
+        Ok(meta) => meta.len(),
+    };
+
+    Ok(OutgoingFile {
+        path: path.to_string(),
+        file_name,
+        mime,
+        size,
+    })
+}
 
-        match std::fs::write(&full_path, &data_bytes) {
-            Ok(()) => {
-                let path_str = full_path.to_string_lossy().to_string();
-                eprintln!("save_media: saved to {path_str}");
-                QString::from(path_str.as_str())
+/// Re-encode, clean and upload one file, reporting progress from 0 to 1.
+async fn upload_outgoing_file(
+    client: &libgmessages_rs::gmclient::GMClient,
+    file: OutgoingFile,
+    limits: MediaLimits,
+    strip_metadata: bool,
+    cancel_flag: Arc<AtomicBool>,
+    on_progress: impl Fn(f32) + Clone + Send + Sync + 'static,
+) -> Result<libgmessages_rs::proto::conversations::MediaContent, String> {
+    let prepare_cancel = cancel_flag.clone();
+    let prepare_progress = on_progress.clone();
+    let prepared = tokio::task::spawn_blocking(move || {
+        prepare_media(
+            std::path::Path::new(&file.path),
+            &file.mime,
+            &file.file_name,
+            limits,
+            &prepare_cancel,
+            |progress| prepare_progress(progress * PREPARE_SHARE),
+        )
+    })
+    .await
+    .map_err(|_| "task join failed".to_string())??;
+    let upload_start = if prepared.is_temporary {
+        PREPARE_SHARE
+    } else {
+        0.0
+    };
+
+    // Read the file in the background — this is the operation that was freezing the UI
+    let upload_path = prepared.path.clone();
+    let bytes = tokio::task::spawn_blocking(move || std::fs::read(&upload_path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("failed to read media file: {e}"))?;
+    if prepared.is_temporary {
+        let _ = std::fs::remove_file(&prepared.path);
+    }
+    if bytes.is_empty() {
+        return Err("media file is empty".to_string());
+    }
+    // Photos are uploaded without their location and camera metadata
+    let bytes = if strip_metadata && prepared.mime.starts_with("image/") {
+        match strip_photo_metadata(&bytes, &prepared.mime) {
+            Some(stripped) => stripped,
+            None => {
+                eprintln!(
+                    "send_media: could not strip metadata from {}",
+                    prepared.mime
+                );
+                bytes
             }
-            Err(e) => {
-                eprintln!("save_media: write failed: {e}");
-                QString::from("")
+        }
+    } else {
+        bytes
+    };
+
+    let uploaded_bytes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let uploaded_for_prog = uploaded_bytes.clone();
+    let on_upload_progress = move |uploaded: usize, total: usize| {
+        uploaded_for_prog.store(uploaded, Ordering::Relaxed);
+        let prog = if total > 0 {
+            uploaded as f32 / total as f32
+        } else {
+            0.0
+        };
+        on_progress(upload_start + (1.0 - upload_start) * prog);
+    };
+
+    let file_size = bytes.len();
+    client
+        .upload_media_with_progress(
+            &bytes,
+            &prepared.mime,
+            &prepared.file_name,
+            Some(on_upload_progress),
+            Some(cancel_flag),
+        )
+        .await
+        .map_err(|e| {
+            let uploaded = uploaded_bytes.load(Ordering::Relaxed);
+            eprintln!(
+                "media upload failed after {:.2} MB of {:.2} MB ({:.1}%)",
+                uploaded as f64 / 1_048_576.0,
+                file_size as f64 / 1_048_576.0,
+                if file_size > 0 {
+                    uploaded as f64 / file_size as f64 * 100.0
+                } else {
+                    0.0
+                }
+            );
+            e.to_string()
+        })
+}
+
+/// Change one attachment of a message being sent, e.g. once its preview or
+/// thumbnail is ready.
+fn queue_attachment_change(
+    qt_thread: &CxxQtThread<ffi::MessageList>,
+    tmp_id: &str,
+    index: usize,
+    change: impl FnOnce(&mut Attachment) + Send + 'static,
+) {
+    let tmp_id = tmp_id.to_string();
+    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+        let mut rust = qobject.as_mut().rust_mut();
+        if let Some(pos) = rust.messages.iter().position(|m| m.message_id == tmp_id) {
+            let item = &mut rust.messages[pos];
+            if let Some(attachment) = item.attachments.get_mut(index) {
+                change(attachment);
+                item.sync_primary_attachment();
             }
+            drop(rust);
+            let model_index = qobject
+                .as_ref()
+                .index(pos as i32, 0, &QModelIndex::default());
+            qobject.as_mut().data_changed(&model_index, &model_index);
         }
+    });
+}
+
+/// Download non-image attachments in the background and point their
+/// messages at the cached files as each one finishes. Images are fetched by
+/// the image provider when shown instead.
+fn download_attachments(
+    qt_thread: CxxQtThread<ffi::MessageList>,
+    downloads: Vec<(String, String, String)>,
+) {
+    if downloads.is_empty() {
+        return;
     }
+    spawn(async move {
+        if get_client().await.is_none() {
+            return;
+        }
+        for (msg_id, file_name, mime) in downloads {
+            if !ensure_media_cached(&file_name).await {
+                continue;
+            }
+            let uri = cache_uri(CacheCategory::Media, &file_name, &mime);
+            let thumb_uri = if mime.starts_with("video/") {
+                thumbnail_uri(ThumbnailSource::Media(file_name.clone()), &mime)
+                    .await
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let audio = media_audio_summary(&file_name, &mime).await;
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                let mut rust = qobject.as_mut().rust_mut();
+                if let Some(pos) = rust.messages.iter().position(|m| m.message_id == msg_id) {
+                    rust.messages[pos].apply_download(&file_name, &uri, &thumb_uri, audio.as_ref());
+                    drop(rust);
+                    let model_index =
+                        qobject
+                            .as_ref()
+                            .index(pos as i32, 0, &QModelIndex::default());
+                    qobject.as_mut().data_changed(&model_index, &model_index);
+                }
+            });
+        }
+    });
+}
+
+/// Register every media part of a message for download and describe it for
+/// the `attachments` role. Images get a provider URI straight away; other
+/// parts are added to `downloads`.
+pub fn attachments_for(
+    message_id: &str,
+    parts: &[MediaPart],
+    downloads: &mut Vec<(String, String, String)>,
+) -> Vec<Attachment> {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            // Parts without a media id fall back to the message id, which must stay unique
+            let fallback_id = if index == 0 {
+                message_id.to_string()
+            } else {
+                format!("{message_id}_{index}")
+            };
+            let cache_name = media_cache_name(&part.media_id, &fallback_id, &part.mime);
+            register_media_source(
+                &cache_name,
+                MediaSource {
+                    media_id: part.media_id.clone(),
+                    decryption_key: part.decryption_key.clone(),
+                    mime: part.mime.clone(),
+                },
+            );
+            if !part.thumbnail_id.is_empty() {
+                register_media_source(
+                    &poster_cache_name(&cache_name),
+                    MediaSource {
+                        media_id: part.thumbnail_id.clone(),
+                        decryption_key: part.thumbnail_key.clone(),
+                        mime: "image/jpeg".to_string(),
+                    },
+                );
+            }
+            let media_url = if part.mime.starts_with("image/") {
+                // Downloaded by the image provider once QML shows it
+                provider_uri(CacheCategory::Media, &cache_name)
+            } else {
+                downloads.push((
+                    message_id.to_string(),
+                    cache_name.clone(),
+                    part.mime.clone(),
+                ));
+                String::new()
+            };
+            Attachment {
+                media_url,
+                thumbnail_url: String::new(),
+                mime_type: part.mime.clone(),
+                file_name: part.file_name.clone(),
+                file_size: part.size,
+                width: part.width,
+                height: part.height,
+                cache_name,
+            }
+        })
+        .collect()
+}
+
+/// Update the progress bar of an outgoing attachment.
+fn queue_upload_progress(qt_thread: &CxxQtThread<ffi::MessageList>, tmp_id: &str, progress: f32) {
+    let tmp_id = tmp_id.to_string();
+    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+        let mut rust = qobject.as_mut().rust_mut();
+        if let Some(pos) = rust.messages.iter().position(|m| m.message_id == tmp_id) {
+            rust.messages[pos].upload_progress = progress;
+            drop(rust);
+            let model_index = qobject
+                .as_ref()
+                .index(pos as i32, 0, &QModelIndex::default());
+            qobject.as_mut().data_changed(&model_index, &model_index);
+        }
+    });
+}
+
+/// Switch a conversation between SMS/MMS only and the phone's own choice.
+/// There is no per-message SMS flag, so forcing SMS means taking the
+/// conversation off RCS before sending.
+async fn set_sms_send_mode(
+    handler: &libgmessages_rs::gmclient::SessionHandler,
+    conversation_id: &str,
+    sms: bool,
+) -> Result<(), String> {
+    let send_mode = if sms {
+        libgmessages_rs::proto::conversations::ConversationSendMode::SendModeXms
+    } else {
+        libgmessages_rs::proto::conversations::ConversationSendMode::SendModeAuto
+    };
+    let request = libgmessages_rs::proto::client::UpdateConversationRequest {
+        conversation_id: conversation_id.to_string(),
+        data: Some(libgmessages_rs::proto::client::UpdateConversationData {
+            conversation_id: conversation_id.to_string(),
+            data: Some(
+                libgmessages_rs::proto::client::update_conversation_data::Data::SendMode(
+                    send_mode as i32,
+                ),
+            ),
+        }),
+        ..Default::default()
+    };
+    let _: libgmessages_rs::proto::client::UpdateConversationResponse = handler
+        .send_request(
+            libgmessages_rs::proto::rpc::ActionType::UpdateConversation,
+            libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+            &request,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whether an attachment to this conversation will go out as MMS. In auto
+/// mode this asks the phone, since the last message's transport may be stale,
+/// and assumes MMS if it can't.
+async fn sends_as_mms(
+    handler: &libgmessages_rs::gmclient::SessionHandler,
+    conversation_id: &str,
+    transport: TransportPreference,
+) -> bool {
+    match transport {
+        TransportPreference::Sms => return true,
+        TransportPreference::Rcs => return false,
+        TransportPreference::Auto => {}
+    }
+    let request = libgmessages_rs::proto::client::GetConversationRequest {
+        conversation_id: conversation_id.to_string(),
+    };
+    let response: Result<libgmessages_rs::proto::client::GetConversationResponse, _> = handler
+        .send_request(
+            libgmessages_rs::proto::rpc::ActionType::GetConversation,
+            libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+            &request,
+        )
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("send: could not look up the conversation, using MMS limits: {e}");
+            return true;
+        }
+    };
+    // Without the conversation, assume the smaller MMS limits
+    let is_rcs = response.conversation.is_some_and(|convo| {
+        use libgmessages_rs::proto::conversations::{ConversationSendMode, ConversationType};
+        convo.r#type == ConversationType::Rcs as i32
+            && convo.send_mode == ConversationSendMode::SendModeAuto as i32
+    });
+    !is_rcs
+}
+
+/// `transport_type` to show on a message until the phone reports the real one.
+/// In auto mode this follows the last message I sent in the conversation.
+fn optimistic_transport_type(
+    messages: &[MessageItem],
+    transport: TransportPreference,
+    is_media: bool,
+) -> i64 {
+    let last_was_sms = messages
+        .iter()
+        .find(|m| m.from_me && !m.is_info)
+        .is_some_and(|m| matches!(m.transport_type, 1..=3));
+    let as_sms = match transport {
+        TransportPreference::Auto => last_was_sms,
+        TransportPreference::Rcs => false,
+        TransportPreference::Sms => true,
+    };
+    match (as_sms, is_media) {
+        (false, _) => 4,
+        (true, false) => 1,
+        (true, true) => 2,
+    }
+}
+
+/// Whether two adjacent messages belong to the same run from one sender:
+/// same author, same day and at most five minutes apart.
+fn same_sender_group(earlier: &MessageItem, later: &MessageItem) -> bool {
+    const GROUP_GAP_MICROS: i64 = 5 * 60 * 1_000_000;
+    !earlier.is_info
+        && !later.is_info
+        && earlier.from_me == later.from_me
+        && (earlier.from_me || earlier.participant_id == later.participant_id)
+        && (later.timestamp_micros - earlier.timestamp_micros).abs() <= GROUP_GAP_MICROS
+        && format_section_date(earlier.timestamp_micros)
+            == format_section_date(later.timestamp_micros)
 }
 
 pub fn spawn_link_preview_fetch(
@@ -1924,47 +2711,17 @@ pub fn spawn_link_preview_fetch(
     url: String,
     msg_id: String,
     convo_id: String,
+    from_me: bool,
+    participant_id: String,
 ) {
     crate::app_state::shared::spawn(async move {
+        if !link_preview_allowed_fetching(&url, from_me, &convo_id, &participant_id).await {
+            return;
+        }
         let og = crate::app_state::utils::fetch_og_metadata(&url).await;
         let Some(og) = og else { return };
 
-        let image_file_url = if !og.image.is_empty() {
-            let cache_dir = std::env::temp_dir().join("kourier_link_previews");
-            let _ = std::fs::create_dir_all(&cache_dir);
-            let hash = {
-                use std::collections::hash_map::DefaultHasher;
-                use std::hash::{Hash, Hasher};
-                let mut h = DefaultHasher::new();
-                og.image.hash(&mut h);
-                h.finish()
-            };
-            let ext = if og.image.contains(".png") {
-                "png"
-            } else if og.image.contains(".webp") {
-                "webp"
-            } else {
-                "jpg"
-            };
-            let path = cache_dir.join(format!("{:016x}.{}", hash, ext));
-
-            if path.exists() {
-                format!("file://{}", path.to_string_lossy())
-            } else {
-                match reqwest::get(&og.image).await {
-                    Ok(resp) if resp.status().is_success() => match resp.bytes().await {
-                        Ok(bytes) if !bytes.is_empty() => {
-                            let _ = std::fs::write(&path, &bytes);
-                            format!("file://{}", path.to_string_lossy())
-                        }
-                        _ => String::new(),
-                    },
-                    _ => String::new(),
-                }
-            }
-        } else {
-            String::new()
-        };
+        let image_file_url = crate::app_state::utils::cache_link_preview_image(&og.image).await;
 
         let title = og.title;
         let link = og.url;
@@ -1986,3 +2743,70 @@ pub fn spawn_link_preview_fetch(
         });
     });
 }
+
+/// Rich card images to fetch in some messages: message id, card index,
+/// image URL and sender.
+fn card_image_targets(messages: &[MessageItem]) -> Vec<(String, usize, String, bool, String)> {
+    messages
+        .iter()
+        .flat_map(|m| {
+            m.rich_content
+                .cards
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, card)| {
+                    card.image_source().map(|url| {
+                        (
+                            m.message_id.clone(),
+                            index,
+                            url.to_string(),
+                            m.from_me,
+                            m.participant_id.clone(),
+                        )
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Fetch rich card images like link preview images, under the same policy
+/// and limits, and show them once cached.
+pub fn spawn_card_image_fetch(
+    qt_thread: CxxQtThread<ffi::MessageList>,
+    convo_id: String,
+    targets: Vec<(String, usize, String, bool, String)>,
+) {
+    if targets.is_empty() {
+        return;
+    }
+    crate::app_state::shared::spawn(async move {
+        for (msg_id, index, url, from_me, participant_id) in targets {
+            if !link_preview_allowed_fetching(&url, from_me, &convo_id, &participant_id).await {
+                continue;
+            }
+            let image_url = crate::app_state::utils::cache_link_preview_image(&url).await;
+            if image_url.is_empty() {
+                continue;
+            }
+            let convo = convo_id.clone();
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::MessageList>| {
+                let mut rust = qobject.as_mut().rust_mut();
+                if rust.selected_conversation_id != convo {
+                    return;
+                }
+                let Some(pos) = rust.messages.iter().position(|m| m.message_id == msg_id) else {
+                    return;
+                };
+                let Some(card) = rust.messages[pos].rich_content.cards.get_mut(index) else {
+                    return;
+                };
+                card.image_url = image_url;
+                drop(rust);
+                let model_index = qobject
+                    .as_ref()
+                    .index(pos as i32, 0, &QModelIndex::default());
+                qobject.as_mut().data_changed(&model_index, &model_index);
+            });
+        }
+    });
+}
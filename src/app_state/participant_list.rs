@@ -0,0 +1,253 @@
+use crate::ffi;
+use core::pin::Pin;
+use cxx_qt::{CxxQtThread, CxxQtType, Threading};
+use cxx_qt_lib::QString;
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::ffi::QHash_i32_QByteArray;
+use crate::ffi::QModelIndex;
+use crate::ffi::QVariant;
+
+use super::*;
+
+// ── ParticipantList ──────────────────────────────────────────────
+
+/// A member of a conversation, as reported by GetConversation.
+#[derive(Clone, Default)]
+pub struct Participant {
+    pub participant_id: String,
+    pub name: String,
+    pub number: String,
+    pub avatar_identifier: String,
+    pub is_me: bool,
+    /// In the phone's address book.
+    pub is_contact: bool,
+}
+
+impl Participant {
+    pub fn from_proto(p: &libgmessages_rs::proto::conversations::Participant) -> Participant {
+        let participant_id = p
+            .id
+            .as_ref()
+            .map(|id| id.participant_id.clone())
+            .unwrap_or_default();
+        let number = p
+            .id
+            .as_ref()
+            .map(|id| id.number.clone())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| p.formatted_number.clone());
+        let contact_name = contact_name_for_number(&number);
+        let is_contact = !p.contact_id.is_empty() || contact_name.is_some();
+        // Prefer the address book name, then the phone's, then the number
+        let name = if !p.full_name.is_empty() {
+            p.full_name.clone()
+        } else {
+            contact_name.unwrap_or_else(|| p.formatted_number.clone())
+        };
+        let avatar_identifier = if p.contact_id.is_empty() {
+            participant_id.clone()
+        } else {
+            p.contact_id.clone()
+        };
+        Participant {
+            participant_id,
+            name,
+            number,
+            avatar_identifier,
+            is_me: p.is_me,
+            is_contact,
+        }
+    }
+}
+
+/// Participants of recently opened conversations, filled in by
+/// `MessageList::load` so other models don't have to fetch them again.
+fn participants_by_conversation() -> &'static RwLock<HashMap<String, Vec<Participant>>> {
+    static INSTANCE: OnceLock<RwLock<HashMap<String, Vec<Participant>>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+pub fn store_participants(conversation_id: &str, participants: Vec<Participant>) {
+    participants_by_conversation()
+        .write()
+        .unwrap()
+        .insert(conversation_id.to_string(), participants);
+}
+
+pub fn participants_for(conversation_id: &str) -> Option<Vec<Participant>> {
+    participants_by_conversation()
+        .read()
+        .unwrap()
+        .get(conversation_id)
+        .cloned()
+}
+
+/// Fetch the current members of a conversation from the phone and store them.
+pub async fn fetch_participants(conversation_id: &str) -> Result<Vec<Participant>, String> {
+    let client = ensure_client().await?;
+    let handler = make_handler(&client).await?;
+    let request = libgmessages_rs::proto::client::GetConversationRequest {
+        conversation_id: conversation_id.to_string(),
+    };
+    let response: libgmessages_rs::proto::client::GetConversationResponse = handler
+        .send_request(
+            libgmessages_rs::proto::rpc::ActionType::GetConversation,
+            libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+            &request,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    let participants: Vec<Participant> = response
+        .conversation
+        .map(|convo| convo.participants.iter().map(Participant::from_proto).collect())
+        .unwrap_or_default();
+    store_participants(conversation_id, participants.clone());
+    Ok(participants)
+}
+
+/// Display name of a conversation member, empty when unknown.
+pub fn sender_name(conversation_id: &str, participant_id: &str) -> String {
+    if participant_id.is_empty() {
+        return String::new();
+    }
+    participants_by_conversation()
+        .read()
+        .unwrap()
+        .get(conversation_id)
+        .and_then(|members| {
+            members
+                .iter()
+                .find(|p| p.participant_id == participant_id)
+                .map(|p| p.name.clone())
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a conversation member is in the phone's address book.
+pub fn is_known_contact(conversation_id: &str, participant_id: &str) -> bool {
+    participants_by_conversation()
+        .read()
+        .unwrap()
+        .get(conversation_id)
+        .is_some_and(|members| {
+            members
+                .iter()
+                .any(|p| p.participant_id == participant_id && p.is_contact)
+        })
+}
+
+#[derive(Clone)]
+pub struct ParticipantItem {
+    pub participant: Participant,
+    pub avatar_url: QString,
+}
+
+pub struct ParticipantListRust {
+    items: Vec<ParticipantItem>,
+    conversation_id: String,
+    pub loading: bool,
+}
+
+impl Default for ParticipantListRust {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            conversation_id: String::new(),
+            loading: false,
+        }
+    }
+}
+
+impl crate::ffi::ParticipantList {
+    pub fn row_count(&self, _parent: &QModelIndex) -> i32 {
+        self.items.len() as i32
+    }
+
+    pub fn data(&self, index: &QModelIndex, role: i32) -> QVariant {
+        let row = index.row() as usize;
+        if row >= self.items.len() {
+            return QVariant::default();
+        }
+
+        let item = &self.items[row];
+        match role {
+            0 => QVariant::from(&QString::from(item.participant.name.as_str())),
+            1 => QVariant::from(&QString::from(item.participant.number.as_str())),
+            2 => QVariant::from(&item.avatar_url),
+            3 => QVariant::from(&item.participant.is_me),
+            4 => QVariant::from(&QString::from(item.participant.participant_id.as_str())),
+            _ => QVariant::default(),
+        }
+    }
+
+    pub fn role_names(&self) -> QHash_i32_QByteArray {
+        let mut roles = QHash_i32_QByteArray::default();
+        roles.insert(0, "name".into());
+        roles.insert(1, "number".into());
+        roles.insert(2, "avatar_url".into());
+        roles.insert(3, "is_me".into());
+        roles.insert(4, "participant_id".into());
+        roles
+    }
+
+    /// Show the members of a conversation, fetching them if `MessageList`
+    /// hasn't already.
+    pub fn load(mut self: Pin<&mut Self>, conversation_id: &QString) {
+        let conversation_id = conversation_id.to_string();
+        self.as_mut().rust_mut().conversation_id = conversation_id.clone();
+
+        if let Some(participants) = participants_for(&conversation_id) {
+            self.as_mut().set_participants(participants);
+            return;
+        }
+
+        self.as_mut().set_participants(Vec::new());
+        self.as_mut().set_loading(true);
+        let qt_thread: CxxQtThread<ffi::ParticipantList> = self.qt_thread();
+
+        spawn(async move {
+            let participants = match fetch_participants(&conversation_id).await {
+                Ok(participants) => participants,
+                Err(error) => {
+                    eprintln!("participant list load failed: {error}");
+                    Vec::new()
+                }
+            };
+            let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::ParticipantList>| {
+                qobject.as_mut().set_loading(false);
+                if qobject.rust().conversation_id == conversation_id {
+                    qobject.as_mut().set_participants(participants);
+                }
+            });
+        });
+    }
+
+    fn set_participants(mut self: Pin<&mut Self>, mut participants: Vec<Participant>) {
+        // Everyone else by name, then me
+        participants.sort_by_key(|p| (p.is_me, p.name.to_lowercase()));
+        let avatars = shared().avatars.try_read().ok();
+        let items = participants
+            .into_iter()
+            .map(|participant| {
+                let url = avatars
+                    .as_ref()
+                    .and_then(|cache| cache.get(&participant.avatar_identifier).cloned())
+                    .unwrap_or_else(|| {
+                        generated_avatar_url(&participant.avatar_identifier, &participant.name)
+                    });
+                ParticipantItem {
+                    participant,
+                    avatar_url: QString::from(url.as_str()),
+                }
+            })
+            .collect();
+        drop(avatars);
+
+        self.as_mut().begin_reset_model();
+        self.as_mut().rust_mut().items = items;
+        self.as_mut().end_reset_model();
+    }
+}
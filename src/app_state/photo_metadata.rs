@@ -0,0 +1,613 @@
+use super::thumbnails::box_header;
+
+// ── Photo metadata ───────────────────────────────────────────────
+
+/// Remove location, camera and editing metadata (EXIF, XMP, IPTC, text
+/// chunks) from a photo without re-encoding it. The EXIF orientation of
+/// JPEGs is kept so photos are not shown sideways. Returns `None` for
+/// formats it doesn't understand or files it can't parse.
+pub fn strip_photo_metadata(bytes: &[u8], mime: &str) -> Option<Vec<u8>> {
+    match mime {
+        "image/jpeg" => strip_jpeg(bytes),
+        "image/png" => strip_png(bytes),
+        "image/webp" => strip_webp(bytes),
+        "image/heic" | "image/heif" | "image/avif" => blank_heif(bytes),
+        _ => None,
+    }
+}
+
+fn be_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn be_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn le_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Big-endian unsigned integer of 0, 2, 4 or 8 bytes, as used by ISOBMFF.
+fn be_uint(bytes: &[u8], pos: usize, size: usize) -> Option<u64> {
+    match size {
+        0 => Some(0),
+        2 => be_u16(bytes, pos).map(u64::from),
+        4 => be_u32(bytes, pos).map(u64::from),
+        8 => Some(u64::from_be_bytes(bytes.get(pos..pos + 8)?.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+// ── JPEG ──
+
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut out = vec![0xFF, 0xD8];
+    let mut orientation = None;
+    // Where a replacement EXIF segment goes: after SOI and a leading JFIF APP0
+    let mut exif_at = out.len();
+    let mut pos = 2;
+    loop {
+        if *bytes.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // End of image. Anything after it, such as an MPF secondary image
+            // or an HDR gain map, is another JPEG with metadata of its own
+            0xD9 => {
+                out.extend_from_slice(&[0xFF, 0xD9]);
+                break;
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&bytes[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        // The length counts itself, so anything under 2 is corrupt
+        let length = be_u16(bytes, pos + 2)? as usize;
+        if length < 2 {
+            return None;
+        }
+        let end = pos + 2 + length;
+        let segment = bytes.get(pos..end)?;
+        let payload = &segment[4..];
+        match marker {
+            // APP1 holds EXIF and XMP
+            0xE1 => {
+                if let Some(tiff) = payload.strip_prefix(b"Exif\0\0") {
+                    orientation = orientation.or(exif_orientation(tiff));
+                }
+            }
+            // APP13 (Photoshop/IPTC) and comments
+            0xED | 0xFE => {}
+            _ => {
+                out.extend_from_slice(segment);
+                if marker == 0xE0 && exif_at == 2 {
+                    exif_at = out.len();
+                }
+            }
+        }
+        pos = end;
+        // Image data follows a start of scan and carries no metadata
+        if marker == 0xDA {
+            pos = scan_end(bytes, pos);
+            out.extend_from_slice(&bytes[end..pos]);
+            // Truncated files without an end of image are kept as they are
+            if pos == bytes.len() {
+                break;
+            }
+        }
+    }
+
+    if let Some(orientation) = orientation.filter(|o| *o > 1) {
+        out.splice(exif_at..exif_at, orientation_segment(orientation));
+    }
+    Some(out)
+}
+
+/// End of the entropy-coded data starting at `pos`: the next marker that
+/// isn't a stuffed zero, a restart marker or fill.
+fn scan_end(bytes: &[u8], mut pos: usize) -> usize {
+    while pos + 1 < bytes.len() {
+        if bytes[pos] == 0xFF && !matches!(bytes[pos + 1], 0x00 | 0xD0..=0xD7 | 0xFF) {
+            return pos;
+        }
+        pos += 1;
+    }
+    bytes.len()
+}
+
+/// The Orientation tag (0x0112) from IFD0 of an EXIF TIFF block.
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let read_u16 = |pos: usize| -> Option<u16> {
+        let b: [u8; 2] = tiff.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    };
+    let read_u32 = |pos: usize| -> Option<u32> {
+        let b: [u8; 4] = tiff.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    };
+
+    let ifd = read_u32(4)? as usize;
+    let entries = read_u16(ifd)? as usize;
+    (0..entries)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|entry| read_u16(*entry) == Some(0x0112))
+        .and_then(|entry| read_u16(entry + 8))
+}
+
+/// An APP1 segment whose EXIF block holds nothing but the orientation.
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut payload = b"Exif\0\0MM\0\x2a".to_vec();
+    payload.extend_from_slice(&8u32.to_be_bytes()); // IFD0 offset
+    payload.extend_from_slice(&1u16.to_be_bytes()); // one entry
+    payload.extend_from_slice(&0x0112u16.to_be_bytes()); // Orientation
+    payload.extend_from_slice(&3u16.to_be_bytes()); // SHORT
+    payload.extend_from_slice(&1u32.to_be_bytes()); // count
+    payload.extend_from_slice(&orientation.to_be_bytes());
+    payload.extend_from_slice(&[0, 0]); // value padding
+    payload.extend_from_slice(&0u32.to_be_bytes()); // no next IFD
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(&payload);
+    segment
+}
+
+// ── PNG ──
+
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !bytes.starts_with(SIGNATURE) {
+        return None;
+    }
+    let mut out = SIGNATURE.to_vec();
+    let mut pos = SIGNATURE.len();
+    while pos < bytes.len() {
+        // Length, type, data and CRC
+        let end = pos + 12 + be_u32(bytes, pos)? as usize;
+        let chunk = bytes.get(pos..end)?;
+        let metadata = matches!(&chunk[4..8], b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME");
+        if !metadata {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+    }
+    Some(out)
+}
+
+// ── WebP ──
+
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let mut out = bytes[0..12].to_vec();
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let size = le_u32(bytes, pos + 4)? as usize;
+        // Chunks are padded to an even length
+        let end = (pos + 8 + size + (size & 1)).min(bytes.len());
+        let fourcc = &bytes[pos..pos + 4];
+        match fourcc {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(&bytes[pos..end]);
+                // Clear the "has EXIF" and "has XMP" flags
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !(0x08 | 0x04);
+                }
+            }
+            _ => out.extend_from_slice(&bytes[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+// ── HEIF / HEIC / AVIF ──
+
+/// Child boxes of an ISOBMFF box as (type, content start, end).
+fn child_boxes(bytes: &[u8], start: usize, end: usize) -> Option<Vec<([u8; 4], usize, usize)>> {
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        let (size, header) = box_header(bytes.get(pos..end)?, (end - pos) as u64)?;
+        let kind: [u8; 4] = bytes[pos + 4..pos + 8].try_into().ok()?;
+        let box_end = usize::try_from(size)
+            .ok()
+            .and_then(|size| pos.checked_add(size))
+            .filter(|box_end| *box_end <= end)?;
+        boxes.push((kind, pos + header, box_end));
+        pos = box_end;
+    }
+    Some(boxes)
+}
+
+/// Metadata in HEIF files lives in items referenced from the `meta` box.
+/// Removing them would mean rewriting every offset in the file, so the
+/// EXIF and XMP item payloads are zeroed in place instead.
+fn blank_heif(bytes: &[u8]) -> Option<Vec<u8>> {
+    let top = child_boxes(bytes, 0, bytes.len())?;
+    if top.first()?.0 != *b"ftyp" {
+        return None;
+    }
+    let &(_, meta_start, meta_end) = top.iter().find(|(kind, _, _)| kind == b"meta")?;
+    // meta is a full box: skip version and flags
+    let meta = child_boxes(bytes, meta_start + 4, meta_end)?;
+
+    let metadata_items = meta
+        .iter()
+        .find(|(kind, _, _)| kind == b"iinf")
+        .and_then(|&(_, start, end)| metadata_item_ids(bytes, start, end))
+        .unwrap_or_default();
+    let mut out = bytes.to_vec();
+    if metadata_items.is_empty() {
+        return Some(out);
+    }
+
+    let &(_, iloc_start, iloc_end) = meta.iter().find(|(kind, _, _)| kind == b"iloc")?;
+    for (offset, length) in item_extents(bytes, iloc_start, iloc_end, &metadata_items)? {
+        let end = offset.checked_add(length)?;
+        out.get_mut(offset..end)?.fill(0);
+    }
+    Some(out)
+}
+
+/// IDs of the EXIF and XMP items listed in an `iinf` box.
+fn metadata_item_ids(bytes: &[u8], start: usize, end: usize) -> Option<Vec<u32>> {
+    let version = *bytes.get(start)?;
+    let entries_start = if version == 0 { start + 6 } else { start + 8 };
+    let mut ids = Vec::new();
+    for (kind, content, box_end) in child_boxes(bytes, entries_start, end)? {
+        if kind != *b"infe" {
+            continue;
+        }
+        // Versions 2 and 3 carry an item type; older ones predate EXIF items
+        let (id, mut pos) = match bytes.get(content)? {
+            2 => (be_u16(bytes, content + 4)? as u32, content + 6),
+            3 => (be_u32(bytes, content + 4)?, content + 8),
+            _ => continue,
+        };
+        pos += 2; // item_protection_index
+        let item_type = bytes.get(pos..pos + 4)?;
+        let is_xmp = || {
+            let rest = bytes.get(pos + 4..box_end)?;
+            // item_name, then content_type
+            let name_end = rest.iter().position(|b| *b == 0)?;
+            let content_type = &rest[name_end + 1..];
+            Some(content_type.starts_with(b"application/rdf+xml"))
+        };
+        if item_type == b"Exif" || (item_type == b"mime" && is_xmp() == Some(true)) {
+            ids.push(id);
+        }
+    }
+    Some(ids)
+}
+
+/// File byte ranges of the given items according to an `iloc` box. Only
+/// extents stored directly in the file (construction method 0) are listed.
+fn item_extents(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    items: &[u32],
+) -> Option<Vec<(usize, usize)>> {
+    let version = *bytes.get(start)?;
+    let mut pos = start + 4;
+    let sizes = *bytes.get(pos)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 0x0F) as usize);
+    let sizes = *bytes.get(pos + 1)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version >= 1 { (sizes & 0x0F) as usize } else { 0 };
+    pos += 2;
+    let item_count = if version < 2 {
+        let count = be_u16(bytes, pos)? as usize;
+        pos += 2;
+        count
+    } else {
+        let count = be_u32(bytes, pos)? as usize;
+        pos += 4;
+        count
+    };
+
+    let mut extents = Vec::new();
+    for _ in 0..item_count {
+        let id = if version < 2 {
+            let id = be_u16(bytes, pos)? as u32;
+            pos += 2;
+            id
+        } else {
+            let id = be_u32(bytes, pos)?;
+            pos += 4;
+            id
+        };
+        let construction_method = if version >= 1 {
+            let method = be_u16(bytes, pos)? & 0x0F;
+            pos += 2;
+            method
+        } else {
+            0
+        };
+        pos += 2; // data_reference_index
+        let base_offset = be_uint(bytes, pos, base_offset_size)? as usize;
+        pos += base_offset_size;
+        let extent_count = be_u16(bytes, pos)?;
+        pos += 2;
+        for _ in 0..extent_count {
+            pos += index_size;
+            let offset = be_uint(bytes, pos, offset_size)? as usize;
+            pos += offset_size;
+            let length = be_uint(bytes, pos, length_size)? as usize;
+            pos += length_size;
+            if items.contains(&id) && construction_method == 0 && length > 0 {
+                extents.push((base_offset.checked_add(offset)?, length));
+            }
+        }
+        if pos > end {
+            return None;
+        }
+    }
+    Some(extents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    const GPS: &[u8] = b"GPSLatitude 51.5";
+    const XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>";
+    const ICC: &[u8] = b"ICC_PROFILE\0\x01\x01fake profile";
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    fn encoded(format: ImageFormat) -> Vec<u8> {
+        let image = RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8 * 30, 128]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    /// EXIF block with the orientation and a location string after the IFD.
+    fn exif_payload(orientation: u16) -> Vec<u8> {
+        let mut payload = orientation_segment(orientation)[4..].to_vec();
+        payload.extend_from_slice(GPS);
+        payload
+    }
+
+    #[test]
+    fn jpeg_loses_exif_and_xmp_but_keeps_orientation_icc_and_pixels() {
+        let original = encoded(ImageFormat::Jpeg);
+        let icc = jpeg_segment(0xE2, ICC);
+        let mut tagged = original[..2].to_vec();
+        tagged.extend(jpeg_segment(0xE1, &exif_payload(6)));
+        tagged.extend(jpeg_segment(0xE1, XMP));
+        tagged.extend(&icc);
+        tagged.extend_from_slice(&original[2..]);
+
+        let stripped = strip_photo_metadata(&tagged, "image/jpeg").unwrap();
+        assert!(!contains(&stripped, GPS));
+        assert!(!contains(&stripped, b"xmpmeta"));
+        assert!(contains(&stripped, &icc));
+        let exif = stripped
+            .windows(6)
+            .position(|window| window == b"Exif\0\0")
+            .unwrap();
+        assert_eq!(exif_orientation(&stripped[exif + 6..]), Some(6));
+        assert_eq!(
+            image::load_from_memory(&stripped).unwrap().to_rgb8(),
+            image::load_from_memory(&original).unwrap().to_rgb8()
+        );
+    }
+
+    #[test]
+    fn jpeg_drops_images_appended_after_the_end() {
+        let original = encoded(ImageFormat::Jpeg);
+        let mut gain_map = original[..2].to_vec();
+        gain_map.extend(jpeg_segment(0xE1, &exif_payload(1)));
+        gain_map.extend_from_slice(&original[2..]);
+        let tagged = [original.as_slice(), &gain_map].concat();
+
+        let stripped = strip_photo_metadata(&tagged, "image/jpeg").unwrap();
+        assert!(!contains(&stripped, GPS));
+        assert_eq!(stripped, original);
+    }
+
+    #[test]
+    fn jpeg_with_too_short_segment_is_rejected() {
+        for length in [0u8, 1] {
+            let bytes = [0xFF, 0xD8, 0xFF, 0xE1, 0x00, length, 0xFF, 0xDA];
+            assert_eq!(strip_photo_metadata(&bytes, "image/jpeg"), None);
+        }
+    }
+
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        // The stripper never checks CRCs
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    #[test]
+    fn png_loses_text_and_exif_chunks_only() {
+        let original = encoded(ImageFormat::Png);
+        // Signature and IHDR
+        let header = 8 + 12 + 13;
+        let icc = png_chunk(b"iCCP", b"sRGB\0\0fake profile");
+        let mut tagged = original[..header].to_vec();
+        tagged.extend(png_chunk(b"eXIf", &exif_payload(1)[6..]));
+        tagged.extend(&icc);
+        tagged.extend(png_chunk(b"iTXt", XMP));
+        tagged.extend(png_chunk(b"tEXt", GPS));
+        tagged.extend_from_slice(&original[header..]);
+
+        let stripped = strip_photo_metadata(&tagged, "image/png").unwrap();
+        let mut expected = original[..header].to_vec();
+        expected.extend(&icc);
+        expected.extend_from_slice(&original[header..]);
+        assert_eq!(stripped, expected);
+    }
+
+    fn webp_chunk(fourcc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = fourcc.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        bytes.extend_from_slice(b"WEBP");
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn webp_loses_exif_and_xmp_chunks_and_flags() {
+        // ICC, EXIF and XMP flags set; canvas size is irrelevant here
+        let vp8x = |flags: u8| webp_chunk(b"VP8X", &[flags, 0, 0, 0, 7, 0, 0, 7, 0, 0]);
+        let icc = webp_chunk(b"ICCP", b"fake profile");
+        let image = webp_chunk(b"VP8L", b"\x2f pixel data");
+        let tagged = riff(&[
+            vp8x(0x20 | 0x08 | 0x04),
+            icc.clone(),
+            image.clone(),
+            webp_chunk(b"EXIF", &exif_payload(1)[6..]),
+            webp_chunk(b"XMP ", XMP),
+        ]);
+
+        let stripped = strip_photo_metadata(&tagged, "image/webp").unwrap();
+        assert_eq!(stripped, riff(&[vp8x(0x20), icc, image]));
+    }
+
+    fn iso_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = (content.len() as u32 + 8).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    fn infe(id: u16, item_type: &[u8; 4], content_type: &[u8]) -> Vec<u8> {
+        let mut content = vec![2, 0, 0, 0];
+        content.extend_from_slice(&id.to_be_bytes());
+        content.extend_from_slice(&[0, 0]);
+        content.extend_from_slice(item_type);
+        content.push(0); // empty item_name
+        content.extend_from_slice(content_type);
+        iso_box(b"infe", &content)
+    }
+
+    /// ftyp, then meta listing an image, an EXIF and an XMP item, then mdat.
+    fn heic(pixels: &[u8], exif: &[u8], xmp: &[u8]) -> Vec<u8> {
+        let ftyp = iso_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+
+        let mut iinf = vec![0, 0, 0, 0, 0, 3];
+        iinf.extend(infe(1, b"hvc1", b""));
+        iinf.extend(infe(2, b"Exif", b""));
+        iinf.extend(infe(3, b"mime", b"application/rdf+xml\0"));
+        let iinf = iso_box(b"iinf", &iinf);
+
+        let iloc = |mdat_data: usize| {
+            // Version 0, 4-byte offsets and lengths, no base offset
+            let mut iloc = vec![0, 0, 0, 0, 0x44, 0x00, 0, 3];
+            let mut offset = mdat_data;
+            for (id, data) in [(1u16, pixels), (2, exif), (3, xmp)] {
+                iloc.extend_from_slice(&id.to_be_bytes());
+                iloc.extend_from_slice(&[0, 0, 0, 1]);
+                iloc.extend_from_slice(&(offset as u32).to_be_bytes());
+                iloc.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                offset += data.len();
+            }
+            iso_box(b"iloc", &iloc)
+        };
+        let meta = |mdat_data: usize| {
+            let mut content = vec![0, 0, 0, 0];
+            content.extend(&iinf);
+            content.extend(iloc(mdat_data));
+            iso_box(b"meta", &content)
+        };
+
+        let mdat_data = ftyp.len() + meta(0).len() + 8;
+        let mut bytes = ftyp.clone();
+        bytes.extend(meta(mdat_data));
+        bytes.extend(iso_box(b"mdat", &[pixels, exif, xmp].concat()));
+        bytes
+    }
+
+    #[test]
+    fn heic_blanks_exif_and_xmp_items_in_place() {
+        let pixels = b"hevc pixel data";
+        let exif = [&[0, 0, 0, 6][..], b"Exif\0\0", GPS].concat();
+        let tagged = heic(pixels, &exif, XMP);
+
+        let stripped = strip_photo_metadata(&tagged, "image/heic").unwrap();
+        assert_eq!(
+            stripped,
+            heic(pixels, &vec![0; exif.len()], &vec![0; XMP.len()])
+        );
+    }
+
+    #[test]
+    fn box_sizes_past_the_parent_are_rejected() {
+        let mut huge = 1u32.to_be_bytes().to_vec();
+        huge.extend_from_slice(b"mdat");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        let tagged = [iso_box(b"ftyp", b"heic"), huge].concat();
+        assert_eq!(child_boxes(&tagged, 0, tagged.len()), None);
+        assert_eq!(strip_photo_metadata(&tagged, "image/heic"), None);
+    }
+
+    #[test]
+    fn unknown_formats_are_left_to_the_caller() {
+        assert_eq!(strip_photo_metadata(b"GIF89a", "image/gif"), None);
+        assert_eq!(strip_photo_metadata(b"not a png", "image/png"), None);
+    }
+}
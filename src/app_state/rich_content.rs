@@ -0,0 +1,567 @@
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::*;
+
+// ── Rich content ─────────────────────────────────────────────────
+
+/// GSMA RCS content types of the payloads handled here, as sent in a media
+/// part's mime type.
+const BOT_MESSAGE_MIME: &str = "application/vnd.gsma.botmessage.v1.0+json";
+const BOT_SUGGESTION_MIME: &str = "application/vnd.gsma.botsuggestion.v1.0+json";
+const PUSH_LOCATION_MIME: &str = "application/vnd.gsma.rcspushlocation+xml";
+
+/// A location shared as a map link, `geo:` URI or RCS push location.
+#[derive(Clone, Default, Serialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Place name, empty when the sender only shared coordinates.
+    pub label: String,
+    /// Link that opens the location in a browser.
+    pub url: String,
+}
+
+impl Location {
+    fn new(latitude: f64, longitude: f64, label: String, url: String) -> Option<Location> {
+        let valid = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+        valid.then(|| {
+            let url = if url.starts_with("https://") {
+                url
+            } else {
+                format!(
+                    "https://www.openstreetmap.org/?mlat={latitude}&mlon={longitude}#map=16/{latitude}/{longitude}"
+                )
+            };
+            Location {
+                latitude,
+                longitude,
+                label,
+                url,
+            }
+        })
+    }
+}
+
+/// A suggested reply or action, shown as a chip.
+#[derive(Clone, Default, Serialize)]
+pub struct Suggestion {
+    pub text: String,
+    /// "reply", "url", "dial" or "location".
+    pub kind: String,
+    /// Text to send for replies, otherwise the URL to open.
+    pub value: String,
+}
+
+/// A standalone or carousel card from an RCS business message.
+#[derive(Clone, Default, Serialize)]
+pub struct RichCard {
+    pub title: String,
+    pub description: String,
+    /// Where the card's image is fetched from, never handed to QML.
+    #[serde(skip)]
+    pub media_url: String,
+    #[serde(skip)]
+    pub thumbnail_url: String,
+    /// The image once cached, as an `image://kourier/` URI.
+    pub image_url: String,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl RichCard {
+    /// The image to fetch for the card: its thumbnail, or else the full image.
+    pub fn image_source(&self) -> Option<&str> {
+        [&self.thumbnail_url, &self.media_url]
+            .into_iter()
+            .find(|url| !url.is_empty())
+            .map(String::as_str)
+    }
+}
+
+/// What a message carries besides plain text and attachments.
+#[derive(Clone, Default)]
+pub struct RichContent {
+    pub location: Option<Location>,
+    pub cards: Vec<RichCard>,
+    /// Chips offered below the message rather than on a card.
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl RichContent {
+    pub fn is_empty(&self) -> bool {
+        self.location.is_none() && self.cards.is_empty() && self.suggestions.is_empty()
+    }
+
+    /// Add what another part of the same message carries.
+    fn merge(&mut self, other: RichContent) {
+        if self.location.is_none() {
+            self.location = other.location;
+        }
+        self.cards.extend(other.cards);
+        self.suggestions.extend(other.suggestions);
+    }
+
+    /// The `rich_cards` role, a JSON list.
+    pub fn cards_json(&self) -> String {
+        serde_json::to_string(&self.cards).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// The `suggestions` role, a JSON list.
+    pub fn suggestions_json(&self) -> String {
+        serde_json::to_string(&self.suggestions).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+/// Rich content of a message: cards, chips and locations from the RCS
+/// payloads among its parts, or a location from a map link in its text.
+/// Cards and chips only come from parts sent with a GSMA content type, so
+/// plain text that happens to be JSON stays text.
+pub fn extract_rich_content(
+    message: &libgmessages_rs::proto::conversations::Message,
+) -> RichContent {
+    let mut rich = RichContent::default();
+    for info in &message.message_info {
+        match &info.data {
+            Some(libgmessages_rs::proto::conversations::message_info::Data::MessageContent(
+                content,
+            )) => rich.merge(RichContent {
+                location: find_location(&content.content),
+                ..Default::default()
+            }),
+            Some(libgmessages_rs::proto::conversations::message_info::Data::MediaContent(
+                media,
+            )) => rich.merge(parse_typed_content(&media.mime_type, &media.media_data)),
+            _ => {}
+        }
+    }
+    rich
+}
+
+/// Whether a media part with this mime type is one of the RCS payloads
+/// `extract_rich_content` shows, rather than a file.
+pub fn is_rich_content_mime(mime: &str) -> bool {
+    let mime = mime.split(';').next().unwrap_or_default().trim();
+    [BOT_MESSAGE_MIME, BOT_SUGGESTION_MIME, PUSH_LOCATION_MIME]
+        .iter()
+        .any(|known| mime.eq_ignore_ascii_case(known))
+}
+
+/// Rich content of a payload sent with an RCS content type, empty for any
+/// other type or a payload that doesn't parse.
+fn parse_typed_content(mime: &str, data: &[u8]) -> RichContent {
+    if !is_rich_content_mime(mime) {
+        return RichContent::default();
+    }
+    let Ok(text) = std::str::from_utf8(data) else {
+        return RichContent::default();
+    };
+    if mime.to_ascii_lowercase().starts_with(PUSH_LOCATION_MIME) {
+        return RichContent {
+            location: push_location(text),
+            ..Default::default()
+        };
+    }
+    match bot_payload(text) {
+        Some(payload) => RichContent {
+            location: None,
+            cards: payload_cards(&payload),
+            suggestions: suggestions_in(payload.get("suggestions")),
+        },
+        None => RichContent::default(),
+    }
+}
+
+/// The message object of a GSMA RCS business message (`botmessage` or
+/// `botsuggestion`) payload, or `None` if it isn't one.
+fn bot_payload(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if !text.starts_with('{') {
+        return None;
+    }
+    let mut value: Value = serde_json::from_str(text).ok()?;
+    let payload = if value.get("message").is_some() {
+        value["message"].take()
+    } else {
+        value
+    };
+    let is_bot_message = [
+        "generalPurposeCard",
+        "generalPurposeCardCarousel",
+        "suggestions",
+    ]
+    .iter()
+    .any(|key| payload.get(key).is_some());
+    is_bot_message.then_some(payload)
+}
+
+fn payload_cards(payload: &Value) -> Vec<RichCard> {
+    if let Some(card) = payload.get("generalPurposeCard") {
+        return card.get("content").map(card_from).into_iter().collect();
+    }
+    payload
+        .get("generalPurposeCardCarousel")
+        .and_then(|carousel| carousel.get("content"))
+        .and_then(Value::as_array)
+        .map(|cards| cards.iter().map(card_from).collect())
+        .unwrap_or_default()
+}
+
+fn card_from(content: &Value) -> RichCard {
+    let text = |value: Option<&Value>, key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let media = content.get("media");
+    let image = |key: &str| https_url(&text(media, key)).unwrap_or_default();
+    RichCard {
+        title: text(Some(content), "title"),
+        description: text(Some(content), "description"),
+        media_url: image("mediaUrl"),
+        thumbnail_url: image("thumbnailUrl"),
+        image_url: String::new(),
+        suggestions: suggestions_in(content.get("suggestions")),
+    }
+}
+
+/// `url` if it is an https link, the only kind a business message may have
+/// us open or fetch.
+fn https_url(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .filter(|parsed| parsed.scheme() == "https")
+        .map(|_| url.to_string())
+}
+
+/// Whether a dial action's number is only a phone number, so the `tel:` URI
+/// built from it can't carry anything else.
+fn is_phone_number(number: &str) -> bool {
+    number.chars().any(|c| c.is_ascii_digit())
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | ' ' | '(' | ')'))
+}
+
+/// Chips we can act on: replies, links, calls and locations. Other actions
+/// (calendar events, device settings) are left out.
+fn suggestions_in(list: Option<&Value>) -> Vec<Suggestion> {
+    let Some(list) = list.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter_map(|suggestion| {
+            if let Some(reply) = suggestion.get("reply") {
+                let text = reply.get("displayText")?.as_str()?.to_string();
+                return Some(Suggestion {
+                    value: text.clone(),
+                    text,
+                    kind: "reply".to_string(),
+                });
+            }
+            let action = suggestion.get("action")?;
+            let text = action.get("displayText")?.as_str()?.to_string();
+            let (kind, value) = if let Some(open) = action.get("openUrlAction") {
+                ("url", https_url(open.get("url")?.as_str()?)?)
+            } else if let Some(dial) = action.get("dialerAction") {
+                let number = dial
+                    .get("dialPhoneNumber")
+                    .and_then(|d| d.get("phoneNumber"))
+                    .and_then(Value::as_str)
+                    .filter(|number| is_phone_number(number))?;
+                ("dial", format!("tel:{number}"))
+            } else if let Some(view) = action.get("viewLocationAction") {
+                let lat_long = view.get("latLong")?;
+                let location = Location::new(
+                    lat_long.get("latitude")?.as_f64()?,
+                    lat_long.get("longitude")?.as_f64()?,
+                    String::new(),
+                    String::new(),
+                )?;
+                ("location", location.url)
+            } else {
+                return None;
+            };
+            Some(Suggestion {
+                text,
+                kind: kind.to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// First shared location in some text: a `geo:` URI or a Google or Apple
+/// Maps link with coordinates in it.
+fn find_location(text: &str) -> Option<Location> {
+    text.split_whitespace().find_map(|word| {
+        let word = word
+            .trim_start_matches(['(', '<'])
+            .trim_end_matches(['.', ',', ')', ';', '!', '>']);
+        if let Some(geo) = word.strip_prefix("geo:") {
+            return geo_location(geo);
+        }
+        let url = Url::parse(word).ok()?;
+        let host = url.host_str()?.trim_start_matches("www.");
+        let query = |key: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.into_owned())
+        };
+        if host == "maps.apple.com" {
+            let (latitude, longitude) = coordinates(&query("ll")?)?;
+            let label = query("q").unwrap_or_default();
+            return Location::new(latitude, longitude, label, word.to_string());
+        }
+        let is_google_maps = host.starts_with("maps.google.")
+            || (host.starts_with("google.") && url.path().starts_with("/maps"));
+        if !is_google_maps {
+            return None;
+        }
+        // ?q=lat,lon, ?query=lat,lon, ?ll=lat,lon or /@lat,lon,zoom in the path
+        let (latitude, longitude) = ["q", "query", "ll"]
+            .iter()
+            .find_map(|key| coordinates(&query(key)?))
+            .or_else(|| {
+                let (_, at) = url.path().split_once('@')?;
+                coordinates(at)
+            })?;
+        // A place link names the place in /maps/place/<name>/
+        let label = url
+            .path_segments()
+            .and_then(|mut segments| {
+                segments.find(|s| *s == "place")?;
+                segments.next()
+            })
+            .map(|name| {
+                percent_encoding::percent_decode_str(&name.replace('+', " "))
+                    .decode_utf8_lossy()
+                    .into_owned()
+            })
+            .unwrap_or_default();
+        Location::new(latitude, longitude, label, word.to_string())
+    })
+}
+
+/// Position and label of an RCS push location, a PIDF-LO document with the
+/// coordinates in `<gml:pos>lat lon</gml:pos>` and the place name in the
+/// `label` attribute of `<rcspushlocation>`.
+fn push_location(xml: &str) -> Option<Location> {
+    let mut position = element_text(xml, "pos")?.split_whitespace();
+    let latitude = position.next()?.parse().ok()?;
+    let longitude = position.next()?.parse().ok()?;
+    let label = element_start(xml, "rcspushlocation")
+        .and_then(|(tag, _)| attribute(tag, "label"))
+        .unwrap_or_default();
+    Location::new(latitude, longitude, label, String::new())
+}
+
+/// The opening tag of the first element named `name`, with or without a
+/// namespace prefix, without its `<>`, and the offset its content starts at.
+fn element_start<'a>(xml: &'a str, name: &str) -> Option<(&'a str, usize)> {
+    xml.match_indices('<').find_map(|(start, _)| {
+        let end = start + 1 + xml[start + 1..].find('>')?;
+        let tag = &xml[start + 1..end];
+        let tag_name = tag.split_whitespace().next().unwrap_or_default();
+        let local = tag_name.rsplit(':').next().unwrap_or_default();
+        (local == name && !tag_name.starts_with('/')).then_some((tag, end + 1))
+    })
+}
+
+/// Text inside the first element named `name`.
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let (_, start) = element_start(xml, name)?;
+    let content = &xml[start..];
+    Some(content[..content.find('<')?].trim())
+}
+
+/// Unescaped value of `name="…"` in an opening tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let (_, rest) = tag.split_once(&format!(" {name}=\""))?;
+    let (value, _) = rest.split_once('"')?;
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// `lat,lon[;u=…][?q=label]` or `0,0?q=lat,lon(label)` after `geo:`.
+fn geo_location(geo: &str) -> Option<Location> {
+    let (position, query) = geo.split_once('?').unwrap_or((geo, ""));
+    let query = percent_encoding::percent_decode_str(query)
+        .decode_utf8_lossy()
+        .replace('+', " ");
+    let q = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("q="))
+        .unwrap_or_default();
+    let (q_position, label) = match q.split_once('(') {
+        Some((position, label)) => (position, label.trim_end_matches(')').trim()),
+        None => (q, ""),
+    };
+    let (latitude, longitude) = coordinates(position)
+        .filter(|&(lat, lon)| lat != 0.0 || lon != 0.0)
+        .or_else(|| coordinates(q_position))?;
+    Location::new(latitude, longitude, label.to_string(), String::new())
+}
+
+/// The leading "lat,lon" of a string, ignoring anything after the longitude.
+fn coordinates(text: &str) -> Option<(f64, f64)> {
+    let (latitude, rest) = text.split_once(',')?;
+    let longitude: String = rest
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+        .collect();
+    Some((latitude.trim().parse().ok()?, longitude.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(location: &Location) -> (f64, f64) {
+        (location.latitude, location.longitude)
+    }
+
+    #[test]
+    fn coordinates_stop_after_the_longitude() {
+        assert_eq!(coordinates("48.1,11.5"), Some((48.1, 11.5)));
+        assert_eq!(coordinates(" -33.9 , 151.2,17z"), Some((-33.9, 151.2)));
+        assert_eq!(coordinates("52.5,13.4;u=35"), Some((52.5, 13.4)));
+        assert_eq!(coordinates("52.5"), None);
+        assert_eq!(coordinates("north,13.4"), None);
+    }
+
+    #[test]
+    fn geo_uri_with_position_and_label() {
+        let location = geo_location("52.52,13.405?q=Berlin").unwrap();
+        assert_eq!(position(&location), (52.52, 13.405));
+        // Only a label inside q=lat,lon(…) counts
+        assert_eq!(location.label, "");
+        assert!(location.url.starts_with("https://www.openstreetmap.org/"));
+    }
+
+    #[test]
+    fn geo_uri_with_position_in_query() {
+        let location = geo_location("0,0?q=48.8584,2.2945(Eiffel%20Tower)").unwrap();
+        assert_eq!(position(&location), (48.8584, 2.2945));
+        assert_eq!(location.label, "Eiffel Tower");
+    }
+
+    #[test]
+    fn geo_uri_out_of_range_or_without_position() {
+        assert!(geo_location("91,10").is_none());
+        assert!(geo_location("10,181").is_none());
+        assert!(geo_location("0,0?q=coffee").is_none());
+    }
+
+    #[test]
+    fn finds_geo_uri_in_text() {
+        let location = find_location("Meet me here: geo:37.7749,-122.4194.").unwrap();
+        assert_eq!(position(&location), (37.7749, -122.4194));
+    }
+
+    #[test]
+    fn finds_google_maps_links() {
+        let location = find_location("https://maps.google.com/?q=40.7128,-74.006").unwrap();
+        assert_eq!(position(&location), (40.7128, -74.006));
+        assert_eq!(location.url, "https://maps.google.com/?q=40.7128,-74.006");
+
+        let location = find_location(
+            "(https://www.google.com/maps/place/Brandenburger+Tor/@52.5163,13.3777,17z)",
+        )
+        .unwrap();
+        assert_eq!(position(&location), (52.5163, 13.3777));
+        assert_eq!(location.label, "Brandenburger Tor");
+    }
+
+    #[test]
+    fn finds_apple_maps_links() {
+        let location =
+            find_location("https://maps.apple.com/?ll=51.5007,-0.1246&q=Big%20Ben").unwrap();
+        assert_eq!(position(&location), (51.5007, -0.1246));
+        assert_eq!(location.label, "Big Ben");
+    }
+
+    #[test]
+    fn ignores_other_links_and_text() {
+        assert!(find_location("https://www.google.com/search?q=1,2").is_none());
+        assert!(find_location("https://example.com/?q=1,2").is_none());
+        assert!(find_location("see you at 5,30").is_none());
+    }
+
+    #[test]
+    fn push_location_payload() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rcsenvelope xmlns="urn:gsma:params:xml:ns:rcs:rcs:geolocation" xmlns:gml="http://www.opengis.net/gml">
+  <rcspushlocation id="a1" label="Caf&eacute; &amp; Bar">
+    <geopriv><location-info><gml:Circle srsName="urn:ogc:def:crs:EPSG::4326">
+      <gml:pos>48.1372 11.5756</gml:pos>
+    </gml:Circle></location-info></geopriv>
+  </rcspushlocation>
+</rcsenvelope>"#;
+        let rich = parse_typed_content(PUSH_LOCATION_MIME, xml.as_bytes());
+        let location = rich.location.unwrap();
+        assert_eq!(position(&location), (48.1372, 11.5756));
+        assert_eq!(location.label, "Caf&eacute; & Bar");
+    }
+
+    #[test]
+    fn bot_message_payload() {
+        let json = r#"{"message":{"generalPurposeCard":{"content":{
+            "title":"Your order","description":"Arrives today",
+            "media":{"mediaUrl":"https://example.com/a.jpg"},
+            "suggestions":[{"reply":{"displayText":"Track"}}]}}}}"#;
+        let rich = parse_typed_content(
+            "application/vnd.gsma.botmessage.v1.0+json; charset=utf-8",
+            json.as_bytes(),
+        );
+        assert_eq!(rich.cards.len(), 1);
+        assert_eq!(rich.cards[0].title, "Your order");
+        assert_eq!(
+            rich.cards[0].image_source(),
+            Some("https://example.com/a.jpg")
+        );
+        assert_eq!(rich.cards[0].suggestions[0].kind, "reply");
+        // QML only ever sees the cached copy
+        assert!(!rich.cards_json().contains("example.com"));
+
+        let json = r#"{"suggestions":[{"action":{"displayText":"Call",
+            "dialerAction":{"dialPhoneNumber":{"phoneNumber":"+15551234"}}}}]}"#;
+        let rich = parse_typed_content(BOT_SUGGESTION_MIME, json.as_bytes());
+        assert_eq!(rich.suggestions[0].value, "tel:+15551234");
+    }
+
+    #[test]
+    fn only_https_links_and_phone_numbers_are_kept() {
+        let json = r#"{"suggestions":[
+            {"action":{"displayText":"File","openUrlAction":{"url":"file:///etc/passwd"}}},
+            {"action":{"displayText":"Web","openUrlAction":{"url":"http://example.com/"}}},
+            {"action":{"displayText":"Call",
+                "dialerAction":{"dialPhoneNumber":{"phoneNumber":"123?body=hi"}}}},
+            {"action":{"displayText":"Site","openUrlAction":{"url":"https://example.com/"}}}],
+            "generalPurposeCard":{"content":{"title":"Card","media":{
+                "mediaUrl":"file:///home/me/photo.jpg",
+                "thumbnailUrl":"http://example.com/thumb.jpg"}}}}"#;
+        let rich = parse_typed_content(BOT_MESSAGE_MIME, json.as_bytes());
+        let values: Vec<_> = rich.suggestions.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, ["https://example.com/"]);
+        assert_eq!(rich.cards[0].image_source(), None);
+    }
+
+    #[test]
+    fn other_content_types_are_not_rich() {
+        assert!(!is_rich_content_mime("image/jpeg"));
+        assert!(is_rich_content_mime(
+            "Application/vnd.gsma.rcspushlocation+xml"
+        ));
+        assert!(parse_typed_content("text/plain", b"geo:1,2").is_empty());
+    }
+}
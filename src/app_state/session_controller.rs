@@ -66,10 +66,9 @@ impl crate::ffi::SessionController {
                             // The library now refreshes in-place and returns
                             // only success/failure. Persist the auth snapshot
                             // so any refreshed token survives restarts.
-                            let store = libgmessages_rs::store::AuthDataStore::default_store();
                             let auth_handle = client.auth();
                             let auth = auth_handle.lock().await;
-                            if let Err(e) = store.save(&auth) {
+                            if let Err(e) = save_auth(&auth).await {
                                 eprintln!("failed to save refreshed auth: {e}");
                             }
                         }
@@ -291,7 +290,12 @@ pub async fn run_long_poll_loop(
         let Some(event) = updates.event else { continue };
         let response_id = data.response_id.clone();
 
-        if catch_up_mode {
+        // SIM settings are current whenever they arrive, so they skip catch-up filtering
+        let is_settings = matches!(
+            event,
+            libgmessages_rs::proto::events::update_events::Event::SettingsEvent(_)
+        );
+        if catch_up_mode && !is_settings {
             if !event_is_recent_enough(&event, recent_cutoff_micros) {
                 continue;
             }
@@ -311,6 +315,8 @@ pub async fn run_long_poll_loop(
                 }
 
                 for message in message_event.data {
+                    record_message_details(&message);
+                    invalidate_gallery(&message);
                     let body = extract_message_body(&message);
                     let conversation_id = message.conversation_id.clone();
                     let participant_id = message.participant_id.clone();
@@ -390,6 +396,7 @@ pub async fn run_long_poll_loop(
                             }
                         })
                         .unwrap_or_default();
+                    let participant_numbers = participant_numbers(&convo).join(",");
 
                     let _ =
                         qt_thread.queue(move |mut qobject: Pin<&mut ffi::SessionController>| {
@@ -402,11 +409,19 @@ pub async fn run_long_poll_loop(
                                 is_group_chat,
                                 status,
                                 &QString::from(avatar_identifier.as_str()),
+                                &QString::from(participant_numbers.as_str()),
                             );
                         });
                 }
             }
-            _ => {} // Ignore typing events, settings, etc. for now
+            libgmessages_rs::proto::events::update_events::Event::SettingsEvent(settings) => {
+                let sims: Vec<Sim> = settings.data.iter().filter_map(Sim::from_proto).collect();
+                set_sims(sims);
+                let _ = qt_thread.queue(|mut qobject: Pin<&mut ffi::SessionController>| {
+                    qobject.as_mut().sims_updated();
+                });
+            }
+            _ => {} // Ignore typing events etc. for now
         }
     };
 
@@ -487,22 +502,42 @@ pub fn extract_message_id(message: &libgmessages_rs::proto::conversations::Messa
 }
 
 /// Extract media information from a Message, if present.
-pub fn extract_message_media(
+/// One media attachment of a message.
+#[derive(Clone)]
+pub struct MediaPart {
+    pub media_id: String,
+    pub decryption_key: Vec<u8>,
+    pub mime: String,
+    pub width: i64,
+    pub height: i64,
+    /// Original file name as sent, empty when unknown.
+    pub file_name: String,
+    /// Size in bytes, 0 when unknown.
+    pub size: i64,
+    /// Preview the sender's phone attached, e.g. a video's poster frame.
+    /// Empty when there is none besides the media itself.
+    pub thumbnail_id: String,
+    pub thumbnail_key: Vec<u8>,
+}
+
+/// Every media attachment of a message, in the order they were sent.
+pub fn extract_media_parts(
     message: &libgmessages_rs::proto::conversations::Message,
-) -> Option<(String, Vec<u8>, String, i64, i64)> {
+) -> Vec<MediaPart> {
     message
         .message_info
         .iter()
-        .find_map(|info| match &info.data {
+        .filter_map(|info| match &info.data {
             Some(libgmessages_rs::proto::conversations::message_info::Data::MediaContent(
                 media,
-            )) => {
-                let id = if !media.media_id.is_empty() {
+            )) if !is_rich_content_mime(&media.mime_type) => {
+                let has_full_media = !media.media_id.is_empty();
+                let media_id = if has_full_media {
                     media.media_id.clone()
                 } else {
                     media.thumbnail_media_id.clone()
                 };
-                let key = if !media.decryption_key.is_empty() {
+                let decryption_key = if !media.decryption_key.is_empty() {
                     media.decryption_key.clone()
                 } else {
                     media.thumbnail_decryption_key.clone()
@@ -512,8 +547,51 @@ pub fn extract_message_media(
                 } else {
                     (0, 0)
                 };
-                Some((id, key, media.mime_type.clone(), width, height))
+                Some(MediaPart {
+                    media_id,
+                    decryption_key,
+                    mime: incoming_mime(&media.mime_type, &media.media_name),
+                    width,
+                    height,
+                    file_name: media.media_name.clone(),
+                    size: media.size,
+                    thumbnail_id: if has_full_media {
+                        media.thumbnail_media_id.clone()
+                    } else {
+                        String::new()
+                    },
+                    thumbnail_key: if has_full_media {
+                        media.thumbnail_decryption_key.clone()
+                    } else {
+                        Vec::new()
+                    },
+                })
             }
             _ => None,
         })
+        .collect()
+}
+
+pub fn extract_message_media(
+    message: &libgmessages_rs::proto::conversations::Message,
+) -> Option<(String, Vec<u8>, String, i64, i64)> {
+    extract_media_parts(message).into_iter().next().map(|part| {
+        (
+            part.media_id,
+            part.decryption_key,
+            part.mime,
+            part.width,
+            part.height,
+        )
+    })
+}
+
+/// Original file name and size in bytes of a message's attachment, if any.
+pub fn extract_message_attachment(
+    message: &libgmessages_rs::proto::conversations::Message,
+) -> Option<(String, i64)> {
+    extract_media_parts(message)
+        .into_iter()
+        .next()
+        .map(|part| (part.file_name, part.size))
 }
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+// ── Persistent settings ──────────────────────────────────────────
+
+/// User preferences, stored as JSON in `~/.config/kourier/settings.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Encrypt media, avatar and link preview caches with a key held in the keyring.
+    pub encrypted_cache: bool,
+    /// Size limits for each on-disk cache category.
+    pub cache_budgets: CacheBudgets,
+    /// SIM number to send from, per conversation. Missing means the phone's default.
+    pub conversation_sims: HashMap<String, i32>,
+    /// How to send in each conversation. Missing means `Auto`.
+    pub conversation_transports: HashMap<String, TransportPreference>,
+    /// How aggressively outgoing photos and videos are shrunk before upload.
+    pub media_quality: MediaQuality,
+    /// Send photos with their EXIF/XMP metadata (location, camera) intact.
+    pub keep_photo_metadata: bool,
+    /// Which links are fetched to show a preview, and how.
+    pub link_previews: LinkPreviewSettings,
+}
+
+/// Whose links are fetched for a preview. Fetching a link tells its server
+/// your IP address and that the message was read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkPreviewPolicy {
+    Off,
+    /// Only links I sent.
+    OwnMessages,
+    /// Links I sent and links from people in my contacts.
+    KnownContacts,
+    #[default]
+    Always,
+}
+
+impl LinkPreviewPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "own_messages" => Some(Self::OwnMessages),
+            "known_contacts" => Some(Self::KnownContacts),
+            "always" => Some(Self::Always),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::OwnMessages => "own_messages",
+            Self::KnownContacts => "known_contacts",
+            Self::Always => "always",
+        }
+    }
+}
+
+/// Link preview fetching. Domains match themselves and their subdomains.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkPreviewSettings {
+    pub policy: LinkPreviewPolicy,
+    /// When not empty, only links to these domains are previewed.
+    pub allowed_domains: Vec<String>,
+    /// Links to these domains are never previewed, nor are images or
+    /// redirects pointing at them.
+    pub blocked_domains: Vec<String>,
+    /// Most of a page read when looking for its title, in kilobytes.
+    pub max_page_kb: u64,
+    /// Largest preview image downloaded, in kilobytes.
+    pub max_image_kb: u64,
+    /// Largest width or height of a preview image, in pixels.
+    pub max_image_dimension: u32,
+    /// Proxy for preview requests, e.g. `socks5h://127.0.0.1:9050`. Empty
+    /// connects directly.
+    pub proxy: String,
+}
+
+impl Default for LinkPreviewSettings {
+    fn default() -> Self {
+        Self {
+            policy: LinkPreviewPolicy::default(),
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+            max_page_kb: 512,
+            max_image_kb: 2048,
+            max_image_dimension: 4096,
+            proxy: String::new(),
+        }
+    }
+}
+
+impl LinkPreviewSettings {
+    /// Whether the domain lists let `url` be fetched.
+    pub fn allows_url(&self, url: &str) -> bool {
+        let Some(host) = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        else {
+            return false;
+        };
+        let matches = |domain: &String| {
+            let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+            !domain.is_empty() && (host == domain || host.ends_with(&format!(".{domain}")))
+        };
+        !self.blocked_domains.iter().any(matches)
+            && (self.allowed_domains.is_empty() || self.allowed_domains.iter().any(matches))
+    }
+}
+
+/// Re-encoding applied to outgoing photos and videos. MMS limits always
+/// apply, whatever the setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaQuality {
+    /// Send files as they are unless they exceed the carrier's limit.
+    Original,
+    /// Downscale very large photos and oversized videos.
+    #[default]
+    High,
+    /// Shrink everything to save mobile data.
+    DataSaver,
+}
+
+impl MediaQuality {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "original" => Some(Self::Original),
+            "high" => Some(Self::High),
+            "data_saver" => Some(Self::DataSaver),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Original => "original",
+            Self::High => "high",
+            Self::DataSaver => "data_saver",
+        }
+    }
+}
+
+/// Which transport outgoing messages should use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportPreference {
+    /// Let the phone pick, as Google Messages does.
+    #[default]
+    Auto,
+    Rcs,
+    /// SMS for text, MMS for attachments.
+    Sms,
+}
+
+impl TransportPreference {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "rcs" => Some(Self::Rcs),
+            "sms" => Some(Self::Sms),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Rcs => "rcs",
+            Self::Sms => "sms",
+        }
+    }
+}
+
+/// Per-category cache budgets in megabytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheBudgets {
+    pub media: u64,
+    pub avatars: u64,
+    pub link_previews: u64,
+}
+
+impl Default for CacheBudgets {
+    fn default() -> Self {
+        Self {
+            media: 1024,
+            avatars: 64,
+            link_previews: 128,
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("kourier")
+        .join("settings.json")
+}
+
+fn settings_lock() -> &'static RwLock<Settings> {
+    static INSTANCE: OnceLock<RwLock<Settings>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let loaded = std::fs::read(settings_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        RwLock::new(loaded)
+    })
+}
+
+/// A snapshot of the current settings.
+pub fn settings() -> Settings {
+    settings_lock().read().unwrap().clone()
+}
+
+/// Whether cache entries are encrypted, without copying the rest of the settings.
+pub fn encrypted_cache() -> bool {
+    settings_lock().read().unwrap().encrypted_cache
+}
+
+pub fn cache_budgets() -> CacheBudgets {
+    settings_lock().read().unwrap().cache_budgets.clone()
+}
+
+pub fn conversation_transport(conversation_id: &str) -> TransportPreference {
+    settings_lock()
+        .read()
+        .unwrap()
+        .conversation_transports
+        .get(conversation_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn set_conversation_transport(conversation_id: &str, transport: TransportPreference) {
+    update_settings(|s| {
+        if transport == TransportPreference::Auto {
+            s.conversation_transports.remove(conversation_id);
+        } else {
+            s.conversation_transports.insert(conversation_id.to_string(), transport);
+        }
+    });
+}
+
+/// Modify the settings and write them back to disk.
+pub fn update_settings(change: impl FnOnce(&mut Settings)) {
+    let snapshot = {
+        let mut guard = settings_lock().write().unwrap();
+        change(&mut guard);
+        guard.clone()
+    };
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_vec_pretty(&snapshot) {
+        Ok(bytes) => {
+            if let Err(e) = std::fs::write(&path, bytes) {
+                eprintln!("settings: failed to write {}: {e}", path.display());
+            }
+        }
+        Err(e) => eprintln!("settings: failed to serialise: {e}"),
+    }
+}
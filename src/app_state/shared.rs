@@ -1,4 +1,8 @@
-use libgmessages_rs::{gmclient::GMClient, store::AuthDataStore};
+use crate::app_state::avatar_cache::{
+    avatar_uri, cached_avatar_urls, lookup_avatar, record_avatars, AvatarLookup,
+};
+use crate::app_state::cache::{now_secs, safe_cache_name, write_cache_file, CacheCategory};
+use libgmessages_rs::gmclient::GMClient;
 use std::sync::OnceLock;
 
 // ── Shared session infrastructure ────────────────────────────────
@@ -23,7 +27,7 @@ pub fn shared() -> &'static SharedSession {
         SharedSession {
             runtime,
             client: tokio::sync::RwLock::new(None),
-            avatars: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+            avatars: tokio::sync::RwLock::new(cached_avatar_urls()),
             handler: tokio::sync::RwLock::new(None),
         }
     })
@@ -38,6 +42,12 @@ where
     shared().runtime.spawn(future)
 }
 
+/// Run a future to completion on the shared runtime, blocking the caller.
+/// Only for threads outside the runtime (e.g. the image provider's pool).
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    shared().runtime.block_on(future)
+}
+
 /// Get a clone of the current GMClient, or None if not logged in.
 pub async fn get_client() -> Option<GMClient> {
     shared().client.read().await.clone()
@@ -54,18 +64,16 @@ pub async fn clear_client() {
     *shared().handler.write().await = None;
 }
 
-/// Helper: load auth from disk and create+store a GMClient.
+/// Helper: load auth from storage and create+store a GMClient.
 /// Returns the client or an error string.
 pub async fn ensure_client() -> Result<GMClient, String> {
     // Fast path: already have a client
     if let Some(c) = get_client().await {
         return Ok(c);
     }
-    // Slow path: load from disk
-    let store = AuthDataStore::default_store();
-    let auth = store
-        .load()
-        .map_err(|e| e.to_string())?
+    // Slow path: load from the keyring or disk
+    let auth = crate::app_state::auth_storage::load_auth()
+        .await?
         .ok_or_else(|| "not logged in".to_string())?;
     let client = GMClient::new(auth);
     set_client(client.clone()).await;
@@ -93,7 +101,19 @@ pub async fn make_handler(
     Ok(handler)
 }
 
+/// Avatar cache file name without the extension, which depends on the image data.
+fn avatar_cache_stem(identifier: &str) -> String {
+    let safe_id = safe_cache_name(identifier);
+    if safe_id.is_empty() {
+        "unknown".to_string()
+    } else {
+        safe_id
+    }
+}
+
 /// Helper: fetch avatars asynchronously and cache them.
+/// Avatars known from earlier runs are served from disk, and contacts known
+/// to have no photo are skipped until their negative entry expires.
 pub async fn fetch_avatars_async(
     client: GMClient,
     identifiers: Vec<String>,
@@ -101,13 +121,22 @@ pub async fn fetch_avatars_async(
     let mut results = std::collections::HashMap::new();
     let mut to_fetch = Vec::new();
     {
-        let cache = shared().avatars.read().await;
+        let mut cache = shared().avatars.write().await;
         for id in &identifiers {
             if let Some(url) = cache.get(id) {
                 results.insert(id.clone(), url.clone());
-            } else {
-                if !to_fetch.contains(id) {
-                    to_fetch.push(id.clone());
+                continue;
+            }
+            match lookup_avatar(id) {
+                AvatarLookup::Hit(url) => {
+                    cache.insert(id.clone(), url.clone());
+                    results.insert(id.clone(), url);
+                }
+                AvatarLookup::NoPhoto => {}
+                AvatarLookup::Miss => {
+                    if !to_fetch.contains(id) {
+                        to_fetch.push(id.clone());
+                    }
                 }
             }
         }
@@ -116,88 +145,123 @@ pub async fn fetch_avatars_async(
         return results;
     }
 
-    if let Ok(handler) = make_handler(&client).await {
-        let request = libgmessages_rs::proto::client::GetThumbnailRequest {
-            identifiers: to_fetch.clone(),
-        };
-        let attempts = [
-            (
-                true,
-                libgmessages_rs::proto::rpc::MessageType::BugleAnnotation,
-            ),
-            (true, libgmessages_rs::proto::rpc::MessageType::BugleMessage),
-            (
-                false,
-                libgmessages_rs::proto::rpc::MessageType::BugleAnnotation,
-            ),
-            (
-                false,
-                libgmessages_rs::proto::rpc::MessageType::BugleMessage,
-            ),
-            (
-                false,
-                libgmessages_rs::proto::rpc::MessageType::UnknownMessageType,
-            ),
-        ];
-
-        for (encrypted, message_type) in attempts {
-            let attempt: Result<libgmessages_rs::proto::client::GetThumbnailResponse, _> =
-                if encrypted {
-                    handler
-                        .send_request(
-                            libgmessages_rs::proto::rpc::ActionType::GetContactsThumbnail,
-                            message_type,
-                            &request,
-                        )
-                        .await
-                } else {
-                    handler
-                        .send_request_dont_encrypt(
-                            libgmessages_rs::proto::rpc::ActionType::GetContactsThumbnail,
-                            message_type,
-                            &request,
-                            std::time::Duration::from_secs(5),
-                        )
-                        .await
-                };
-            if let Ok(response) = attempt {
-                if !response.thumbnail.is_empty() {
-                    let mut cache = shared().avatars.write().await;
-                    for thumb in response.thumbnail {
-                        if let Some(data) = thumb.data.as_ref() {
-                            if !data.image_buffer.is_empty() {
-                                let ext =
-                                    crate::app_state::utils::detect_extension(&data.image_buffer);
-
-                                let safe_id = thumb
-                                    .identifier
-                                    .replace("/", "_")
-                                    .replace("+", "_")
-                                    .replace("=", "")
-                                    .replace("-", "_");
-                                let safe_id = if safe_id.is_empty() {
-                                    "unknown".to_string()
-                                } else {
-                                    safe_id
-                                };
-
-                                let tmp_dir = std::env::temp_dir().join("kourier_avatars");
-                                let _ = std::fs::create_dir_all(&tmp_dir);
-                                let path = tmp_dir.join(format!("{}.{}", safe_id, ext));
-
-                                let _ = std::fs::write(&path, &data.image_buffer);
-                                let url = format!("file://{}", path.to_string_lossy());
-
-                                cache.insert(thumb.identifier.clone(), url.clone());
-                                results.insert(thumb.identifier.clone(), url);
-                            }
-                        }
-                    }
-                    break;
-                }
+    results.extend(download_avatars(&client, to_fetch).await);
+    results
+}
+
+/// Request thumbnails from the phone, write them to the avatar cache and
+/// record which contacts came back without a photo.
+pub async fn download_avatars(
+    client: &GMClient,
+    identifiers: Vec<String>,
+) -> std::collections::HashMap<String, String> {
+    let mut results = std::collections::HashMap::new();
+    let Some(thumbnails) = request_thumbnails(client, &identifiers).await else {
+        // No attempt got an answer; try again next time rather than caching a miss
+        return results;
+    };
+
+    let mut records = Vec::new();
+    let mut failed = Vec::new();
+    let fetched_at = now_secs();
+    {
+        let mut cache = shared().avatars.write().await;
+        for thumb in thumbnails {
+            let Some(data) = thumb.data.as_ref() else {
+                continue;
+            };
+            if data.image_buffer.is_empty() {
+                continue;
+            }
+            let ext = crate::app_state::utils::detect_extension(&data.image_buffer);
+            let file_name = format!("{}.{}", avatar_cache_stem(&thumb.identifier), ext);
+
+            if write_cache_file(
+                CacheCategory::Avatars,
+                &file_name,
+                &data.image_buffer,
+                &format!("image/{ext}"),
+            )
+            .is_err()
+            {
+                failed.push(thumb.identifier.clone());
+                continue;
             }
+            let url = avatar_uri(&file_name, fetched_at);
+
+            cache.insert(thumb.identifier.clone(), url.clone());
+            results.insert(thumb.identifier.clone(), url);
+            records.push((thumb.identifier.clone(), Some(file_name)));
+        }
+    }
+    for id in identifiers {
+        if !results.contains_key(&id) && !failed.contains(&id) {
+            records.push((id, None));
         }
     }
+    record_avatars(records, fetched_at);
 
     results
 }
+
+/// Try the known request variants in turn. `Some(empty)` means the phone
+/// answered but had no photos; `None` means every attempt failed.
+async fn request_thumbnails(
+    client: &GMClient,
+    identifiers: &[String],
+) -> Option<Vec<libgmessages_rs::proto::client::get_thumbnail_response::Thumbnail>> {
+    let handler = make_handler(client).await.ok()?;
+    let request = libgmessages_rs::proto::client::GetThumbnailRequest {
+        identifiers: identifiers.to_vec(),
+    };
+    let attempts = [
+        (
+            true,
+            libgmessages_rs::proto::rpc::MessageType::BugleAnnotation,
+        ),
+        (true, libgmessages_rs::proto::rpc::MessageType::BugleMessage),
+        (
+            false,
+            libgmessages_rs::proto::rpc::MessageType::BugleAnnotation,
+        ),
+        (
+            false,
+            libgmessages_rs::proto::rpc::MessageType::BugleMessage,
+        ),
+        (
+            false,
+            libgmessages_rs::proto::rpc::MessageType::UnknownMessageType,
+        ),
+    ];
+
+    let mut answered = false;
+    for (encrypted, message_type) in attempts {
+        let attempt: Result<libgmessages_rs::proto::client::GetThumbnailResponse, _> =
+            if encrypted {
+                handler
+                    .send_request(
+                        libgmessages_rs::proto::rpc::ActionType::GetContactsThumbnail,
+                        message_type,
+                        &request,
+                    )
+                    .await
+            } else {
+                handler
+                    .send_request_dont_encrypt(
+                        libgmessages_rs::proto::rpc::ActionType::GetContactsThumbnail,
+                        message_type,
+                        &request,
+                        std::time::Duration::from_secs(5),
+                    )
+                    .await
+            };
+        if let Ok(response) = attempt {
+            if !response.thumbnail.is_empty() {
+                return Some(response.thumbnail);
+            }
+            answered = true;
+        }
+    }
+
+    answered.then(Vec::new)
+}
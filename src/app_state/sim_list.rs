@@ -0,0 +1,223 @@
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::QString;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use crate::ffi::QHash_i32_QByteArray;
+use crate::ffi::QModelIndex;
+use crate::ffi::QVariant;
+
+use super::*;
+
+// ── SimList ──────────────────────────────────────────────────────
+
+/// A SIM card in the paired phone, from the session's settings events.
+#[derive(Clone)]
+pub struct Sim {
+    pub sim_number: i32,
+    pub carrier_name: String,
+    pub formatted_number: String,
+    pub colour: String,
+    pub payload: libgmessages_rs::proto::settings::SimPayload,
+}
+
+impl Sim {
+    pub fn from_proto(card: &libgmessages_rs::proto::settings::SimCard) -> Option<Sim> {
+        let data = card.sim_data.as_ref()?;
+        let payload = data.sim_payload.clone()?;
+        Some(Sim {
+            sim_number: payload.sim_number,
+            carrier_name: data.carrier_name.clone(),
+            formatted_number: data.formatted_phone_number.clone(),
+            colour: data.color_hex.clone(),
+            payload,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        if self.carrier_name.is_empty() {
+            format!("SIM {}", self.sim_number)
+        } else {
+            self.carrier_name.clone()
+        }
+    }
+}
+
+/// How long a send waits for the phone's first settings event before using
+/// the default SIM.
+const SIMS_REPORT_WAIT: Duration = Duration::from_secs(5);
+
+fn sims_lock() -> &'static RwLock<Vec<Sim>> {
+    static INSTANCE: OnceLock<RwLock<Vec<Sim>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Set once the phone has reported its SIMs this session.
+static SIMS_REPORTED: AtomicBool = AtomicBool::new(false);
+
+fn sims_reported() -> &'static Notify {
+    static INSTANCE: OnceLock<Notify> = OnceLock::new();
+    INSTANCE.get_or_init(Notify::new)
+}
+
+/// Replace the known SIMs with those from a settings event.
+pub fn set_sims(mut sims: Vec<Sim>) {
+    sims.sort_by_key(|sim| sim.sim_number);
+    *sims_lock().write().unwrap() = sims;
+    SIMS_REPORTED.store(true, Ordering::Release);
+    sims_reported().notify_waiters();
+}
+
+pub fn sims() -> Vec<Sim> {
+    sims_lock().read().unwrap().clone()
+}
+
+/// SIM number a message was sent or received on, 0 when unknown.
+pub fn message_sim_number(message: &libgmessages_rs::proto::conversations::Message) -> i32 {
+    message
+        .sim_card
+        .as_ref()
+        .and_then(|card| card.sim_data.as_ref())
+        .and_then(|data| data.sim_payload.as_ref())
+        .map(|payload| payload.sim_number)
+        .unwrap_or(0)
+}
+
+/// Label to show on a bubble, empty unless the phone has more than one SIM.
+pub fn sim_label(sim_number: i32) -> String {
+    let sims = sims_lock().read().unwrap();
+    if sims.len() < 2 {
+        return String::new();
+    }
+    sims.iter()
+        .find(|sim| sim.sim_number == sim_number)
+        .map(Sim::label)
+        .unwrap_or_default()
+}
+
+/// `SendMessageRequest.sim_payload` for a SIM number, or `None` to let the
+/// phone use its default SIM. SIMs are only known once the phone's first
+/// settings event arrives, so right after startup this waits briefly for it.
+/// A SIM the phone still hasn't reported falls back to the default.
+pub async fn sim_payload(sim_number: i32) -> Option<libgmessages_rs::proto::settings::SimPayload> {
+    if sim_number <= 0 {
+        return None;
+    }
+    // Created before checking the flag so a report in between isn't missed
+    let reported = sims_reported().notified();
+    if !SIMS_REPORTED.load(Ordering::Acquire) {
+        let _ = tokio::time::timeout(SIMS_REPORT_WAIT, reported).await;
+    }
+    let payload = sims_lock()
+        .read()
+        .unwrap()
+        .iter()
+        .find(|sim| sim.sim_number == sim_number)
+        .map(|sim| sim.payload.clone());
+    if payload.is_none() {
+        eprintln!("send: SIM {sim_number} is not known, using the default SIM");
+    }
+    payload
+}
+
+/// The SIM remembered for a conversation, 0 for the phone's default.
+pub fn conversation_sim(conversation_id: &str) -> i32 {
+    settings()
+        .conversation_sims
+        .get(conversation_id)
+        .copied()
+        .unwrap_or(0)
+}
+
+pub fn set_conversation_sim(conversation_id: &str, sim_number: i32) {
+    update_settings(|s| {
+        if sim_number <= 0 {
+            s.conversation_sims.remove(conversation_id);
+        } else {
+            s.conversation_sims.insert(conversation_id.to_string(), sim_number);
+        }
+    });
+}
+
+pub struct SimListRust {
+    items: Vec<Sim>,
+}
+
+impl Default for SimListRust {
+    fn default() -> Self {
+        Self { items: sims() }
+    }
+}
+
+impl crate::ffi::SimList {
+    pub fn row_count(&self, _parent: &QModelIndex) -> i32 {
+        self.items.len() as i32
+    }
+
+    pub fn data(&self, index: &QModelIndex, role: i32) -> QVariant {
+        let row = index.row() as usize;
+        if row >= self.items.len() {
+            return QVariant::default();
+        }
+
+        let item = &self.items[row];
+        match role {
+            0 => QVariant::from(&QString::from(item.label().as_str())),
+            1 => QVariant::from(&QString::from(item.carrier_name.as_str())),
+            2 => QVariant::from(&QString::from(item.formatted_number.as_str())),
+            3 => QVariant::from(&item.sim_number),
+            4 => QVariant::from(&QString::from(item.colour.as_str())),
+            _ => QVariant::default(),
+        }
+    }
+
+    pub fn role_names(&self) -> QHash_i32_QByteArray {
+        let mut roles = QHash_i32_QByteArray::default();
+        roles.insert(0, "label".into());
+        roles.insert(1, "carrier_name".into());
+        roles.insert(2, "formatted_number".into());
+        roles.insert(3, "sim_number".into());
+        roles.insert(4, "colour".into());
+        roles
+    }
+
+    /// Reload the SIMs after the session controller emits sims_updated.
+    pub fn refresh(mut self: Pin<&mut Self>) {
+        self.as_mut().begin_reset_model();
+        self.as_mut().rust_mut().items = sims();
+        self.as_mut().end_reset_model();
+    }
+
+    /// Row of the SIM remembered for a conversation, -1 for the default SIM.
+    pub fn row_for_conversation(&self, conversation_id: &QString) -> i32 {
+        let sim_number = conversation_sim(&conversation_id.to_string());
+        self.items
+            .iter()
+            .position(|sim| sim.sim_number == sim_number)
+            .map(|pos| pos as i32)
+            .unwrap_or(-1)
+    }
+
+    /// Remember the SIM at `row` for a conversation; -1 reverts to the default.
+    pub fn set_conversation_sim(&self, conversation_id: &QString, row: i32) {
+        let sim_number = usize::try_from(row)
+            .ok()
+            .and_then(|row| self.items.get(row))
+            .map(|sim| sim.sim_number)
+            .unwrap_or(0);
+        set_conversation_sim(&conversation_id.to_string(), sim_number);
+    }
+
+    pub fn sim_number_at(&self, row: i32) -> i32 {
+        usize::try_from(row)
+            .ok()
+            .and_then(|row| self.items.get(row))
+            .map(|sim| sim.sim_number)
+            .unwrap_or(0)
+    }
+}
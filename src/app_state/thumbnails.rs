@@ -0,0 +1,339 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use tokio::sync::Semaphore;
+
+use super::*;
+
+// ── Thumbnails ───────────────────────────────────────────────────
+
+/// Longest side of generated thumbnails, the size bubbles decode images at.
+pub const THUMBNAIL_SIZE: u32 = 400;
+/// Thumbnails generated at once, so opening a thread full of videos doesn't
+/// start a dozen decoders.
+const MAX_CONCURRENT: usize = 2;
+/// Largest `moov` box read when looking for embedded cover art.
+const MAX_MOOV_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Where a thumbnail is made from.
+pub enum ThumbnailSource {
+    /// A local file, such as an attachment being sent.
+    File(PathBuf),
+    /// A media cache entry, downloaded first if needed.
+    Media(String),
+}
+
+impl ThumbnailSource {
+    /// Media cache entry the thumbnail is stored as. Local files are keyed by
+    /// path, size and modification time so an edited file gets a new one.
+    fn cache_name(&self) -> Option<String> {
+        match self {
+            ThumbnailSource::Media(file_name) => Some(format!("{file_name}.thumb.jpg")),
+            ThumbnailSource::File(path) => {
+                let meta = std::fs::metadata(path).ok()?;
+                let mut hasher = DefaultHasher::new();
+                path.hash(&mut hasher);
+                meta.len().hash(&mut hasher);
+                meta.modified().ok().hash(&mut hasher);
+                Some(format!("local-{:016x}.thumb.jpg", hasher.finish()))
+            }
+        }
+    }
+}
+
+fn permits() -> &'static Semaphore {
+    static INSTANCE: OnceLock<Semaphore> = OnceLock::new();
+    INSTANCE.get_or_init(|| Semaphore::new(MAX_CONCURRENT))
+}
+
+/// Name of the sender-provided preview of a received attachment, registered
+/// as a media source next to the attachment itself.
+pub fn poster_cache_name(file_name: &str) -> String {
+    format!("{file_name}.poster")
+}
+
+/// URI of a JPEG thumbnail of an image or video, at most `THUMBNAIL_SIZE`
+/// pixels on its longest side. Thumbnails are made once and kept in the
+/// media cache; decoding runs on the blocking pool, never the Qt thread.
+///
+/// Videos use, in order: the preview sent along with a received attachment,
+/// cover art embedded in the file, and a frame decoded by ffmpeg if it is
+/// installed. `None` when none of those work.
+pub async fn thumbnail_uri(source: ThumbnailSource, mime: &str) -> Option<String> {
+    let name = source.cache_name()?;
+    if cache_contains(CacheCategory::Media, &name) {
+        return Some(provider_uri(CacheCategory::Media, &name));
+    }
+    let _permit = permits().acquire().await.ok()?;
+    // Another request may have made it while this one waited
+    if cache_contains(CacheCategory::Media, &name) {
+        return Some(provider_uri(CacheCategory::Media, &name));
+    }
+
+    let mut poster = None;
+    if let ThumbnailSource::Media(file_name) = &source {
+        if mime.starts_with("video/") {
+            poster = fetch_media(&poster_cache_name(file_name)).await;
+        }
+        if poster.is_none() && !ensure_media_cached(file_name).await {
+            return None;
+        }
+    }
+
+    let mime = mime.to_string();
+    let jpeg = tokio::task::spawn_blocking(move || {
+        let data = match poster {
+            Some(poster) => poster,
+            None => source_image(&source, &mime)?,
+        };
+        scale_to_thumbnail(&data)
+    })
+    .await
+    .ok()??;
+
+    match write_cache_file(CacheCategory::Media, &name, &jpeg, "image/jpeg") {
+        Ok(uri) => Some(uri),
+        Err(e) => {
+            eprintln!("thumbnails: failed to cache {name}: {e}");
+            None
+        }
+    }
+}
+
+/// Full-size image data to scale down: the image itself, converted if Qt
+/// and the image crate can't read it, or a still from a video.
+fn source_image(source: &ThumbnailSource, mime: &str) -> Option<Vec<u8>> {
+    if mime.starts_with("video/") {
+        let path = match source {
+            ThumbnailSource::File(path) => path.clone(),
+            ThumbnailSource::Media(file_name) => playback_path(CacheCategory::Media, file_name)?,
+        };
+        return mp4_cover_art(&path).or_else(|| ffmpeg_frame(&path));
+    }
+    match source {
+        ThumbnailSource::File(path) if is_heif_family(mime) => {
+            let preview = heif_preview(path)?;
+            let data = std::fs::read(&preview).ok();
+            let _ = std::fs::remove_file(&preview);
+            data
+        }
+        ThumbnailSource::File(path) => std::fs::read(path).ok(),
+        ThumbnailSource::Media(file_name) => read_cache_file(CacheCategory::Media, file_name)
+            .map(|data| displayable_image(file_name, data)),
+    }
+}
+
+/// Decode, turn upright and shrink an image, re-encoded as JPEG.
+fn scale_to_thumbnail(data: &[u8]) -> Option<Vec<u8>> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder).ok()?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).into_rgb8();
+    let mut bytes = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, 80)
+        .encode_image(&thumbnail)
+        .ok()?;
+    Some(bytes)
+}
+
+/// First frame of a video, scaled down by ffmpeg, or `None` if it isn't
+/// installed or can't read the file.
+fn ffmpeg_frame(path: &Path) -> Option<Vec<u8>> {
+    let scale =
+        format!("scale={THUMBNAIL_SIZE}:{THUMBNAIL_SIZE}:force_original_aspect_ratio=decrease");
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-vf", &scale])
+        .args(["-f", "image2pipe", "-c:v", "png", "-"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+/// Cover art embedded in an MP4 or QuickTime file (`moov/udta/meta/ilst/covr`),
+/// as added by some cameras and editors.
+fn mp4_cover_art(path: &Path) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let moov = read_top_level_box(&mut file, b"moov")?;
+    moov_cover_art(&moov)
+}
+
+fn moov_cover_art(moov: &[u8]) -> Option<Vec<u8>> {
+    let udta = child_box(moov, b"udta")?;
+    let meta = child_box(udta, b"meta")?;
+    // ISO `meta` is a full box that starts with version and flags; QuickTime's isn't
+    let meta = if meta.get(4..8) == Some(b"hdlr".as_slice()) {
+        meta
+    } else {
+        meta.get(4..)?
+    };
+    let ilst = child_box(meta, b"ilst")?;
+    let covr = child_box(ilst, b"covr")?;
+    let data = child_box(covr, b"data")?;
+    // The image follows a 4-byte type indicator and a 4-byte locale
+    data.get(8..)
+        .filter(|image| !image.is_empty())
+        .map(<[u8]>::to_vec)
+}
+
+/// Size of an ISOBMFF (MP4, HEIF) box including its header, and the header's
+/// length.
+pub fn box_header(head: &[u8], remaining: u64) -> Option<(u64, usize)> {
+    let size = u32::from_be_bytes(head.get(0..4)?.try_into().ok()?) as u64;
+    let (size, header) = match size {
+        0 => (remaining, 8),
+        1 => (u64::from_be_bytes(head.get(8..16)?.try_into().ok()?), 16),
+        size => (size, 8),
+    };
+    (size >= header as u64).then_some((size, header))
+}
+
+/// Body of the first top-level box of type `kind`, read without loading the
+/// media data around it.
+fn read_top_level_box(file: &mut (impl Read + Seek), kind: &[u8; 4]) -> Option<Vec<u8>> {
+    let len = file.seek(SeekFrom::End(0)).ok()?;
+    let mut offset: u64 = 0;
+    while offset + 8 <= len {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut head = Vec::with_capacity(16);
+        file.by_ref().take(16).read_to_end(&mut head).ok()?;
+        let (size, header) = box_header(&head, len - offset)?;
+        if head.get(4..8)? == kind {
+            if size > MAX_MOOV_BYTES {
+                return None;
+            }
+            let mut body = vec![0; (size - header as u64) as usize];
+            file.seek(SeekFrom::Start(offset + header as u64)).ok()?;
+            file.read_exact(&mut body).ok()?;
+            return Some(body);
+        }
+        // Sizes past the end of the file mean it is corrupt or truncated
+        offset = offset.checked_add(size).filter(|next| *next <= len)?;
+    }
+    None
+}
+
+/// Body of the first box of type `kind` among the boxes in `data`.
+fn child_box<'a>(mut data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    while data.len() >= 8 {
+        let (size, header) = box_header(data, data.len() as u64)?;
+        let size = usize::try_from(size).ok()?.min(data.len());
+        if &data[4..8] == kind {
+            return data.get(header..size);
+        }
+        data = &data[size..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = (content.len() as u32 + 8).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    fn large_box(kind: &[u8; 4], size: u64, content: &[u8]) -> Vec<u8> {
+        let mut bytes = 1u32.to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(&size.to_be_bytes());
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    fn top_level(bytes: Vec<u8>, kind: &[u8; 4]) -> Option<Vec<u8>> {
+        read_top_level_box(&mut Cursor::new(bytes), kind)
+    }
+
+    #[test]
+    fn finds_moov_after_other_boxes() {
+        let file = [
+            mp4_box(b"ftyp", b"isom"),
+            large_box(b"mdat", 16 + 4, b"data"),
+            mp4_box(b"moov", b"movie"),
+        ]
+        .concat();
+        assert_eq!(top_level(file, b"moov"), Some(b"movie".to_vec()));
+    }
+
+    #[test]
+    fn zero_size_box_runs_to_end_of_file() {
+        let mut last = mp4_box(b"moov", b"movie");
+        last[0..4].copy_from_slice(&0u32.to_be_bytes());
+        let file = [mp4_box(b"ftyp", b"isom"), last].concat();
+        assert_eq!(top_level(file, b"moov"), Some(b"movie".to_vec()));
+    }
+
+    #[test]
+    fn corrupt_sizes_stop_the_scan() {
+        // Too small to hold its own header
+        let mut short = mp4_box(b"free", b"");
+        short[0..4].copy_from_slice(&4u32.to_be_bytes());
+        // Would overflow the offset
+        let huge = large_box(b"mdat", u64::MAX, b"");
+        // Claims more than the file holds
+        let past_end = large_box(b"mdat", 1 << 40, b"");
+
+        let moov = mp4_box(b"moov", b"movie");
+        for corrupt in [short, huge, past_end] {
+            assert_eq!(top_level([corrupt, moov.clone()].concat(), b"moov"), None);
+        }
+    }
+
+    #[test]
+    fn missing_box_is_none() {
+        assert_eq!(top_level(mp4_box(b"ftyp", b"isom"), b"moov"), None);
+        assert_eq!(top_level(Vec::new(), b"moov"), None);
+    }
+
+    fn moov_with_cover(meta_prefix: &[u8]) -> Vec<u8> {
+        let data = mp4_box(
+            b"data",
+            &[&[0, 0, 0, 13, 0, 0, 0, 0][..], b"jpeg bytes"].concat(),
+        );
+        let ilst = mp4_box(b"ilst", &mp4_box(b"covr", &data));
+        let meta = mp4_box(
+            b"meta",
+            &[meta_prefix, &mp4_box(b"hdlr", b"mdir"), &ilst].concat(),
+        );
+        mp4_box(b"udta", &meta)
+    }
+
+    #[test]
+    fn cover_art_in_iso_and_quicktime_meta() {
+        // ISO meta is a full box; QuickTime's starts straight with hdlr
+        for prefix in [&[0u8, 0, 0, 0][..], &[]] {
+            assert_eq!(
+                moov_cover_art(&moov_with_cover(prefix)),
+                Some(b"jpeg bytes".to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn child_box_clamps_sizes_to_the_parent() {
+        let mut truncated = mp4_box(b"udta", b"short");
+        truncated[0..4].copy_from_slice(&100u32.to_be_bytes());
+        assert_eq!(child_box(&truncated, b"udta"), Some(b"short".as_slice()));
+        assert_eq!(child_box(&truncated, b"meta"), None);
+    }
+}
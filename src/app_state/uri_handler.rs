@@ -0,0 +1,284 @@
+use crate::ffi;
+use core::pin::Pin;
+use cxx_qt::CxxQtThread;
+use cxx_qt_lib::QString;
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+// ── sms: / smsto: / tel: URI handling ────────────────────────────
+
+/// A parsed `sms:`, `smsto:`, `mms:`, `mmsto:` or `tel:` link.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmsUri {
+    pub numbers: Vec<String>,
+    pub body: String,
+}
+
+const URI_SCHEMES: [&str; 5] = ["sms:", "smsto:", "mms:", "mmsto:", "tel:"];
+
+/// Whether a command-line argument looks like one of the URI schemes we handle.
+pub fn is_sms_uri(arg: &str) -> bool {
+    let lower = arg.to_lowercase();
+    URI_SCHEMES.iter().any(|scheme| lower.starts_with(scheme))
+}
+
+/// Parse an RFC 5724 `sms:` URI (and the common `smsto:`/`mms:`/`mmsto:`/`tel:`
+/// variants).
+///
+/// Accepts `sms:+15551234567?body=hello`, `sms:+1555,+1666`, `sms://+1555`,
+/// `smsto:+1555:hello` and `tel:+1555`. Returns None when no number is present.
+pub fn parse_sms_uri(uri: &str) -> Option<SmsUri> {
+    let uri = uri.trim();
+    let colon = uri.find(':')?;
+    let scheme = uri[..colon].to_lowercase();
+    if !URI_SCHEMES.contains(&format!("{scheme}:").as_str()) {
+        return None;
+    }
+    let rest = uri[colon + 1..].trim_start_matches("//");
+
+    let (target, query) = match rest.split_once('?') {
+        Some((t, q)) => (t, q),
+        None => (rest, ""),
+    };
+
+    // smsto:<number>:<body> carries the body after a second colon
+    let (target, mut body) = if scheme == "smsto" || scheme == "mmsto" {
+        match target.split_once(':') {
+            Some((t, b)) => (t, percent_decode(b)),
+            None => (target, String::new()),
+        }
+    } else {
+        (target, String::new())
+    };
+
+    if scheme != "tel" {
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if key.eq_ignore_ascii_case("body") {
+                body = percent_decode(value);
+            }
+        }
+    }
+
+    // tel: URIs may carry parameters such as ;phone-context=
+    let target = target.split(';').next().unwrap_or("");
+    let numbers: Vec<String> = percent_decode(target)
+        .split(',')
+        .map(normalize_number)
+        .filter(|n| !n.is_empty())
+        .collect();
+
+    if numbers.is_empty() {
+        return None;
+    }
+
+    Some(SmsUri { numbers, body })
+}
+
+/// Strip visual separators from a phone number, keeping a leading `+`.
+pub fn normalize_number(number: &str) -> String {
+    let trimmed = number.trim();
+    let mut out = String::with_capacity(trimmed.len());
+    for (i, c) in trimmed.chars().enumerate() {
+        if c.is_ascii_digit() || (c == '+' && i == 0) {
+            out.push(c);
+        }
+    }
+    if out == "+" {
+        String::new()
+    } else {
+        out
+    }
+}
+
+/// Loose phone number comparison: exact match after normalisation, or a match
+/// on the trailing digits when one side lacks the country code.
+pub fn numbers_match(a: &str, b: &str) -> bool {
+    let a = normalize_number(a);
+    let b = normalize_number(b);
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+    let a_digits = a.trim_start_matches('+');
+    let b_digits = b.trim_start_matches('+');
+    let shortest = a_digits.len().min(b_digits.len());
+    shortest >= 7 && (a_digits.ends_with(b_digits) || b_digits.ends_with(a_digits))
+}
+
+fn percent_decode(value: &str) -> String {
+    percent_encoding::percent_decode_str(value)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+// ── Single-instance forwarding ───────────────────────────────────
+
+struct UriState {
+    pending: Vec<SmsUri>,
+    target: Option<CxxQtThread<ffi::AppState>>,
+}
+
+fn uri_state() -> &'static Mutex<UriState> {
+    static INSTANCE: OnceLock<Mutex<UriState>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        Mutex::new(UriState {
+            pending: Vec::new(),
+            target: None,
+        })
+    })
+}
+
+/// The forwarding socket in `$XDG_RUNTIME_DIR`, or `None` without one: any
+/// shared directory would let other users send us links.
+fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir().map(|dir| dir.join("kourier.sock"))
+}
+
+/// Hand a URI to an already running instance. Returns true if one accepted it.
+pub fn forward_to_running_instance(uri: &str) -> bool {
+    let Some(path) = socket_path() else {
+        return false;
+    };
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return false;
+    };
+    if let Err(e) = stream.write_all(uri.as_bytes()) {
+        eprintln!("uri forward failed: {e}");
+        return false;
+    }
+    true
+}
+
+/// Listen for URIs forwarded by later launches. Call once from the primary instance.
+pub fn start_uri_listener() {
+    let Some(path) = socket_path() else {
+        eprintln!("uri listener disabled: XDG_RUNTIME_DIR is not set");
+        return;
+    };
+    // A leftover socket from a crashed instance would make bind() fail
+    if UnixStream::connect(&path).is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = match UnixListener::bind(&path) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("uri listener bind failed: {e}");
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut uri = String::new();
+            if stream.take(64 * 1024).read_to_string(&mut uri).is_ok() {
+                queue_uri(&uri);
+            }
+        }
+    });
+}
+
+/// Parse a URI and deliver it to the AppState, or keep it until one registers.
+pub fn queue_uri(uri: &str) {
+    let Some(parsed) = parse_sms_uri(uri) else {
+        eprintln!("ignoring unrecognised uri: {uri}");
+        return;
+    };
+    let mut state = uri_state().lock().unwrap();
+    if let Some(target) = state.target.as_ref() {
+        emit_uri(target, parsed);
+    } else {
+        state.pending.push(parsed);
+    }
+}
+
+/// Register the AppState that receives URI requests and flush anything queued.
+pub fn set_uri_target(qt_thread: CxxQtThread<ffi::AppState>) {
+    let mut state = uri_state().lock().unwrap();
+    for uri in std::mem::take(&mut state.pending) {
+        emit_uri(&qt_thread, uri);
+    }
+    state.target = Some(qt_thread);
+}
+
+fn emit_uri(qt_thread: &CxxQtThread<ffi::AppState>, uri: SmsUri) {
+    let numbers = uri.numbers.join(",");
+    let body = uri.body;
+    let _ = qt_thread.queue(move |mut qobject: Pin<&mut ffi::AppState>| {
+        qobject.as_mut().sms_uri_requested(
+            &QString::from(numbers.as_str()),
+            &QString::from(body.as_str()),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(uri: &str) -> (Vec<String>, String) {
+        let parsed = parse_sms_uri(uri).expect("uri should parse");
+        (parsed.numbers, parsed.body)
+    }
+
+    #[test]
+    fn sms_with_body() {
+        assert_eq!(
+            parsed("sms:+15551234567?body=hello%20there"),
+            (vec!["+15551234567".to_string()], "hello there".to_string())
+        );
+    }
+
+    #[test]
+    fn sms_with_several_numbers() {
+        assert_eq!(
+            parsed("sms:+1 (555) 123-4567,+1555-765-4321").0,
+            vec!["+15551234567".to_string(), "+15557654321".to_string()]
+        );
+    }
+
+    #[test]
+    fn sms_with_slashes_and_uppercase_scheme() {
+        assert_eq!(
+            parsed("SMS://+15551234567").0,
+            vec!["+15551234567".to_string()]
+        );
+    }
+
+    #[test]
+    fn smsto_and_mmsto_carry_body_after_colon() {
+        for uri in ["smsto:5551234567:hi%21", "mmsto:5551234567:hi%21"] {
+            assert_eq!(
+                parsed(uri),
+                (vec!["5551234567".to_string()], "hi!".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn tel_ignores_parameters_and_query() {
+        assert_eq!(
+            parsed("tel:+15551234567;phone-context=example.com?body=x"),
+            (vec!["+15551234567".to_string()], String::new())
+        );
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_missing_numbers() {
+        assert_eq!(parse_sms_uri("https://example.com"), None);
+        assert_eq!(parse_sms_uri("sms:?body=hello"), None);
+        assert_eq!(parse_sms_uri("sms:+"), None);
+    }
+
+    #[test]
+    fn numbers_match_without_country_code() {
+        assert!(numbers_match("+1 555 123 4567", "5551234567"));
+        assert!(!numbers_match("+15551234567", "+15551234568"));
+        assert!(!numbers_match("123", "+44123"));
+    }
+}
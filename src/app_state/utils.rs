@@ -1,4 +1,7 @@
+use crate::app_state::cache::{cache_contains, cache_uri, write_cache_file, CacheCategory};
 use crate::app_state::conversation_list::ConversationItem;
+use crate::app_state::participant_list::{fetch_participants, is_known_contact, participants_for};
+use crate::app_state::settings::{settings, LinkPreviewPolicy, LinkPreviewSettings};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use chrono::{Datelike, Local, Timelike};
@@ -158,17 +161,128 @@ pub fn build_preview(convo: &libgmessages_rs::proto::conversations::Conversation
 }
 
 pub fn detect_extension(bytes: &[u8]) -> &'static str {
-    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
-        "png"
-    } else if bytes.starts_with(b"\xff\xd8\xff") {
-        "jpg"
-    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
-        "gif"
-    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
-        "webp"
-    } else {
-        "bin"
+    sniff_mime(bytes).map(mime_to_extension).unwrap_or("bin")
+}
+
+/// MIME type from a file's leading bytes. Text formats that have no magic
+/// number (vCard, iCalendar) are recognised by their BEGIN line.
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    let text_start = String::from_utf8_lossy(&bytes[..bytes.len().min(64)]).to_uppercase();
+    let text_start = text_start.trim_start_matches('\u{feff}').trim_start();
+    if text_start.starts_with("BEGIN:VCARD") {
+        return Some("text/vcard");
+    }
+    if text_start.starts_with("BEGIN:VCALENDAR") {
+        return Some("text/calendar");
+    }
+    // infer reports every HEIF brand as image/heif
+    heif_brand_mime(bytes).or_else(|| infer::get(bytes).map(|kind| kind.mime_type()))
+}
+
+/// MIME type of a file on disk: sniffed from its first few KiB, falling back
+/// to the extension.
+pub fn file_mime(path: &std::path::Path) -> String {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(8192);
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file.take(8192).read_to_end(&mut head);
+    }
+    let extension = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    sniff_mime(&head)
+        .or_else(|| mime_from_extension(&extension))
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+/// MIME type reported for an incoming attachment, falling back to the
+/// original file name's extension when the phone sent a generic type.
+pub fn incoming_mime(mime: &str, file_name: &str) -> String {
+    if !mime.is_empty() && mime != "application/octet-stream" {
+        return mime.to_string();
+    }
+    let extension = std::path::Path::new(file_name)
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    mime_from_extension(&extension)
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+/// A sender-supplied file name reduced to something safe to create in the
+/// Downloads folder: no directories, no leading dots, no control characters.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string();
+    (!cleaned.is_empty()).then_some(cleaned)
+}
+
+/// `dir/name`, or `dir/name (n).ext` with the first free `n` if that exists.
+pub fn unique_path(dir: &std::path::Path, name: &str) -> std::path::PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
     }
+    let path = std::path::Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{stem} ({n}){extension}")))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+pub fn mime_from_extension(extension: &str) -> Option<&'static str> {
+    let mime = match extension {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "avif" => "image/avif",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "3gp" => "video/3gpp",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "amr" => "audio/amr",
+        "ogg" | "opus" => "audio/ogg",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "odp" => "application/vnd.oasis.opendocument.presentation",
+        "zip" => "application/zip",
+        "vcf" => "text/vcard",
+        "ics" => "text/calendar",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "rtf" => "application/rtf",
+        _ => return None,
+    };
+    Some(mime)
 }
 
 pub fn map_message_status(status_code: i32, from_me: bool) -> &'static str {
@@ -189,62 +303,39 @@ pub fn mime_to_extension(mime: &str) -> &str {
         "image/jpeg" => "jpg",
         "image/gif" => "gif",
         "image/webp" => "webp",
+        "image/heic" | "image/heic-sequence" => "heic",
+        "image/heif" | "image/heif-sequence" => "heif",
+        "image/avif" => "avif",
         "video/mp4" => "mp4",
         "video/webm" => "webm",
         "video/3gpp" => "3gp",
         "video/3gpp2" => "3g2",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/aac" => "aac",
+        "audio/amr" => "amr",
+        "audio/ogg" | "audio/opus" => "ogg",
+        "audio/mpeg" => "mp3",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "application/pdf" => "pdf",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.ms-powerpoint" => "ppt",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        "application/vnd.oasis.opendocument.text" => "odt",
+        "application/vnd.oasis.opendocument.spreadsheet" => "ods",
+        "application/vnd.oasis.opendocument.presentation" => "odp",
+        "application/zip" => "zip",
+        "text/vcard" | "text/x-vcard" => "vcf",
+        "text/calendar" => "ics",
+        "text/plain" => "txt",
+        "text/csv" => "csv",
+        "application/rtf" => "rtf",
         _ => "bin",
     }
 }
 
-/// Convert downloaded media bytes into a URI suitable for QML.
-/// Images → data: URI (works natively with QML Image).
-/// Videos → file: URI (Qt MediaPlayer needs a real file).
-pub fn media_data_to_uri(data: &[u8], mime: &str, file_name: &str) -> String {
-    let ext = mime_to_extension(mime);
-    let tmp_dir = std::env::temp_dir().join("kourier_media");
-    let _ = std::fs::create_dir_all(&tmp_dir);
-    let path = tmp_dir.join(format!("{}.{}", file_name, ext));
-
-    // Always write to temp file for disk caching
-    match std::fs::write(&path, data) {
-        Ok(()) => format!("file://{}", path.to_string_lossy()),
-        Err(e) => {
-            eprintln!("media_data_to_uri: failed to write temp file: {e}");
-            // Fallback to data URI
-            let b64 = base64::engine::general_purpose::STANDARD.encode(data);
-            format!("data:{};base64,{}", mime, b64)
-        }
-    }
-}
-
-pub fn generate_video_thumbnail(video_path: &std::path::Path) -> Option<String> {
-    let thumb_path = video_path.with_extension("thumb.jpg");
-    if thumb_path.exists() {
-        return Some(format!("file://{}", thumb_path.to_string_lossy()));
-    }
-
-    let output = std::process::Command::new("ffmpeg")
-        .args(&[
-            "-y",
-            "-i",
-            &video_path.to_string_lossy().into_owned(),
-            "-vframes",
-            "1",
-            "-q:v",
-            "2",
-            &thumb_path.to_string_lossy().into_owned(),
-        ])
-        .output()
-        .ok()?;
-
-    if output.status.success() {
-        Some(format!("file://{}", thumb_path.to_string_lossy()))
-    } else {
-        None
-    }
-}
-
 /// Extract the first HTTP(S) URL from a text string.
 pub fn extract_first_url(text: &str) -> Option<String> {
     // Simple but effective URL extraction — looks for http:// or https:// followed by
@@ -269,47 +360,110 @@ pub struct OgMetadata {
     pub url: String,
 }
 
+/// Whether a link in a message may be fetched for a preview: the sender
+/// must be covered by the link preview policy and the domain lists must
+/// allow the URL.
+pub fn link_preview_allowed(
+    url: &str,
+    from_me: bool,
+    conversation_id: &str,
+    participant_id: &str,
+) -> bool {
+    let prefs = settings().link_previews;
+    let sender_allowed = match prefs.policy {
+        LinkPreviewPolicy::Off => false,
+        LinkPreviewPolicy::OwnMessages => from_me,
+        LinkPreviewPolicy::KnownContacts => {
+            from_me || is_known_contact(conversation_id, participant_id)
+        }
+        LinkPreviewPolicy::Always => true,
+    };
+    sender_allowed && prefs.allows_url(url)
+}
+
+/// `link_preview_allowed`, first fetching the members of the conversation
+/// when the policy depends on them and they haven't been loaded yet, so a
+/// known contact isn't mistaken for a stranger.
+pub async fn link_preview_allowed_fetching(
+    url: &str,
+    from_me: bool,
+    conversation_id: &str,
+    participant_id: &str,
+) -> bool {
+    let needs_members = !from_me
+        && matches!(
+            settings().link_previews.policy,
+            LinkPreviewPolicy::KnownContacts
+        )
+        && participants_for(conversation_id).is_none();
+    if needs_members {
+        if let Err(e) = fetch_participants(conversation_id).await {
+            eprintln!("link preview: failed to fetch participants: {e}");
+        }
+    }
+    link_preview_allowed(url, from_me, conversation_id, participant_id)
+}
+
+/// HTTP client for link previews. It says it is Kourier, goes through the
+/// configured proxy and won't follow redirects to blocked domains. `None`
+/// if the proxy is invalid, rather than connecting directly.
+fn link_preview_client(prefs: &LinkPreviewSettings) -> Option<reqwest::Client> {
+    let redirect_prefs = prefs.clone();
+    let mut builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(8))
+        .user_agent(concat!("Kourier/", env!("CARGO_PKG_VERSION")))
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= 10 || !redirect_prefs.allows_url(attempt.url().as_str())
+            {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        }));
+    let proxy = prefs.proxy.trim();
+    if !proxy.is_empty() {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => {
+                eprintln!("link_preview: invalid proxy '{proxy}': {e}");
+                return None;
+            }
+        }
+    }
+    builder.build().ok()
+}
+
+/// Read a response body as it arrives, stopping at `limit` bytes. A longer
+/// body is cut short when `truncate` is set and rejected otherwise.
+async fn read_capped(
+    mut response: reqwest::Response,
+    limit: usize,
+    truncate: bool,
+) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        if body.len() + chunk.len() > limit {
+            if !truncate {
+                return None;
+            }
+            body.extend_from_slice(&chunk[..limit - body.len()]);
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Some(body)
+}
+
 /// Fetch OpenGraph metadata (title, image) from a URL.
 /// Returns None on any failure (network, parsing, timeout).
 pub async fn fetch_og_metadata(url: &str) -> Option<OgMetadata> {
     eprintln!("link_preview: fetching OG metadata for {url}");
 
-    let (fetch_url, user_agent) = if url.starts_with("https://twitter.com/") {
-        (
-            url.replace("https://twitter.com/", "https://x.com/"),
-            "Mozilla/5.0 (compatible; TelegramBot/1.0; +https://core.telegram.org/bots)",
-        )
-    } else if url.starts_with("https://x.com/") {
-        (
-            url.to_string(),
-            "Mozilla/5.0 (compatible; TelegramBot/1.0; +https://core.telegram.org/bots)",
-        )
-    } else if url.starts_with("https://www.twitter.com/") {
-        (
-            url.replace("https://www.twitter.com/", "https://x.com/"),
-            "Mozilla/5.0 (compatible; TelegramBot/1.0; +https://core.telegram.org/bots)",
-        )
-    } else if url.starts_with("https://www.x.com/") {
-        (
-            url.replace("https://www.x.com/", "https://x.com/"),
-            "Mozilla/5.0 (compatible; TelegramBot/1.0; +https://core.telegram.org/bots)",
-        )
-    } else {
-        (
-            url.to_string(),
-            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
-        )
-    };
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(8))
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .user_agent(user_agent)
-        .build()
-        .ok()?;
+    let prefs = settings().link_previews;
+    let client = link_preview_client(&prefs)?;
 
     let response = client
-        .get(&fetch_url)
+        .get(url)
         .header(
             "Accept",
             "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
@@ -344,21 +498,15 @@ pub async fn fetch_og_metadata(url: &str) -> Option<OgMetadata> {
         return None;
     }
 
-    // Limit body to 512KB to avoid downloading huge pages
-    let body = match response.text().await {
-        Ok(b) => b,
-        Err(e) => {
-            eprintln!("link_preview: failed to read body for {url}: {e}");
-            return None;
-        }
-    };
-    let body = if body.len() > 512 * 1024 {
-        &body[..512 * 1024]
-    } else {
-        &body
+    // Only the start of the page is read; the title and meta tags are in its head
+    let limit = (prefs.max_page_kb * 1024) as usize;
+    let Some(body) = read_capped(response, limit, true).await else {
+        eprintln!("link_preview: failed to read body for {url}");
+        return None;
     };
+    let body = String::from_utf8_lossy(&body);
 
-    let document = scraper::Html::parse_document(body);
+    let document = scraper::Html::parse_document(&body);
     let meta_selector = scraper::Selector::parse("meta").ok()?;
 
     let mut og_title = String::new();
@@ -434,27 +582,70 @@ pub async fn fetch_og_metadata(url: &str) -> Option<OgMetadata> {
         url: og_url,
     })
 }
-pub fn cleanup_old_cache_files() {
-    let dirs_to_clean = vec![
-        std::env::temp_dir().join("kourier_media"),
-        std::env::temp_dir().join("kourier_link_previews"),
-    ];
-    let max_age = std::time::Duration::from_secs(7 * 24 * 60 * 60); // 7 days
-    let now = std::time::SystemTime::now();
-
-    for dir in dirs_to_clean {
-        if let Ok(entries) = std::fs::read_dir(&dir) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                if let Ok(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        if let Ok(age) = now.duration_since(modified) {
-                            if age > max_age {
-                                let _ = std::fs::remove_file(entry.path());
-                            }
-                        }
-                    }
-                }
-            }
-        }
+
+/// Download an og:image into the link preview cache, returning its URI (or
+/// empty on failure or when there is no image).
+pub async fn cache_link_preview_image(image_url: &str) -> String {
+    if image_url.is_empty() {
+        return String::new();
+    }
+    // Create a stable filename from the image URL hash
+    let hash = {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut h = DefaultHasher::new();
+        image_url.hash(&mut h);
+        h.finish()
+    };
+    let (ext, mime) = if image_url.contains(".png") {
+        ("png", "image/png")
+    } else if image_url.contains(".webp") {
+        ("webp", "image/webp")
+    } else {
+        ("jpg", "image/jpeg")
+    };
+    let file_name = format!("{:016x}.{}", hash, ext);
+
+    if cache_contains(CacheCategory::LinkPreviews, &file_name) {
+        return cache_uri(CacheCategory::LinkPreviews, &file_name, mime);
+    }
+
+    let prefs = settings().link_previews;
+    if !prefs.allows_url(image_url) {
+        return String::new();
+    }
+    let Some(client) = link_preview_client(&prefs) else {
+        return String::new();
+    };
+    let response = match client.get(image_url).send().await {
+        Ok(resp) if resp.status().is_success() => resp,
+        _ => return String::new(),
+    };
+    let limit = prefs.max_image_kb * 1024;
+    if response.content_length().is_some_and(|len| len > limit) {
+        eprintln!(
+            "link_preview: image over {} KB: {image_url}",
+            prefs.max_image_kb
+        );
+        return String::new();
+    }
+    let Some(bytes) = read_capped(response, limit as usize, false)
+        .await
+        .filter(|bytes| !bytes.is_empty())
+    else {
+        return String::new();
+    };
+
+    // Check the dimensions in the header before anything decodes the image
+    let max = prefs.max_image_dimension;
+    let within_limits = image::ImageReader::new(std::io::Cursor::new(bytes.as_slice()))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .is_some_and(|(width, height)| width <= max && height <= max);
+    if !within_limits {
+        eprintln!("link_preview: unreadable image or larger than {max}px: {image_url}");
+        return String::new();
     }
+    write_cache_file(CacheCategory::LinkPreviews, &file_name, &bytes, mime).unwrap_or_default()
 }
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use crate::app_state::cache::{runtime_subdir, write_private_file};
+use crate::app_state::utils::{mime_to_extension, sniff_mime, unique_path};
+
+// ── Clipboard paste ──────────────────────────────────────────────
+
+#[cxx_qt::bridge]
+pub mod ffi {
+    unsafe extern "C++" {
+        include!("clipboard.h");
+
+        #[rust_name = "clipboard_file_urls"]
+        fn kourierClipboardFileUrls() -> Vec<String>;
+
+        #[rust_name = "clipboard_image_data"]
+        fn kourierClipboardImageData() -> Vec<u8>;
+    }
+}
+
+/// Files to stage for a paste into the composer, as file:// URLs. Copied
+/// files are used in place; a copied image such as a Spectacle screenshot is
+/// written to the runtime directory first. Empty when the clipboard holds
+/// neither, so the paste can fall through to plain text.
+///
+/// Must be called on the Qt thread.
+pub fn pasted_attachments() -> Vec<String> {
+    let urls = ffi::clipboard_file_urls();
+    if !urls.is_empty() {
+        return urls;
+    }
+
+    let data = ffi::clipboard_image_data();
+    let Some(mime) = sniff_mime(&data).filter(|mime| mime.starts_with("image/")) else {
+        return Vec::new();
+    };
+    let name = format!(
+        "Pasted image {}.{}",
+        chrono::Local::now().format("%Y-%m-%d %H-%M-%S"),
+        mime_to_extension(mime)
+    );
+    let path = unique_path(&runtime_subdir("pasted"), &name);
+    match write_private_file(&path, &data) {
+        Ok(()) => vec![format!("file://{}", path.to_string_lossy())],
+        Err(e) => {
+            eprintln!("paste: failed to stage {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+/// Delete an image `pasted_attachments` wrote, once it has been uploaded or
+/// unstaged. Copied files belong to the user and are left alone.
+pub fn discard_pasted(path: &Path) {
+    if path.parent() == Some(runtime_subdir("pasted").as_path()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,57 @@
+#include "clipboard.h"
+
+#include <QtCore/QBuffer>
+#include <QtCore/QMimeData>
+#include <QtCore/QUrl>
+#include <QtGui/QClipboard>
+#include <QtGui/QGuiApplication>
+#include <QtGui/QImage>
+
+rust::Vec<rust::String> kourierClipboardFileUrls()
+{
+    rust::Vec<rust::String> urls;
+    const QMimeData *mime = QGuiApplication::clipboard()->mimeData();
+    if (!mime || !mime->hasUrls()) {
+        return urls;
+    }
+    for (const QUrl &url : mime->urls()) {
+        if (url.isLocalFile()) {
+            // Unencoded, like the file:// URLs the Rust side builds and strips
+            const QByteArray utf8 = (QStringLiteral("file://") + url.toLocalFile()).toUtf8();
+            urls.push_back(rust::String(utf8.constData(), utf8.size()));
+        }
+    }
+    return urls;
+}
+
+rust::Vec<uint8_t> kourierClipboardImageData()
+{
+    rust::Vec<uint8_t> data;
+    const QMimeData *mime = QGuiApplication::clipboard()->mimeData();
+    if (!mime) {
+        return data;
+    }
+
+    // Keep the copied encoding (e.g. Spectacle's PNG, a browser's JPEG or GIF)
+    QByteArray bytes;
+    for (const char *format : {"image/png", "image/jpeg", "image/webp", "image/gif"}) {
+        if (mime->hasFormat(QLatin1String(format))) {
+            bytes = mime->data(QLatin1String(format));
+            if (!bytes.isEmpty()) {
+                break;
+            }
+        }
+    }
+    if (bytes.isEmpty() && mime->hasImage()) {
+        const QImage image = qvariant_cast<QImage>(mime->imageData());
+        QBuffer buffer(&bytes);
+        buffer.open(QIODevice::WriteOnly);
+        image.save(&buffer, "PNG");
+    }
+
+    data.reserve(bytes.size());
+    for (const char byte : bytes) {
+        data.push_back(static_cast<uint8_t>(byte));
+    }
+    return data;
+}
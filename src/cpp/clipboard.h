@@ -0,0 +1,10 @@
+#pragma once
+
+#include "rust/cxx.h"
+
+// Local files copied in a file manager, as unencoded file:// URLs.
+rust::Vec<rust::String> kourierClipboardFileUrls();
+
+// Image on the clipboard, in the format it was copied as when that is one we
+// can send, otherwise encoded as PNG. Empty when there is no image.
+rust::Vec<uint8_t> kourierClipboardImageData();
@@ -0,0 +1,68 @@
+#include "image_provider.h"
+
+#include <QtCore/QBuffer>
+#include <QtGui/QImageReader>
+
+#include "kourier/src/image_provider.cxxqt.h"
+
+KourierImageProvider::KourierImageProvider()
+{
+    m_pool.setMaxThreadCount(4);
+}
+
+KourierImageProvider::~KourierImageProvider()
+{
+    // Drop queued requests; the pool's destructor waits for running ones
+    m_pool.clear();
+}
+
+QQuickImageResponse *KourierImageProvider::requestImageResponse(const QString &id, const QSize &requestedSize)
+{
+    auto *response = new KourierImageResponse(id, requestedSize);
+    m_pool.start(response);
+    return response;
+}
+
+KourierImageResponse::KourierImageResponse(const QString &id, const QSize &requestedSize)
+    : m_id(id)
+    , m_requestedSize(requestedSize)
+{
+    // The engine owns and deletes the response, not the thread pool
+    setAutoDelete(false);
+}
+
+QQuickTextureFactory *KourierImageResponse::textureFactory() const
+{
+    return QQuickTextureFactory::textureFactoryForImage(m_image);
+}
+
+void KourierImageResponse::run()
+{
+    const QByteArray utf8 = m_id.toUtf8();
+    const rust::Vec<uint8_t> data = image_provider_data(rust::Str(utf8.constData(), utf8.size()));
+
+    QByteArray bytes(reinterpret_cast<const char *>(data.data()), static_cast<qsizetype>(data.size()));
+    QBuffer buffer(&bytes);
+    QImageReader reader(&buffer);
+    reader.setAutoTransform(true);
+
+    // Decode straight to the requested size instead of scaling a full-size image
+    const QSize original = reader.size();
+    if (original.isValid() && (m_requestedSize.width() > 0 || m_requestedSize.height() > 0)) {
+        const QSize bound(m_requestedSize.width() > 0 ? m_requestedSize.width() : original.width(),
+                          m_requestedSize.height() > 0 ? m_requestedSize.height() : original.height());
+        const QSize target = original.scaled(bound, Qt::KeepAspectRatio);
+        if (target.width() < original.width()) {
+            reader.setScaledSize(target);
+        }
+    }
+
+    m_image = reader.read();
+    Q_EMIT finished();
+}
+
+void kourierRegisterImageProvider(QQmlApplicationEngine &engine)
+{
+    // The engine takes ownership of the provider
+    engine.addImageProvider(QStringLiteral("kourier"), new KourierImageProvider);
+}
@@ -0,0 +1,40 @@
+#pragma once
+
+#include <QtCore/QRunnable>
+#include <QtCore/QThreadPool>
+#include <QtQml/QQmlApplicationEngine>
+#include <QtQuick/QQuickAsyncImageProvider>
+
+// Serves image://kourier/<category>/<name> from the Rust cache so encrypted
+// cache entries never have to exist as plaintext files, and media is only
+// downloaded once QML actually asks for it.
+class KourierImageProvider : public QQuickAsyncImageProvider
+{
+public:
+    KourierImageProvider();
+    ~KourierImageProvider() override;
+
+    QQuickImageResponse *requestImageResponse(const QString &id, const QSize &requestedSize) override;
+
+private:
+    // Requests may wait on downloads, so they get their own threads rather
+    // than tying up the global pool QML and Qt Quick rely on
+    QThreadPool m_pool;
+};
+
+// Loads and decodes one image on the provider's thread pool.
+class KourierImageResponse : public QQuickImageResponse, public QRunnable
+{
+public:
+    KourierImageResponse(const QString &id, const QSize &requestedSize);
+
+    QQuickTextureFactory *textureFactory() const override;
+    void run() override;
+
+private:
+    QString m_id;
+    QSize m_requestedSize;
+    QImage m_image;
+};
+
+void kourierRegisterImageProvider(QQmlApplicationEngine &engine);
@@ -0,0 +1,48 @@
+use crate::app_state::cache::{fetch_media, parse_provider_id, read_cache_file, CacheCategory};
+use crate::app_state::generated_avatar::render_generated_avatar;
+use crate::app_state::heif::displayable_image;
+use crate::app_state::shared::block_on;
+
+// ── image://kourier provider ─────────────────────────────────────
+
+#[cxx_qt::bridge]
+pub mod ffi {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qqmlapplicationengine.h");
+        type QQmlApplicationEngine = cxx_qt_lib::QQmlApplicationEngine;
+
+        include!("image_provider.h");
+
+        /// Install the `image://kourier/...` provider on the engine.
+        #[rust_name = "register_image_provider"]
+        fn kourierRegisterImageProvider(engine: Pin<&mut QQmlApplicationEngine>);
+    }
+
+    extern "Rust" {
+        fn image_provider_data(id: &str) -> Vec<u8>;
+    }
+}
+
+pub use ffi::register_image_provider;
+
+/// Bytes for an `image://kourier/<category>/<name>` request, decrypted if the
+/// cache is encrypted. Media that is not cached yet is downloaded on demand,
+/// and HEIC/AVIF attachments are converted to a format Qt can decode.
+/// `generated/<key>` ids are avatars drawn on request. Empty when the entry is
+/// unknown.
+///
+/// Called from the provider's worker threads, never the Qt or tokio threads.
+fn image_provider_data(id: &str) -> Vec<u8> {
+    if let Some(key) = id.strip_prefix("generated/") {
+        return render_generated_avatar(key).unwrap_or_default();
+    }
+    let Some((category, file_name)) = parse_provider_id(id) else {
+        return Vec::new();
+    };
+    if category == CacheCategory::Media {
+        return block_on(fetch_media(file_name))
+            .map(|data| displayable_image(file_name, data))
+            .unwrap_or_default();
+    }
+    read_cache_file(category, file_name).unwrap_or_default()
+}
@@ -1,12 +1,23 @@
 mod app_state;
+mod clipboard;
+mod image_provider;
 
 use core::pin::Pin;
 use cxx_qt_lib_extras as _;
 
 pub use app_state::AppStateRust;
+pub use app_state::ContactListRust;
 pub use app_state::ConversationListRust;
+pub use app_state::MediaExportRust;
+pub use app_state::MediaGalleryRust;
 pub use app_state::MessageListRust;
+pub use app_state::ParticipantListRust;
 pub use app_state::SessionControllerRust;
+pub use app_state::SimListRust;
+pub use app_state::{
+    flush_cache_index, forward_to_running_instance, is_sms_uri, queue_uri, start_uri_listener,
+};
+pub use image_provider::register_image_provider;
 
 #[cxx_qt::bridge]
 mod ffi {
@@ -23,6 +34,9 @@ mod ffi {
         include!("cxx-qt-lib/qstring.h");
         type QString = cxx_qt_lib::QString;
 
+        include!("cxx-qt-lib/qstringlist.h");
+        type QStringList = cxx_qt_lib::QStringList;
+
         include!("cxx-qt-lib/qhash.h");
         type QHash_i32_QByteArray = cxx_qt_lib::QHash<cxx_qt_lib::QHashPair_i32_QByteArray>;
     }
@@ -37,6 +51,10 @@ mod ffi {
         #[qproperty(QString, qr_url)]
         #[qproperty(QString, qr_svg_data_url)]
         #[qproperty(QString, status_message)]
+        #[qproperty(bool, encrypted_cache)]
+        #[qproperty(QString, media_quality)]
+        #[qproperty(bool, keep_photo_metadata)]
+        #[qproperty(QString, link_preview_policy)]
         type AppState = super::AppStateRust;
 
         #[qinvokable]
@@ -51,11 +69,38 @@ mod ffi {
         #[qsignal]
         fn initialized(self: Pin<&mut AppState>, logged_in: bool);
 
+        #[qsignal]
+        fn auth_storage_error(self: Pin<&mut AppState>, message: &QString);
+
+        #[qinvokable]
+        fn retry_auth_load(self: Pin<&mut AppState>);
+
         #[qinvokable]
         fn logout(self: Pin<&mut AppState>, reason: &QString);
 
         #[qinvokable]
         fn clear_cache(self: Pin<&mut AppState>);
+
+        #[qinvokable]
+        fn set_cache_encryption(self: Pin<&mut AppState>, enabled: bool);
+
+        #[qinvokable]
+        fn set_media_quality_setting(self: Pin<&mut AppState>, quality: &QString);
+
+        #[qinvokable]
+        fn set_photo_metadata_kept(self: Pin<&mut AppState>, keep: bool);
+
+        #[qinvokable]
+        fn set_link_preview_policy_setting(self: Pin<&mut AppState>, policy: &QString);
+
+        #[qinvokable]
+        fn cache_usage(self: &AppState) -> QString;
+
+        #[qinvokable]
+        fn set_cache_budget(self: Pin<&mut AppState>, category: &QString, megabytes: i32);
+
+        #[qsignal]
+        fn sms_uri_requested(self: Pin<&mut AppState>, numbers: &QString, body: &QString);
     }
 
     impl cxx_qt::Threading for AppState {}
@@ -99,6 +144,7 @@ mod ffi {
             is_group_chat: bool,
             status: i32,
             avatar_identifier: &QString,
+            participant_numbers: &QString,
         );
 
         #[qsignal]
@@ -107,6 +153,9 @@ mod ffi {
         #[qsignal]
         fn updates_settled(self: Pin<&mut SessionController>);
 
+        #[qsignal]
+        fn sims_updated(self: Pin<&mut SessionController>);
+
         #[qinvokable]
         fn start(self: Pin<&mut SessionController>);
 
@@ -155,6 +204,12 @@ mod ffi {
         #[qinvokable]
         fn me_participant_id(self: &ConversationList, row: i32) -> QString;
 
+        #[qinvokable]
+        fn conversation_name(self: &ConversationList, row: i32) -> QString;
+
+        #[qinvokable]
+        fn is_group_chat(self: &ConversationList, row: i32) -> bool;
+
         #[qinvokable]
         fn handle_conversation_event(
             self: Pin<&mut ConversationList>,
@@ -166,6 +221,7 @@ mod ffi {
             is_group_chat: bool,
             status: i32,
             avatar_identifier: &QString,
+            participant_numbers: &QString,
         );
 
         #[qsignal]
@@ -185,6 +241,44 @@ mod ffi {
         #[qsignal]
         fn auth_error(self: Pin<&mut ConversationList>, message: &QString);
 
+        #[qinvokable]
+        fn row_for_conversation(self: &ConversationList, conversation_id: &QString) -> i32;
+
+        #[qinvokable]
+        fn open_for_numbers(self: Pin<&mut ConversationList>, numbers: &QString, body: &QString);
+
+        #[qsignal]
+        fn conversation_opened(
+            self: Pin<&mut ConversationList>,
+            conversation_id: &QString,
+            body: &QString,
+        );
+
+        #[qinvokable]
+        fn rename_group(
+            self: Pin<&mut ConversationList>,
+            conversation_id: &QString,
+            name: &QString,
+        );
+
+        #[qinvokable]
+        fn add_participants(
+            self: Pin<&mut ConversationList>,
+            conversation_id: &QString,
+            numbers: &QString,
+        );
+
+        #[qinvokable]
+        fn leave_group(self: Pin<&mut ConversationList>, conversation_id: &QString);
+
+        #[qsignal]
+        fn group_action_finished(
+            self: Pin<&mut ConversationList>,
+            conversation_id: &QString,
+            action: &QString,
+            error: &QString,
+        );
+
         #[inherit]
         #[rust_name = "begin_reset_model"]
         fn beginResetModel(self: Pin<&mut Self>);
@@ -209,6 +303,230 @@ mod ffi {
         fn index(self: &Self, row: i32, column: i32, parent: &QModelIndex) -> QModelIndex;
     }
 
+    // ── ContactList ──────────────────────────────────────────────
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[base = QAbstractListModel]
+        #[qproperty(bool, loading)]
+        type ContactList = super::ContactListRust;
+
+        #[cxx_override]
+        #[rust_name = "row_count"]
+        fn rowCount(&self, parent: &QModelIndex) -> i32;
+
+        #[cxx_override]
+        fn data(&self, index: &QModelIndex, role: i32) -> QVariant;
+
+        #[cxx_override]
+        #[rust_name = "role_names"]
+        fn roleNames(&self) -> QHash_i32_QByteArray;
+
+        #[qinvokable]
+        fn load(self: Pin<&mut ContactList>);
+
+        #[qinvokable]
+        fn apply_filter(self: Pin<&mut ContactList>, filter: &QString);
+
+        #[qinvokable]
+        fn numbers_at(self: &ContactList, row: i32) -> QString;
+
+        #[qinvokable]
+        fn name_for_number(self: &ContactList, number: &QString) -> QString;
+
+        #[qsignal]
+        fn loaded(self: Pin<&mut ContactList>);
+
+        #[inherit]
+        #[rust_name = "begin_reset_model"]
+        fn beginResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "end_reset_model"]
+        fn endResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "data_changed"]
+        fn dataChanged(self: Pin<&mut Self>, top_left: &QModelIndex, bottom_right: &QModelIndex);
+
+        #[inherit]
+        fn index(self: &Self, row: i32, column: i32, parent: &QModelIndex) -> QModelIndex;
+    }
+
+    impl cxx_qt::Threading for ContactList {}
+
+    // ── ParticipantList ──────────────────────────────────────────
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[base = QAbstractListModel]
+        #[qproperty(bool, loading)]
+        type ParticipantList = super::ParticipantListRust;
+
+        #[cxx_override]
+        #[rust_name = "row_count"]
+        fn rowCount(&self, parent: &QModelIndex) -> i32;
+
+        #[cxx_override]
+        fn data(&self, index: &QModelIndex, role: i32) -> QVariant;
+
+        #[cxx_override]
+        #[rust_name = "role_names"]
+        fn roleNames(&self) -> QHash_i32_QByteArray;
+
+        #[qinvokable]
+        fn load(self: Pin<&mut ParticipantList>, conversation_id: &QString);
+
+        #[inherit]
+        #[rust_name = "begin_reset_model"]
+        fn beginResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "end_reset_model"]
+        fn endResetModel(self: Pin<&mut Self>);
+    }
+
+    impl cxx_qt::Threading for ParticipantList {}
+
+    // ── MediaGallery ─────────────────────────────────────────────
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[base = QAbstractListModel]
+        #[qproperty(bool, loading)]
+        #[qproperty(bool, has_more)]
+        type MediaGallery = super::MediaGalleryRust;
+
+        #[cxx_override]
+        #[rust_name = "row_count"]
+        fn rowCount(&self, parent: &QModelIndex) -> i32;
+
+        #[cxx_override]
+        fn data(&self, index: &QModelIndex, role: i32) -> QVariant;
+
+        #[cxx_override]
+        #[rust_name = "role_names"]
+        fn roleNames(&self) -> QHash_i32_QByteArray;
+
+        #[qinvokable]
+        fn load(self: Pin<&mut MediaGallery>, conversation_id: &QString);
+
+        #[qinvokable]
+        fn load_more(self: Pin<&mut MediaGallery>);
+
+        #[qinvokable]
+        fn request_thumbnail(self: Pin<&mut MediaGallery>, row: i32);
+
+        #[qinvokable]
+        fn item_at(self: &MediaGallery, row: i32) -> QString;
+
+        #[inherit]
+        #[rust_name = "begin_reset_model"]
+        fn beginResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "end_reset_model"]
+        fn endResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "begin_insert_rows"]
+        fn beginInsertRows(self: Pin<&mut Self>, parent: &QModelIndex, first: i32, last: i32);
+
+        #[inherit]
+        #[rust_name = "end_insert_rows"]
+        fn endInsertRows(self: Pin<&mut Self>);
+
+        #[inherit]
+        fn index(self: &Self, row: i32, column: i32, parent: &QModelIndex) -> QModelIndex;
+
+        #[inherit]
+        #[rust_name = "data_changed"]
+        fn dataChanged(self: Pin<&mut Self>, top_left: &QModelIndex, bottom_right: &QModelIndex);
+    }
+
+    impl cxx_qt::Threading for MediaGallery {}
+
+    // ── MediaExport ──────────────────────────────────────────────
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qproperty(bool, running)]
+        type MediaExport = super::MediaExportRust;
+
+        #[qinvokable]
+        fn start(
+            self: Pin<&mut MediaExport>,
+            conversation_id: &QString,
+            directory_url: &QString,
+            from_date: &QString,
+            to_date: &QString,
+        );
+
+        #[qinvokable]
+        fn cancel(self: &MediaExport);
+
+        #[qsignal]
+        fn progress(self: Pin<&mut MediaExport>, exported: i32, skipped: i32, failed: i32);
+
+        #[qsignal]
+        fn finished(
+            self: Pin<&mut MediaExport>,
+            exported: i32,
+            skipped: i32,
+            failed: i32,
+            cancelled: bool,
+        );
+
+        #[qsignal]
+        fn export_error(self: Pin<&mut MediaExport>, message: &QString);
+    }
+
+    impl cxx_qt::Threading for MediaExport {}
+
+    // ── SimList ──────────────────────────────────────────────────
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[base = QAbstractListModel]
+        type SimList = super::SimListRust;
+
+        #[cxx_override]
+        #[rust_name = "row_count"]
+        fn rowCount(&self, parent: &QModelIndex) -> i32;
+
+        #[cxx_override]
+        fn data(&self, index: &QModelIndex, role: i32) -> QVariant;
+
+        #[cxx_override]
+        #[rust_name = "role_names"]
+        fn roleNames(&self) -> QHash_i32_QByteArray;
+
+        #[qinvokable]
+        fn refresh(self: Pin<&mut SimList>);
+
+        #[qinvokable]
+        fn row_for_conversation(self: &SimList, conversation_id: &QString) -> i32;
+
+        #[qinvokable]
+        fn set_conversation_sim(self: &SimList, conversation_id: &QString, row: i32);
+
+        #[qinvokable]
+        fn sim_number_at(self: &SimList, row: i32) -> i32;
+
+        #[inherit]
+        #[rust_name = "begin_reset_model"]
+        fn beginResetModel(self: Pin<&mut Self>);
+
+        #[inherit]
+        #[rust_name = "end_reset_model"]
+        fn endResetModel(self: Pin<&mut Self>);
+    }
+
     // ── MessageList ──────────────────────────────────────────────
 
     unsafe extern "RustQt" {
@@ -237,13 +555,33 @@ mod ffi {
         fn load_more(self: Pin<&mut MessageList>);
 
         #[qinvokable]
-        fn send_message(self: Pin<&mut MessageList>, text: &QString);
+        fn send_message(self: Pin<&mut MessageList>, text: &QString, options: &QString);
+
+        #[qinvokable]
+        fn send_media(
+            self: Pin<&mut MessageList>,
+            file_urls: &QStringList,
+            text: &QString,
+            options: &QString,
+        );
+
+        #[qinvokable]
+        fn conversation_transport(self: &MessageList) -> QString;
+
+        #[qinvokable]
+        fn set_conversation_transport(self: &MessageList, transport: &QString);
+
+        #[qinvokable]
+        fn request_thumbnail(self: Pin<&mut MessageList>, file_url: &QString);
+
+        #[qinvokable]
+        fn paste_attachments(self: &MessageList) -> QString;
 
         #[qinvokable]
-        fn send_media(self: Pin<&mut MessageList>, file_url: &QString, text: &QString);
+        fn discard_attachment(self: &MessageList, file_url: &QString);
 
         #[qinvokable]
-        fn get_video_thumbnail(self: &MessageList, file_url: &QString) -> QString;
+        fn new_voice_note_path(self: &MessageList) -> QString;
 
         #[qinvokable]
         fn get_file_size(self: &MessageList, file_url: &QString) -> i64;
@@ -256,7 +594,14 @@ mod ffi {
             self: Pin<&mut MessageList>,
             source_url: &QString,
             mime_type: &QString,
-        ) -> QString;
+            file_name: &QString,
+        );
+
+        #[qsignal]
+        fn media_saved(self: Pin<&mut MessageList>, path: &QString);
+
+        #[qinvokable]
+        fn message_details(self: &MessageList, message_id: &QString) -> QString;
 
         #[qinvokable]
         fn delete_message(self: Pin<&mut MessageList>, message_id: &QString);
@@ -292,6 +637,16 @@ mod ffi {
         #[qsignal]
         fn auth_error(self: Pin<&mut MessageList>, message: &QString);
 
+        #[qsignal]
+        fn send_failed(self: Pin<&mut MessageList>, error: &QString);
+
+        #[qsignal]
+        fn thumbnail_ready(
+            self: Pin<&mut MessageList>,
+            file_url: &QString,
+            thumbnail_url: &QString,
+        );
+
         #[inherit]
         #[rust_name = "begin_reset_model"]
         fn beginResetModel(self: Pin<&mut Self>);
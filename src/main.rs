@@ -1,9 +1,20 @@
 use cxx_qt_lib::{QGuiApplication, QQmlApplicationEngine, QQuickStyle, QString, QUrl};
 use cxx_qt_lib_extras::QApplication;
-use kourier as _;
 use std::env;
 
 fn main() {
+    // sms:/smsto:/tel: links are handed to an already running instance
+    let uri_arg = env::args().skip(1).find(|arg| kourier::is_sms_uri(arg));
+    if let Some(uri) = uri_arg.as_deref() {
+        if kourier::forward_to_running_instance(uri) {
+            return;
+        }
+    }
+    kourier::start_uri_listener();
+    if let Some(uri) = uri_arg.as_deref() {
+        kourier::queue_uri(uri);
+    }
+
     cxx_qt::init_crate!(kourier);
     cxx_qt::init_qml_module!("org.kourier");
 
@@ -18,11 +29,13 @@ fn main() {
     }
 
     let mut engine = QQmlApplicationEngine::new();
-    if let Some(engine) = engine.as_mut() {
+    if let Some(mut engine) = engine.as_mut() {
+        kourier::register_image_provider(engine.as_mut());
         engine.load(&QUrl::from("qrc:/qt/qml/org/kourier/src/qml/Main.qml"));
     }
 
     if let Some(app) = app.as_mut() {
         app.exec();
     }
+    kourier::flush_cache_index();
 }
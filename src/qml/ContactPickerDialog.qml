@@ -0,0 +1,139 @@
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls as Controls
+import org.kde.kirigami as Kirigami
+import org.kde.kirigamiaddons.delegates as Delegates
+import org.kourier
+
+Controls.Dialog {
+    id: contactPickerDialog
+
+    // Emitted with comma-separated numbers for ConversationList.open_for_numbers
+    signal numbersChosen(string numbers)
+
+    // Set to add the chosen numbers to this group instead of opening a conversation
+    property string targetConversationId: ""
+
+    readonly property ContactList contactList: ContactList {}
+
+    title: targetConversationId.length > 0 ? "Add Participants" : "New Conversation"
+    modal: true
+    standardButtons: Controls.Dialog.Close
+    width: Math.min(root.width * 0.80, Kirigami.Units.gridUnit * 24)
+    height: Math.min(root.height * 0.80, Kirigami.Units.gridUnit * 32)
+
+    onOpened: {
+        searchField.text = ""
+        searchField.forceActiveFocus()
+        contactList.load()
+    }
+
+    function choose(numbers) {
+        if (numbers.length === 0) {
+            return
+        }
+        contactPickerDialog.numbersChosen(numbers)
+        contactPickerDialog.close()
+    }
+
+    // Digits typed into the search field, offered as a number to message directly
+    readonly property string typedNumber: /^[+\d\s()-]{3,}$/.test(searchField.text.trim())
+        ? searchField.text.trim()
+        : ""
+
+    contentItem: ColumnLayout {
+        spacing: Kirigami.Units.smallSpacing
+
+        Kirigami.SearchField {
+            id: searchField
+            Layout.fillWidth: true
+            placeholderText: "Search name or number…"
+            onTextChanged: contactPickerDialog.contactList.apply_filter(text)
+            onAccepted: {
+                if (contactPickerDialog.typedNumber.length > 0) {
+                    contactPickerDialog.choose(contactPickerDialog.typedNumber)
+                } else if (contactListView.count > 0) {
+                    contactPickerDialog.choose(contactPickerDialog.contactList.numbers_at(0))
+                }
+            }
+        }
+
+        Delegates.RoundedItemDelegate {
+            Layout.fillWidth: true
+            visible: contactPickerDialog.typedNumber.length > 0
+            text: (contactPickerDialog.targetConversationId.length > 0 ? "Add " : "Message ") + contactPickerDialog.typedNumber
+            icon.name: contactPickerDialog.targetConversationId.length > 0 ? "list-add-user" : "document-send"
+            onClicked: contactPickerDialog.choose(contactPickerDialog.typedNumber)
+        }
+
+        Controls.ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            ListView {
+                id: contactListView
+
+                model: contactPickerDialog.contactList
+                clip: true
+                reuseItems: true
+
+                delegate: Delegates.RoundedItemDelegate {
+                    id: contactDelegate
+
+                    required property int index
+                    required property string name
+                    required property string numbers
+                    required property string avatar_url
+
+                    width: contactListView.width
+                    onClicked: contactPickerDialog.choose(contactPickerDialog.contactList.numbers_at(contactDelegate.index))
+
+                    contentItem: RowLayout {
+                        spacing: Kirigami.Units.largeSpacing
+
+                        Rectangle {
+                            Layout.preferredWidth: Kirigami.Units.gridUnit * 2
+                            Layout.preferredHeight: Kirigami.Units.gridUnit * 2
+                            radius: width / 2
+                            color: Kirigami.Theme.alternateBackgroundColor
+                            clip: true
+
+                            Image {
+                                anchors.fill: parent
+                                source: contactDelegate.avatar_url
+                                fillMode: Image.PreserveAspectCrop
+                                visible: contactDelegate.avatar_url.length > 0
+                            }
+                        }
+
+                        ColumnLayout {
+                            Layout.fillWidth: true
+                            spacing: 0
+
+                            Controls.Label {
+                                Layout.fillWidth: true
+                                text: contactDelegate.name.length > 0 ? contactDelegate.name : contactDelegate.numbers
+                                elide: Text.ElideRight
+                            }
+                            Controls.Label {
+                                Layout.fillWidth: true
+                                text: contactDelegate.numbers
+                                visible: contactDelegate.name.length > 0
+                                opacity: 0.7
+                                font: Kirigami.Theme.smallFont
+                                elide: Text.ElideRight
+                            }
+                        }
+                    }
+                }
+
+                Kirigami.PlaceholderMessage {
+                    anchors.centerIn: parent
+                    width: parent.width - Kirigami.Units.gridUnit * 4
+                    visible: contactListView.count === 0
+                    text: contactPickerDialog.contactList.loading ? "Loading contacts…" : "No contacts found"
+                }
+            }
+        }
+    }
+}
@@ -4,6 +4,7 @@ import QtQuick.Controls as Controls
 import org.kde.kirigami as Kirigami
 import org.kde.kirigamiaddons.delegates as Delegates
 import QtQuick.Dialogs
+import QtMultimedia
 import org.kourier
 import Qt.labs.platform as Platform
 
@@ -79,15 +80,21 @@ Kirigami.ApplicationWindow {
     readonly property SessionController sessionController: SessionController {}
     readonly property ConversationList conversationList: ConversationList {}
     readonly property MessageList messageListModel: MessageList {}
+    readonly property SimList simList: SimList {}
 
     property int selectedConversationIndex: -1
     property string selectedConversationName: ""
     property string selectedMeParticipantId: ""
+    property bool selectedIsGroupChat: false
+    property string selectedTransport: "auto"
     property string outgoingText: ""
     property int statusVisibleIndex: -1
     property int lastMessageCount: 0
     property string pendingConversationFilter: ""
     property bool userAtBottom: true
+    property bool conversationsLoaded: false
+    property string pendingUriNumbers: ""
+    property string pendingUriBody: ""
 
     // ── Staged attachments model ───────────────────────────────────
     ListModel {
@@ -105,16 +112,20 @@ Kirigami.ApplicationWindow {
         const convoId = root.conversationList.conversation_id(root.selectedConversationIndex);
 
         if (hasMedia) {
-            // Send each attachment individually; text goes with the last one
+            // All attachments and the text go out as one message, shown as an album.
+            // Location is only kept if it was kept for every photo.
+            const urls = [];
+            let keepMetadata = true;
             for (let i = 0; i < stagedAttachments.count; i++) {
-                const fileUrl = stagedAttachments.get(i).fileUrl;
-                const caption = (i === stagedAttachments.count - 1 && hasText) ? body : "";
-                root.messageListModel.send_media(fileUrl, caption);
+                const staged = stagedAttachments.get(i);
+                urls.push(staged.fileUrl);
+                keepMetadata = keepMetadata && staged.keepMetadata;
             }
+            root.messageListModel.send_media(urls, body, JSON.stringify({ keep_metadata: keepMetadata }));
             stagedAttachments.clear();
             root.conversationList.update_preview(convoId, hasText ? "You: " + body : "You: Media", Date.now() * 1000);
         } else {
-            root.messageListModel.send_message(body);
+            root.messageListModel.send_message(body, "");
             root.conversationList.update_preview(convoId, "You: " + body, Date.now() * 1000);
         }
 
@@ -123,9 +134,91 @@ Kirigami.ApplicationWindow {
         typingDebounce.stop();
     }
 
+    // ── Helper: add a file to the attachments waiting to be sent ──
+    function stageAttachment(url) {
+        // Check file size via Rust FFI (fs::metadata — instant, no file read)
+        const maxBytes = 100 * 1024 * 1024; // 100 MB RCS limit
+        const size = root.messageListModel.get_file_size(url);
+        if (size < 0) {
+            root.showPassiveNotification("Could not read file info", "short");
+            return;
+        }
+        // Photos and videos are re-encoded to fit before upload
+        const lower = url.toLowerCase();
+        const shrinkable = /\.(jpe?g|png|webp|mp4|webm|3gp|3g2|mov|mkv)$/.test(lower);
+        if (size > maxBytes && !shrinkable) {
+            const sizeMB = (size / 1048576).toFixed(1);
+            root.showPassiveNotification(
+                `File too large: ${sizeMB} MB (RCS limit is 100 MB)`,
+                "long"
+            );
+            return;
+        }
+
+        // The thumbnail arrives later through onThumbnail_ready
+        stagedAttachments.append({ fileUrl: url, thumbUrl: "", keepMetadata: root.appState.keep_photo_metadata });
+        root.messageListModel.request_thumbnail(url);
+    }
+
+    // ── Helper: stage images or files from the clipboard ─────────
+    // Returns false when there are none, so the paste goes in as text.
+    function pasteAttachments() {
+        const urls = root.messageListModel.paste_attachments();
+        if (urls.length === 0) return false;
+        for (const url of urls.split("\n")) {
+            root.stageAttachment(url);
+        }
+        return true;
+    }
+
+    // ── Helper: remember how to send in the open conversation ────
+    function setSelectedTransport(transport) {
+        root.messageListModel.set_conversation_transport(transport)
+        root.selectedTransport = transport
+    }
+
+    // ── Helper: open a conversation row ──────────────────────────
+    function openConversation(index) {
+        const convoId = root.conversationList.conversation_id(index)
+        root.selectedConversationIndex = index
+        root.selectedConversationName = root.conversationList.conversation_name(index)
+        root.selectedMeParticipantId = root.conversationList.me_participant_id(index)
+        root.selectedIsGroupChat = root.conversationList.is_group_chat(index)
+        root.statusVisibleIndex = -1
+        root.lastMessageCount = 0
+        root.messageListModel.load(convoId)
+        root.selectedTransport = root.messageListModel.conversation_transport()
+        root.conversationList.mark_conversation_read(convoId)
+
+        // Push the conversation page if not already shown
+        if (root.pageStack.depth < 2) {
+            root.pageStack.push(conversationPageComponent)
+        }
+        root.pageStack.currentIndex = 1
+    }
+
+    // ── Helper: resolve a pending sms:/tel: link ─────────────────
+    function openPendingUri() {
+        if (root.pendingUriNumbers.length === 0 || !root.conversationsLoaded) return;
+        root.conversationList.open_for_numbers(root.pendingUriNumbers, root.pendingUriBody);
+        root.pendingUriNumbers = "";
+        root.pendingUriBody = "";
+    }
+
     // ── Global Drawer ────────────────────────────────────────────
     globalDrawer: Kirigami.GlobalDrawer {
         id: globalDrawer
+
+        property string cacheSizeText: ""
+
+        onOpened: {
+            var usage = JSON.parse(root.appState.cache_usage())
+            var total = 0
+            for (var i = 0; i < usage.length; i++) {
+                total += usage[i].bytes
+            }
+            cacheSizeText = (total / (1024 * 1024)).toFixed(1) + " MB"
+        }
         
         // The drawer needs at least one action or it might refuse to open
         actions: [
@@ -153,7 +246,9 @@ Kirigami.ApplicationWindow {
             }
             Controls.ItemDelegate {
                 Layout.fillWidth: true
-                text: "Clear Cache"
+                text: globalDrawer.cacheSizeText.length > 0
+                    ? "Clear Cache (" + globalDrawer.cacheSizeText + ")"
+                    : "Clear Cache"
                 icon.name: "edit-clear"
                 onClicked: {
                     globalDrawer.close()
@@ -161,6 +256,87 @@ Kirigami.ApplicationWindow {
                     root.showPassiveNotification("Cache cleared", "short")
                 }
             }
+            Controls.SwitchDelegate {
+                Layout.fillWidth: true
+                text: "Encrypt Cache"
+                icon.name: "document-encrypt"
+                checked: root.appState.encrypted_cache
+                onToggled: root.appState.set_cache_encryption(checked)
+            }
+            Controls.SwitchDelegate {
+                Layout.fillWidth: true
+                text: "Keep Photo Location"
+                icon.name: "mark-location"
+                checked: root.appState.keep_photo_metadata
+                onToggled: root.appState.set_photo_metadata_kept(checked)
+            }
+            Controls.ItemDelegate {
+                id: mediaQualityDelegate
+
+                readonly property var qualities: [
+                    { name: "original", label: "Original" },
+                    { name: "high", label: "High" },
+                    { name: "data_saver", label: "Data Saver" }
+                ]
+
+                Layout.fillWidth: true
+                text: "Media Quality: " + (qualities.find(q => q.name === root.appState.media_quality) || qualities[1]).label
+                icon.name: "image-x-generic"
+                onClicked: mediaQualityMenu.popup()
+
+                Controls.Menu {
+                    id: mediaQualityMenu
+
+                    Repeater {
+                        model: mediaQualityDelegate.qualities
+
+                        Controls.MenuItem {
+                            required property var modelData
+
+                            text: modelData.label
+                            checkable: true
+                            checked: root.appState.media_quality === modelData.name
+                            onTriggered: root.appState.set_media_quality_setting(modelData.name)
+                        }
+                    }
+                }
+            }
+            Controls.ItemDelegate {
+                id: linkPreviewDelegate
+
+                readonly property var policies: [
+                    { name: "off", label: "Off" },
+                    { name: "own_messages", label: "My Messages" },
+                    { name: "known_contacts", label: "Contacts" },
+                    { name: "always", label: "Always" }
+                ]
+
+                Layout.fillWidth: true
+                text: "Link Previews: " + (policies.find(p => p.name === root.appState.link_preview_policy) || policies[3]).label
+                icon.name: "internet-services"
+                onClicked: linkPreviewMenu.popup()
+
+                Controls.ToolTip.text: "Opening a link for its preview tells the website your IP address"
+                Controls.ToolTip.visible: hovered
+                Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+
+                Controls.Menu {
+                    id: linkPreviewMenu
+
+                    Repeater {
+                        model: linkPreviewDelegate.policies
+
+                        Controls.MenuItem {
+                            required property var modelData
+
+                            text: modelData.label
+                            checkable: true
+                            checked: root.appState.link_preview_policy === modelData.name
+                            onTriggered: root.appState.set_link_preview_policy_setting(modelData.name)
+                        }
+                    }
+                }
+            }
             Controls.ItemDelegate {
                 Layout.fillWidth: true
                 text: "Log out"
@@ -245,6 +421,14 @@ Kirigami.ApplicationWindow {
                             filterDebounce.restart()
                         }
                     }
+                    Controls.ToolButton {
+                        icon.name: "list-add"
+                        display: Controls.AbstractButton.IconOnly
+                        text: "New Conversation"
+                        Controls.ToolTip.text: text
+                        Controls.ToolTip.visible: hovered
+                        onClicked: contactPickerDialog.open()
+                    }
                 }
             }
 
@@ -281,22 +465,7 @@ Kirigami.ApplicationWindow {
                         width: conversationListView.width
                         highlighted: conversationDelegate.index === root.selectedConversationIndex
 
-                        onClicked: {
-                            const convoId = root.conversationList.conversation_id(conversationDelegate.index)
-                            root.selectedConversationIndex = conversationDelegate.index
-                            root.selectedConversationName = conversationDelegate.name
-                            root.selectedMeParticipantId = root.conversationList.me_participant_id(conversationDelegate.index)
-                            root.statusVisibleIndex = -1
-                            root.lastMessageCount = 0
-                            root.messageListModel.load(convoId)
-                            root.conversationList.mark_conversation_read(convoId)
-
-                            // Push the conversation page if not already shown
-                            if (root.pageStack.depth < 2) {
-                                root.pageStack.push(conversationPageComponent)
-                            }
-                            root.pageStack.currentIndex = 1
-                        }
+                        onClicked: root.openConversation(conversationDelegate.index)
 
                         contentItem: RowLayout {
                             spacing: Kirigami.Units.largeSpacing
@@ -404,6 +573,53 @@ Kirigami.ApplicationWindow {
                 : "Messages"
             padding: 0
 
+            actions: [
+                Kirigami.Action {
+                    text: root.selectedIsGroupChat ? "Participants" : "Details"
+                    icon.name: root.selectedIsGroupChat ? "group" : "user-identity"
+                    enabled: root.selectedConversationIndex >= 0
+                    onTriggered: participantsDialog.open()
+                },
+                Kirigami.Action {
+                    text: "Media"
+                    icon.name: "folder-pictures"
+                    enabled: root.selectedConversationIndex >= 0
+                    onTriggered: mediaGalleryDialog.open()
+                },
+                Kirigami.Action {
+                    text: "Save All Media…"
+                    icon.name: "document-save-all"
+                    enabled: root.selectedConversationIndex >= 0
+                    displayHint: Kirigami.DisplayHint.AlwaysHide
+                    onTriggered: mediaExportDialog.open()
+                },
+                Kirigami.Action {
+                    text: "Send As"
+                    icon.name: "mail-send"
+                    enabled: root.selectedConversationIndex >= 0
+                    displayHint: Kirigami.DisplayHint.AlwaysHide
+
+                    Kirigami.Action {
+                        text: "Automatic"
+                        checkable: true
+                        checked: root.selectedTransport === "auto"
+                        onTriggered: root.setSelectedTransport("auto")
+                    }
+                    Kirigami.Action {
+                        text: "RCS"
+                        checkable: true
+                        checked: root.selectedTransport === "rcs"
+                        onTriggered: root.setSelectedTransport("rcs")
+                    }
+                    Kirigami.Action {
+                        text: "SMS/MMS"
+                        checkable: true
+                        checked: root.selectedTransport === "sms"
+                        onTriggered: root.setSelectedTransport("sms")
+                    }
+                }
+            ]
+
             ColumnLayout {
                 anchors.fill: parent
                 spacing: 0
@@ -624,6 +840,7 @@ Kirigami.ApplicationWindow {
                             required property int index
                             required property string fileUrl
                             required property string thumbUrl
+                            required property bool keepMetadata
 
                             readonly property bool isVideoFile: {
                                 const lower = thumbContainer.fileUrl.toLowerCase()
@@ -640,7 +857,9 @@ Kirigami.ApplicationWindow {
                             // Image thumbnail
                             Image {
                                 anchors.fill: parent
-                                source: thumbContainer.isVideoFile ? thumbContainer.thumbUrl : thumbContainer.fileUrl
+                                source: thumbContainer.isVideoFile || thumbContainer.thumbUrl.length > 0
+                                    ? thumbContainer.thumbUrl
+                                    : thumbContainer.fileUrl
                                 fillMode: Image.PreserveAspectCrop
                                 asynchronous: true
                                 sourceSize.width: Kirigami.Units.gridUnit * 5
@@ -687,8 +906,45 @@ Kirigami.ApplicationWindow {
                                 MouseArea {
                                     anchors.fill: parent
                                     cursorShape: Qt.PointingHandCursor
-                                    onClicked: stagedAttachments.remove(thumbContainer.index)
+                                    onClicked: {
+                                        root.messageListModel.discard_attachment(stagedAttachments.get(thumbContainer.index).fileUrl)
+                                        stagedAttachments.remove(thumbContainer.index)
+                                    }
+                                }
+                            }
+
+                            // Per-photo override of the metadata setting
+                            Rectangle {
+                                anchors.bottom: parent.bottom
+                                anchors.left: parent.left
+                                width: Kirigami.Units.gridUnit * 1.5
+                                height: Kirigami.Units.gridUnit * 1.5
+                                radius: width / 2
+                                color: Qt.rgba(0, 0, 0, 0.55)
+                                visible: !thumbContainer.isVideoFile
+
+                                Kirigami.Icon {
+                                    anchors.centerIn: parent
+                                    width: Kirigami.Units.iconSizes.small
+                                    height: Kirigami.Units.iconSizes.small
+                                    source: "mark-location"
+                                    color: "white"
+                                    opacity: thumbContainer.keepMetadata ? 1.0 : 0.4
+                                }
+
+                                MouseArea {
+                                    id: metadataToggleArea
+                                    anchors.fill: parent
+                                    hoverEnabled: true
+                                    cursorShape: Qt.PointingHandCursor
+                                    onClicked: stagedAttachments.setProperty(thumbContainer.index, "keepMetadata", !thumbContainer.keepMetadata)
                                 }
+
+                                Controls.ToolTip.text: thumbContainer.keepMetadata
+                                    ? "Location and camera info will be sent"
+                                    : "Location and camera info will be removed"
+                                Controls.ToolTip.visible: metadataToggleArea.containsMouse
+                                Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
                             }
                         }
                     }
@@ -704,12 +960,36 @@ Kirigami.ApplicationWindow {
                     Controls.RoundButton {
                         icon.name: "list-add"
                         Layout.alignment: Qt.AlignBottom
+                        visible: voiceRecorder.recorderState === MediaRecorder.StoppedState
                         onClicked: attachmentDialog.open()
                         Controls.ToolTip.text: "Add media"
                         Controls.ToolTip.visible: hovered
                         Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
                     }
 
+                    Controls.RoundButton {
+                        icon.name: "dialog-cancel"
+                        Layout.alignment: Qt.AlignBottom
+                        visible: voiceRecorder.recorderState === MediaRecorder.RecordingState
+                        onClicked: voiceRecorder.cancel()
+                        Controls.ToolTip.text: "Discard recording"
+                        Controls.ToolTip.visible: hovered
+                        Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+                    }
+
+                    Controls.RoundButton {
+                        readonly property bool recording: voiceRecorder.recorderState === MediaRecorder.RecordingState
+
+                        icon.name: recording ? "media-playback-stop" : "audio-input-microphone"
+                        Layout.alignment: Qt.AlignBottom
+                        onClicked: recording ? voiceRecorder.stop() : voiceRecorder.start()
+                        Controls.ToolTip.text: recording
+                            ? "Stop and send (" + Math.floor(voiceRecorder.duration / 1000) + "s)"
+                            : "Record voice message"
+                        Controls.ToolTip.visible: hovered || recording
+                        Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+                    }
+
                     Controls.ScrollView {
                         Layout.fillWidth: true
                         Layout.maximumHeight: Kirigami.Units.gridUnit * 8
@@ -734,6 +1014,12 @@ Kirigami.ApplicationWindow {
                                     typingDebounce.stop()
                                 }
                             }
+                            // Screenshots and copied files are attached instead of pasted as text
+                            Keys.onPressed: function(event) {
+                                if (event.matches(StandardKey.Paste) && root.pasteAttachments()) {
+                                    event.accepted = true
+                                }
+                            }
                             Keys.onReturnPressed: function(event) {
                                 if (event.modifiers & Qt.ShiftModifier) {
                                     // Shift+Enter: insert newline
@@ -746,6 +1032,36 @@ Kirigami.ApplicationWindow {
                             }
                         }
                     }
+                    // Only shown for dual-SIM phones; the choice is remembered per conversation
+                    Controls.ComboBox {
+                        id: simSelector
+                        Layout.alignment: Qt.AlignBottom
+                        visible: count > 1
+                        model: root.simList
+                        textRole: "label"
+                        displayText: currentIndex < 0 ? "Default SIM" : currentText
+
+                        function syncFromSettings() {
+                            currentIndex = root.selectedConversationIndex >= 0
+                                ? root.simList.row_for_conversation(root.conversationList.conversation_id(root.selectedConversationIndex))
+                                : -1
+                        }
+
+                        Component.onCompleted: syncFromSettings()
+                        onCountChanged: syncFromSettings()
+                        onActivated: (index) => root.simList.set_conversation_sim(root.conversationList.conversation_id(root.selectedConversationIndex), index)
+
+                        Connections {
+                            target: root
+                            function onSelectedConversationIndexChanged() {
+                                simSelector.syncFromSettings()
+                            }
+                        }
+
+                        Controls.ToolTip.text: "Send from"
+                        Controls.ToolTip.visible: hovered
+                        Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+                    }
                     Controls.Button {
                         icon.name: "document-send"
                         text: "Send"
@@ -789,36 +1105,99 @@ Kirigami.ApplicationWindow {
         id: mediaViewerDialog
     }
 
+    ParticipantsDialog {
+        id: participantsDialog
+    }
+
+    MediaGalleryDialog {
+        id: mediaGalleryDialog
+    }
+
+    MediaExportDialog {
+        id: mediaExportDialog
+    }
+
+    MessageDetailsDialog {
+        id: messageDetailsDialog
+    }
+
+    ContactPickerDialog {
+        id: contactPickerDialog
+        onNumbersChosen: (numbers) => {
+            if (contactPickerDialog.targetConversationId.length > 0) {
+                root.conversationList.add_participants(contactPickerDialog.targetConversationId, numbers)
+            } else {
+                root.conversationList.open_for_numbers(numbers, "")
+            }
+        }
+        onClosed: contactPickerDialog.targetConversationId = ""
+    }
+
+    // ── Locked keyring ──
+    Kirigami.PromptDialog {
+        id: keyringErrorDialog
+        title: "Keyring Locked"
+        standardButtons: Kirigami.Dialog.Close
+        customFooterActions: [
+            Kirigami.Action {
+                text: "Try Again"
+                icon.name: "view-refresh"
+                onTriggered: {
+                    keyringErrorDialog.close()
+                    appState.retry_auth_load()
+                }
+            }
+        ]
+    }
+
+    // ── Voice Recorder ──
+    CaptureSession {
+        audioInput: AudioInput {}
+        recorder: MediaRecorder {
+            id: voiceRecorder
+
+            // Cleared when the recording is cancelled rather than sent
+            property bool sendWhenStopped: false
+
+            mediaFormat {
+                fileFormat: MediaFormat.Mpeg4Audio
+                audioCodec: MediaFormat.AudioCodec.AAC
+            }
+            quality: MediaRecorder.NormalQuality
+
+            function start() {
+                sendWhenStopped = true
+                outputLocation = "file://" + root.messageListModel.new_voice_note_path()
+                record()
+            }
+
+            function cancel() {
+                sendWhenStopped = false
+                stop()
+            }
+
+            onRecorderStateChanged: {
+                if (recorderState === MediaRecorder.StoppedState && sendWhenStopped) {
+                    sendWhenStopped = false
+                    root.messageListModel.send_media([actualLocation.toString()], "", "")
+                }
+            }
+            onErrorOccurred: (error, errorString) => {
+                sendWhenStopped = false
+                root.showPassiveNotification("Recording failed: " + errorString, "long")
+            }
+        }
+    }
+
     // ── Attachment Dialog ──
     FileDialog {
         id: attachmentDialog
         title: "Select Attachment"
-        nameFilters: ["All media (*.png *.jpg *.jpeg *.gif *.webp *.mp4 *.webm *.3gp)", "Images (*.png *.jpg *.jpeg *.gif *.webp)", "Videos (*.mp4 *.webm *.3gp)", "All files (*)"]
+        nameFilters: ["All media (*.png *.jpg *.jpeg *.gif *.webp *.heic *.heif *.avif *.mp4 *.webm *.3gp)", "Images (*.png *.jpg *.jpeg *.gif *.webp *.heic *.heif *.avif)", "Videos (*.mp4 *.webm *.3gp)", "Documents (*.pdf *.doc *.docx *.xls *.xlsx *.ppt *.pptx *.odt *.ods *.odp *.txt *.csv *.rtf)", "Contacts and events (*.vcf *.ics)", "All files (*)"]
         onAccepted: {
             const url = String(attachmentDialog.selectedFile || attachmentDialog.currentFile);
             if (url.length > 0) {
-                // Check file size via Rust FFI (fs::metadata — instant, no file read)
-                const maxBytes = 100 * 1024 * 1024; // 100 MB RCS limit
-                const size = root.messageListModel.get_file_size(url);
-                if (size < 0) {
-                    root.showPassiveNotification("Could not read file info", "short");
-                    return;
-                }
-                if (size > maxBytes) {
-                    const sizeMB = (size / 1048576).toFixed(1);
-                    root.showPassiveNotification(
-                        `File too large: ${sizeMB} MB (RCS limit is 100 MB)`,
-                        "long"
-                    );
-                    return;
-                }
-
-                let thumb = "";
-                const lower = url.toLowerCase();
-                if (lower.endsWith(".mp4") || lower.endsWith(".webm") || lower.endsWith(".3gp") || lower.endsWith(".3g2")) {
-                    thumb = root.messageListModel.get_video_thumbnail(url);
-                }
-                stagedAttachments.append({ fileUrl: url, thumbUrl: thumb });
+                root.stageAttachment(url);
             }
         }
     }
@@ -835,6 +1214,12 @@ Kirigami.ApplicationWindow {
             }
         }
 
+        function onAuth_storage_error(message) {
+            keyringErrorDialog.subtitle = message
+                + "\n\nKourier keeps its pairing credentials in the system keyring. Unlock it to stay logged in."
+            keyringErrorDialog.open()
+        }
+
         function onLogged_inChanged() {
             if (appState.logged_in && loginDialog.visible) {
                 loginDialog.close()
@@ -852,9 +1237,18 @@ Kirigami.ApplicationWindow {
                 root.pageStack.push(welcomeComponent)
                 root.selectedConversationIndex = -1
                 root.selectedConversationName = ""
+                root.conversationsLoaded = false
                 root.showPassiveNotification("Logged out", "short")
             }
         }
+
+        function onSms_uri_requested(numbers, body) {
+            root.visible = true
+            root.requestActivate()
+            root.pendingUriNumbers = numbers
+            root.pendingUriBody = body
+            root.openPendingUri()
+        }
     }
 
     Connections {
@@ -870,6 +1264,10 @@ Kirigami.ApplicationWindow {
             }
         }
 
+        function onSims_updated() {
+            root.simList.refresh()
+        }
+
         function onMessage_received(conversationId, participantId, body, transportType, messageId, tmpId, timestampMicros, statusCode, isMedia, mediaId, decryptionKey, mimeType, mediaWidth, mediaHeight) {
             messageListModel.handle_message_event(conversationId, participantId, body, transportType, messageId, tmpId, timestampMicros, statusCode, isMedia, mediaWidth, mediaHeight)
             conversationList.update_preview(conversationId, isMedia ? "Media" : body, timestampMicros)
@@ -884,8 +1282,16 @@ Kirigami.ApplicationWindow {
             }
         }
 
-        function onConversation_updated(conversationId, name, preview, unread, lastMessageTimestamp, isGroupChat, status, avatarIdentifier) {
-            conversationList.handle_conversation_event(conversationId, name, preview, unread, lastMessageTimestamp, isGroupChat, status, avatarIdentifier)
+        function onConversation_updated(conversationId, name, preview, unread, lastMessageTimestamp, isGroupChat, status, avatarIdentifier, participantNumbers) {
+            const wasViewing = root.selectedConversationIndex >= 0 && root.conversationList.conversation_id(root.selectedConversationIndex) === conversationId
+            conversationList.handle_conversation_event(conversationId, name, preview, unread, lastMessageTimestamp, isGroupChat, status, avatarIdentifier, participantNumbers)
+            if (wasViewing) {
+                // Rows may have been re-sorted; keep the selection on this conversation
+                root.selectedConversationIndex = root.conversationList.row_for_conversation(conversationId)
+                if (name.length > 0) {
+                    root.selectedConversationName = name
+                }
+            }
 
             if (unread && lastMessageTimestamp > Date.now() * 1000 - 60000000) {
                 const isViewing = (root.selectedConversationIndex >= 0 && root.conversationList.conversation_id(root.selectedConversationIndex) === conversationId)
@@ -906,12 +1312,44 @@ Kirigami.ApplicationWindow {
 
         function onLoaded() {
             sessionController.fetch_updates()
+            root.conversationsLoaded = true
+            root.openPendingUri()
+        }
+
+        function onConversation_opened(conversationId, body) {
+            let row = root.conversationList.row_for_conversation(conversationId)
+            if (row < 0) {
+                // Hidden by the search filter
+                root.pendingConversationFilter = ""
+                root.conversationList.apply_filter("")
+                row = root.conversationList.row_for_conversation(conversationId)
+            }
+            if (row < 0) return;
+            root.openConversation(row)
+            if (body.length > 0) {
+                root.outgoingText = body
+            }
         }
 
         function onAuth_error(message) {
             root.showPassiveNotification("Authentication error: " + message, "long")
             appState.logout(message)
         }
+
+        function onGroup_action_finished(conversationId, action, error) {
+            if (error.length > 0) {
+                root.showPassiveNotification("Group update failed: " + error, "long")
+                return
+            }
+            if (action === "leave") {
+                root.selectedConversationIndex = -1
+                root.selectedConversationName = ""
+                if (root.pageStack.depth > 1) {
+                    root.pageStack.pop()
+                }
+                root.showPassiveNotification("Left the group", "short")
+            }
+        }
     }
 
     Connections {
@@ -921,5 +1359,25 @@ Kirigami.ApplicationWindow {
             root.showPassiveNotification("Authentication error: " + message, "long")
             appState.logout(message)
         }
+
+        function onMedia_saved(path) {
+            if (path.length > 0) {
+                root.showPassiveNotification("Saved to " + path, "long")
+            } else {
+                root.showPassiveNotification("Failed to save media", "short")
+            }
+        }
+
+        function onSend_failed(error) {
+            root.showPassiveNotification("Not sent: " + error, "long")
+        }
+
+        function onThumbnail_ready(fileUrl, thumbnailUrl) {
+            for (let i = 0; i < stagedAttachments.count; i++) {
+                if (stagedAttachments.get(i).fileUrl === fileUrl) {
+                    stagedAttachments.setProperty(i, "thumbUrl", thumbnailUrl)
+                }
+            }
+        }
     }
 }
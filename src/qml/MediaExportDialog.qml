@@ -0,0 +1,133 @@
+import QtCore
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls as Controls
+import QtQuick.Dialogs
+import org.kde.kirigami as Kirigami
+import org.kourier
+
+Controls.Dialog {
+    id: mediaExportDialog
+
+    // Keeps running in the background when the dialog is closed
+    readonly property MediaExport exporter: MediaExport {
+        onProgress: (exported, skipped, failed) => {
+            mediaExportDialog.exported = exported
+            mediaExportDialog.skipped = skipped
+            mediaExportDialog.failed = failed
+        }
+        onFinished: (exported, skipped, failed, cancelled) => {
+            let summary = (cancelled ? "Export cancelled: " : "Export finished: ")
+                + exported + " saved"
+            if (skipped > 0) {
+                summary += ", " + skipped + " already saved"
+            }
+            if (failed > 0) {
+                summary += ", " + failed + " failed"
+            }
+            root.showPassiveNotification(summary, "long")
+        }
+        onExport_error: (message) => root.showPassiveNotification("Export failed: " + message, "long")
+    }
+
+    property int exported: 0
+    property int skipped: 0
+    property int failed: 0
+
+    title: "Save All Media"
+    modal: true
+    width: Math.min(root.width * 0.80, Kirigami.Units.gridUnit * 26)
+
+    // Plain path from a folder URL, for showing and editing
+    function localPath(url) {
+        return decodeURIComponent(String(url).replace(/^file:\/\//, ""))
+    }
+
+    onOpened: {
+        if (!exporter.running) {
+            const name = root.selectedConversationName.replace(/[\/\\]/g, "-")
+            folderField.text = localPath(StandardPaths.writableLocation(StandardPaths.PicturesLocation))
+                + "/Kourier/" + name
+        }
+    }
+
+    contentItem: Kirigami.FormLayout {
+        RowLayout {
+            Kirigami.FormData.label: "Folder:"
+            enabled: !mediaExportDialog.exporter.running
+
+            Controls.TextField {
+                id: folderField
+                Layout.fillWidth: true
+            }
+            Controls.Button {
+                icon.name: "document-open-folder"
+                onClicked: exportFolderDialog.open()
+            }
+        }
+
+        Controls.TextField {
+            id: fromField
+            Kirigami.FormData.label: "From:"
+            placeholderText: "YYYY-MM-DD (optional)"
+            enabled: !mediaExportDialog.exporter.running
+        }
+
+        Controls.TextField {
+            id: toField
+            Kirigami.FormData.label: "To:"
+            placeholderText: "YYYY-MM-DD (optional)"
+            enabled: !mediaExportDialog.exporter.running
+        }
+
+        Controls.Label {
+            Kirigami.FormData.label: "Progress:"
+            visible: mediaExportDialog.exporter.running
+            text: mediaExportDialog.exported + " saved, "
+                + mediaExportDialog.skipped + " already saved"
+                + (mediaExportDialog.failed > 0 ? ", " + mediaExportDialog.failed + " failed" : "")
+        }
+
+        Controls.Label {
+            Layout.fillWidth: true
+            text: "Files are named after their date, sender and original name. Items already saved to this folder are skipped."
+            wrapMode: Text.WordWrap
+            opacity: 0.7
+            font: Kirigami.Theme.smallFont
+        }
+    }
+
+    footer: Controls.DialogButtonBox {
+        Controls.Button {
+            text: mediaExportDialog.exporter.running ? "Stop" : "Save"
+            icon.name: mediaExportDialog.exporter.running ? "process-stop" : "document-save"
+            enabled: mediaExportDialog.exporter.running || folderField.text.length > 0
+            Controls.DialogButtonBox.buttonRole: Controls.DialogButtonBox.ActionRole
+            onClicked: {
+                if (mediaExportDialog.exporter.running) {
+                    mediaExportDialog.exporter.cancel()
+                    return
+                }
+                mediaExportDialog.exported = 0
+                mediaExportDialog.skipped = 0
+                mediaExportDialog.failed = 0
+                mediaExportDialog.exporter.start(
+                    root.conversationList.conversation_id(root.selectedConversationIndex),
+                    folderField.text,
+                    fromField.text,
+                    toField.text
+                )
+            }
+        }
+        Controls.Button {
+            text: "Close"
+            Controls.DialogButtonBox.buttonRole: Controls.DialogButtonBox.RejectRole
+        }
+    }
+
+    FolderDialog {
+        id: exportFolderDialog
+        title: "Save Media To"
+        onAccepted: folderField.text = mediaExportDialog.localPath(exportFolderDialog.selectedFolder)
+    }
+}
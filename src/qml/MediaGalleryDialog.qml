@@ -0,0 +1,152 @@
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls as Controls
+import org.kde.kirigami as Kirigami
+import org.kourier
+
+Controls.Dialog {
+    id: mediaGalleryDialog
+
+    readonly property MediaGallery gallery: MediaGallery {}
+
+    title: "Media, Files and Links"
+    modal: true
+    standardButtons: Controls.Dialog.Close
+    width: Math.min(root.width * 0.80, Kirigami.Units.gridUnit * 26)
+    height: Math.min(root.height * 0.85, Kirigami.Units.gridUnit * 34)
+
+    onOpened: gallery.load(root.conversationList.conversation_id(root.selectedConversationIndex))
+
+    // Open a row in the viewer, which can then step through the rest
+    function openItem(row) {
+        const item = JSON.parse(gallery.item_at(row))
+        if (item.link_url && item.link_url.length > 0) {
+            Qt.openUrlExternally(item.link_url)
+            return
+        }
+        if (!item.mime_type.startsWith("image/") && !item.mime_type.startsWith("video/")) {
+            // Other files open in their own application once downloaded
+            if (item.media_url.length > 0) {
+                Qt.openUrlExternally(item.media_url)
+            } else {
+                root.showPassiveNotification("Still downloading " + item.file_name, "short")
+            }
+            return
+        }
+        mediaViewerDialog.gallery = gallery
+        mediaViewerDialog.showGalleryItem(row)
+        mediaViewerDialog.open()
+    }
+
+    contentItem: ListView {
+        id: galleryList
+        clip: true
+        model: mediaGalleryDialog.gallery
+
+        section.property: "month"
+        section.delegate: Kirigami.ListSectionHeader {
+            required property string section
+            width: ListView.view.width
+            text: section
+        }
+
+        // Page further back as the end comes into view
+        onAtYEndChanged: {
+            if (atYEnd && count > 0) {
+                mediaGalleryDialog.gallery.load_more()
+            }
+        }
+
+        delegate: Controls.ItemDelegate {
+            id: galleryRow
+            required property int index
+            required property string media_url
+            required property string thumbnail_url
+            required property string mime_type
+            required property string file_name
+            required property string link_url
+            required property bool is_link
+            required property string date
+            required property string sender_name
+
+            readonly property bool isImage: galleryRow.mime_type.startsWith("image/")
+            readonly property bool isVideo: galleryRow.mime_type.startsWith("video/")
+
+            width: ListView.view.width
+            Component.onCompleted: mediaGalleryDialog.gallery.request_thumbnail(galleryRow.index)
+            onClicked: mediaGalleryDialog.openItem(galleryRow.index)
+
+            contentItem: RowLayout {
+                spacing: Kirigami.Units.largeSpacing
+
+                Rectangle {
+                    Layout.preferredWidth: Kirigami.Units.gridUnit * 3
+                    Layout.preferredHeight: Kirigami.Units.gridUnit * 3
+                    radius: Kirigami.Units.smallSpacing
+                    color: Kirigami.Theme.alternateBackgroundColor
+                    clip: true
+
+                    Image {
+                        anchors.fill: parent
+                        source: galleryRow.thumbnail_url.length > 0 ? galleryRow.thumbnail_url
+                            : galleryRow.isImage ? galleryRow.media_url : ""
+                        fillMode: Image.PreserveAspectCrop
+                        sourceSize.width: Kirigami.Units.gridUnit * 3
+                        sourceSize.height: Kirigami.Units.gridUnit * 3
+                        asynchronous: true
+                        visible: source.toString().length > 0
+                    }
+
+                    Kirigami.Icon {
+                        anchors.centerIn: parent
+                        width: Kirigami.Units.iconSizes.medium
+                        height: Kirigami.Units.iconSizes.medium
+                        source: galleryRow.is_link ? "link"
+                            : galleryRow.isVideo ? "media-playback-start"
+                            : galleryRow.mime_type.replace("/", "-")
+                        fallback: "unknown"
+                        visible: galleryRow.is_link || !galleryRow.isImage
+                    }
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 0
+
+                    Controls.Label {
+                        Layout.fillWidth: true
+                        text: galleryRow.is_link ? galleryRow.link_url
+                            : galleryRow.file_name.length > 0 ? galleryRow.file_name
+                            : galleryRow.isVideo ? "Video"
+                            : galleryRow.isImage ? "Photo"
+                            : "Attachment"
+                        elide: galleryRow.is_link ? Text.ElideRight : Text.ElideMiddle
+                    }
+                    Controls.Label {
+                        Layout.fillWidth: true
+                        text: galleryRow.sender_name.length > 0
+                            ? galleryRow.sender_name + " · " + galleryRow.date
+                            : galleryRow.date
+                        opacity: 0.7
+                        font: Kirigami.Theme.smallFont
+                        elide: Text.ElideRight
+                    }
+                }
+            }
+        }
+
+        footer: Controls.BusyIndicator {
+            width: ListView.view.width
+            running: mediaGalleryDialog.gallery.loading
+            visible: running
+        }
+
+        Kirigami.PlaceholderMessage {
+            anchors.centerIn: parent
+            width: parent.width - Kirigami.Units.gridUnit * 4
+            visible: galleryList.count === 0 && !mediaGalleryDialog.gallery.loading
+            icon.name: "folder-pictures"
+            text: "No media in this conversation"
+        }
+    }
+}
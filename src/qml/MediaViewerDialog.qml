@@ -17,10 +17,66 @@ Controls.Dialog {
     
     property string sourceUrl: ""
     property string mimeType: ""
+    // Original name of the attachment, used when saving
+    property string fileName: ""
     property bool isActualSize: false
+    // Media gallery the item was opened from, for stepping through the rest
+    property var gallery: null
+    property int galleryIndex: -1
     property bool windowTooBig: viewerContent.width >= imageActual.implicitWidth && viewerContent.height >= imageActual.implicitHeight
 
     readonly property bool isVideo: mimeType.startsWith("video/")
+
+    // Show a gallery row, downloading it first if needed
+    function showGalleryItem(row) {
+        const item = JSON.parse(gallery.item_at(row))
+        if (item.mime_type === undefined) {
+            return
+        }
+        galleryIndex = row
+        gallery.request_thumbnail(row)
+        mimeType = item.mime_type
+        fileName = item.file_name
+        sourceUrl = item.media_url
+        isActualSize = false
+    }
+
+    // Next (delta 1) or previous (delta -1) photo or video in the gallery
+    function stepGallery(delta) {
+        if (!gallery) {
+            return
+        }
+        for (let row = galleryIndex + delta; row >= 0 && row < gallery.rowCount(); row += delta) {
+            const item = JSON.parse(gallery.item_at(row))
+            if (item.mime_type.startsWith("image/") || item.mime_type.startsWith("video/")) {
+                if (videoPlayer.playbackState === MediaPlayer.PlayingState) {
+                    videoPlayer.stop()
+                }
+                showGalleryItem(row)
+                return
+            }
+        }
+        // Older items may still be on the server
+        if (delta > 0 && gallery.has_more) {
+            gallery.load_more()
+        }
+    }
+
+    Connections {
+        target: mediaViewerDialog.gallery
+        enabled: mediaViewerDialog.opened && mediaViewerDialog.galleryIndex >= 0
+
+        // Pick up the media URL once a video or file has downloaded
+        function onDataChanged(topLeft, bottomRight) {
+            const row = mediaViewerDialog.galleryIndex
+            if (topLeft.row <= row && row <= bottomRight.row) {
+                const item = JSON.parse(mediaViewerDialog.gallery.item_at(row))
+                if (item.media_url !== mediaViewerDialog.sourceUrl) {
+                    mediaViewerDialog.sourceUrl = item.media_url
+                }
+            }
+        }
+    }
     
     onClosed: {
         if (videoPlayer.playbackState === MediaPlayer.PlayingState) {
@@ -28,7 +84,10 @@ Controls.Dialog {
         }
         sourceUrl = ""
         mimeType = ""
+        fileName = ""
         isActualSize = false
+        gallery = null
+        galleryIndex = -1
     }
     
     background: Rectangle {
@@ -40,6 +99,10 @@ Controls.Dialog {
         id: viewerContent
         anchors.fill: parent
         clip: true
+        focus: true
+
+        Keys.onLeftPressed: mediaViewerDialog.stepGallery(-1)
+        Keys.onRightPressed: mediaViewerDialog.stepGallery(1)
 
         // ── Image viewer ──
         Flickable {
@@ -209,6 +272,26 @@ Controls.Dialog {
             }
         }
 
+        // ── Gallery navigation ──
+        Controls.RoundButton {
+            anchors.left: parent.left
+            anchors.verticalCenter: parent.verticalCenter
+            anchors.margins: Kirigami.Units.largeSpacing
+            icon.name: "go-previous"
+            visible: mediaViewerDialog.gallery !== null
+            enabled: mediaViewerDialog.galleryIndex > 0
+            onClicked: mediaViewerDialog.stepGallery(-1)
+        }
+
+        Controls.RoundButton {
+            anchors.right: parent.right
+            anchors.verticalCenter: parent.verticalCenter
+            anchors.margins: Kirigami.Units.largeSpacing
+            icon.name: "go-next"
+            visible: mediaViewerDialog.gallery !== null
+            onClicked: mediaViewerDialog.stepGallery(1)
+        }
+
         // ── Top toolbar (close + download) ──
         RowLayout {
             anchors.top: parent.top
@@ -221,17 +304,12 @@ Controls.Dialog {
                 Controls.ToolTip.text: "Save to Downloads"
                 Controls.ToolTip.visible: hovered
                 Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
-                onClicked: {
-                    const path = root.messageListModel.save_media(
-                        mediaViewerDialog.sourceUrl,
-                        mediaViewerDialog.mimeType
-                    )
-                    if (path.length > 0) {
-                        root.showPassiveNotification("Saved to " + path, "long")
-                    } else {
-                        root.showPassiveNotification("Failed to save media", "short")
-                    }
-                }
+                // Reported through onMedia_saved in Main.qml
+                onClicked: root.messageListModel.save_media(
+                    mediaViewerDialog.sourceUrl,
+                    mediaViewerDialog.mimeType,
+                    mediaViewerDialog.fileName
+                )
             }
 
             Controls.RoundButton {
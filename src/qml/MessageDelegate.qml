@@ -1,6 +1,7 @@
 import QtQuick
 import QtQuick.Layouts
 import QtQuick.Controls as Controls
+import QtMultimedia
 import org.kde.kirigami as Kirigami
 
 Item {
@@ -26,6 +27,25 @@ Item {
     required property string link_image_url
     required property int media_width
     required property int media_height
+    required property string sender_name
+    required property bool is_first_in_group
+    required property bool is_last_in_group
+    required property string sim_label
+    required property string transport_label
+    required property int audio_duration_ms
+    required property string waveform
+    required property string file_name
+    required property int file_size
+    // JSON list of every part when a message has more than one attachment
+    required property string attachments
+    // Shared location, and business message cards and chips as JSON lists
+    required property bool has_location
+    required property real latitude
+    required property real longitude
+    required property string location_label
+    required property string location_url
+    required property string rich_cards
+    required property string suggestions
 
     required property bool is_start_of_day
 
@@ -37,7 +57,35 @@ Item {
     // 1=SMS, 2=Downloaded MMS, 3=Undownloaded MMS
     readonly property bool isSms: messageDelegate.transport_type === 1 || messageDelegate.transport_type === 2 || messageDelegate.transport_type === 3
     readonly property bool isVideo: messageDelegate.mime_type.startsWith("video/")
+    readonly property bool isAudio: messageDelegate.mime_type.startsWith("audio/")
+    readonly property bool isImage: messageDelegate.mime_type.length === 0 || messageDelegate.mime_type.startsWith("image/")
+    // Documents, contact cards and anything else shown as a file row
+    readonly property bool isFile: messageDelegate.is_media && !messageDelegate.isAlbum && !messageDelegate.isImage && !messageDelegate.isVideo && !messageDelegate.isAudio
+    readonly property var albumItems: messageDelegate.attachments.length > 0 ? JSON.parse(messageDelegate.attachments) : []
+    readonly property bool isAlbum: messageDelegate.albumItems.length > 1
     readonly property bool hasLinkPreview: messageDelegate.link_title.length > 0
+    readonly property var cardItems: messageDelegate.rich_cards.length > 0 ? JSON.parse(messageDelegate.rich_cards) : []
+    readonly property var suggestionItems: messageDelegate.suggestions.length > 0 ? JSON.parse(messageDelegate.suggestions) : []
+    // A location sent on its own shows just the location card
+    readonly property bool bodyIsLocationLink: messageDelegate.has_location && !/\s/.test(messageDelegate.body.trim())
+
+    // Send a suggested reply, or open a suggested link, call or map
+    function runSuggestion(suggestion) {
+        if (suggestion.kind === "reply") {
+            root.messageListModel.send_message(suggestion.value, "")
+        } else if (/^(https|tel):/.test(suggestion.value)) {
+            Qt.openUrlExternally(suggestion.value)
+        }
+    }
+
+    function suggestionIcon(kind) {
+        switch (kind) {
+        case "url": return "internet-services"
+        case "dial": return "call-start"
+        case "location": return "mark-location"
+        default: return ""
+        }
+    }
 
     // Convert plain-text body into HTML with clickable links
     function linkifyBody(text) {
@@ -50,6 +98,19 @@ Item {
         return escaped;
     }
 
+    function formatDuration(ms) {
+        const total = Math.round(ms / 1000)
+        const seconds = total % 60
+        return Math.floor(total / 60) + ":" + (seconds < 10 ? "0" : "") + seconds
+    }
+
+    function formatFileSize(bytes) {
+        if (bytes <= 0) return ""
+        if (bytes < 1024) return bytes + " B"
+        if (bytes < 1024 * 1024) return (bytes / 1024).toFixed(1) + " KB"
+        return (bytes / (1024 * 1024)).toFixed(1) + " MB"
+    }
+
     ColumnLayout {
         id: messageCol
         width: parent.width
@@ -102,6 +163,20 @@ Item {
             Layout.maximumWidth: parent.width * 0.8
         }
 
+        // Sender name above the first message of a run in group chats
+        Controls.Label {
+            Layout.leftMargin: Kirigami.Units.gridUnit * 1.5 + Kirigami.Units.smallSpacing * 2
+            visible: !messageDelegate.is_info
+                && !messageDelegate.from_me
+                && root.selectedIsGroupChat
+                && messageDelegate.is_first_in_group
+                && messageDelegate.sender_name.length > 0
+            text: messageDelegate.sender_name
+            font: Kirigami.Theme.smallFont
+            color: Kirigami.Theme.disabledTextColor
+            elide: Text.ElideRight
+        }
+
         // Bubble row with avatar
         RowLayout {
             Layout.fillWidth: true
@@ -109,11 +184,12 @@ Item {
             spacing: Kirigami.Units.smallSpacing
             visible: !messageDelegate.is_info
 
-            // Avatar circle
+            // Avatar circle, only beside the last message of a run
             Rectangle {
                 Layout.preferredWidth: Kirigami.Units.gridUnit * 1.5
                 Layout.preferredHeight: Kirigami.Units.gridUnit * 1.5
                 Layout.alignment: Qt.AlignBottom
+                opacity: messageDelegate.is_last_in_group ? 1 : 0
                 radius: width / 2
                 color: messageDelegate.from_me
                     ? Kirigami.Theme.highlightColor
@@ -195,17 +271,24 @@ Item {
                         Layout.alignment: Qt.AlignHCenter
                         Layout.margins: 0
                         fillMode: Image.PreserveAspectFit
-                        source: messageDelegate.isVideo ? "" : messageDelegate.media_url
-                        visible: messageDelegate.is_media && messageDelegate.media_url.length > 0 && !messageDelegate.isVideo
+                        source: messageDelegate.isImage ? messageDelegate.media_url : ""
+                        visible: messageDelegate.is_media && !messageDelegate.isAlbum && messageDelegate.media_url.length > 0 && messageDelegate.isImage
                         sourceSize.width: 400
                         sourceSize.height: 400
                         asynchronous: true
 
+                        // Shown while the image provider downloads the attachment
+                        Controls.BusyIndicator {
+                            anchors.centerIn: parent
+                            visible: parent.status === Image.Loading
+                        }
+
                         MouseArea {
                             anchors.fill: parent
                             cursorShape: Qt.PointingHandCursor
                             onClicked: {
                                 mediaViewerDialog.mimeType = messageDelegate.mime_type
+                                mediaViewerDialog.fileName = messageDelegate.file_name
                                 mediaViewerDialog.sourceUrl = messageDelegate.media_url
                                 mediaViewerDialog.isActualSize = false
                                 mediaViewerDialog.open()
@@ -257,7 +340,7 @@ Item {
                         Layout.preferredWidth: Kirigami.Units.gridUnit * 12
                         Layout.preferredHeight: Kirigami.Units.gridUnit * 9
                         Layout.alignment: Qt.AlignHCenter
-                        visible: messageDelegate.is_media && messageDelegate.media_url.length > 0 && messageDelegate.isVideo
+                        visible: messageDelegate.is_media && !messageDelegate.isAlbum && messageDelegate.media_url.length > 0 && messageDelegate.isVideo
 
                         Rectangle {
                             anchors.fill: parent
@@ -349,6 +432,7 @@ Item {
                             cursorShape: Qt.PointingHandCursor
                             onClicked: {
                                 mediaViewerDialog.mimeType = messageDelegate.mime_type
+                                mediaViewerDialog.fileName = messageDelegate.file_name
                                 mediaViewerDialog.sourceUrl = messageDelegate.media_url
                                 mediaViewerDialog.isActualSize = false
                                 mediaViewerDialog.open()
@@ -356,9 +440,368 @@ Item {
                         }
                     }
 
+                    // ── Audio media (voice message with waveform) ──
+                    RowLayout {
+                        id: audioRow
+
+                        readonly property color foreground: messageDelegate.from_me
+                            ? Kirigami.Theme.highlightedTextColor
+                            : Kirigami.Theme.textColor
+                        readonly property var levels: messageDelegate.waveform.length > 0
+                            ? messageDelegate.waveform.split(",").map(Number)
+                            : []
+                        readonly property bool playing: audioPlayer.playbackState === MediaPlayer.PlayingState
+                        readonly property real progress: audioPlayer.duration > 0
+                            ? audioPlayer.position / audioPlayer.duration
+                            : 0
+
+                        Layout.maximumWidth: messageCol.width * 0.6
+                        Layout.preferredWidth: Kirigami.Units.gridUnit * 14
+                        visible: messageDelegate.is_media && !messageDelegate.isAlbum && messageDelegate.media_url.length > 0 && messageDelegate.isAudio
+                        spacing: Kirigami.Units.smallSpacing
+
+                        MediaPlayer {
+                            id: audioPlayer
+                            audioOutput: AudioOutput {}
+                        }
+
+                        Controls.ToolButton {
+                            icon.name: audioRow.playing ? "media-playback-pause" : "media-playback-start"
+                            icon.color: audioRow.foreground
+                            onClicked: {
+                                if (audioRow.playing) {
+                                    audioPlayer.pause()
+                                } else {
+                                    // Only load the file once it is actually played
+                                    audioPlayer.source = messageDelegate.media_url
+                                    audioPlayer.play()
+                                }
+                            }
+                        }
+
+                        Row {
+                            id: waveformRow
+                            Layout.fillWidth: true
+                            Layout.preferredHeight: Kirigami.Units.gridUnit * 1.5
+                            spacing: 1
+
+                            Repeater {
+                                model: audioRow.levels
+
+                                Rectangle {
+                                    required property int index
+                                    required property real modelData
+
+                                    anchors.verticalCenter: parent.verticalCenter
+                                    width: Math.max(1, (waveformRow.width - (audioRow.levels.length - 1)) / audioRow.levels.length)
+                                    height: Math.max(2, waveformRow.height * modelData / 100)
+                                    radius: width / 2
+                                    color: audioRow.foreground
+                                    opacity: index / audioRow.levels.length < audioRow.progress ? 1.0 : 0.45
+                                }
+                            }
+                        }
+
+                        Controls.Label {
+                            text: messageDelegate.formatDuration(audioRow.playing || audioPlayer.position > 0
+                                ? audioPlayer.position
+                                : messageDelegate.audio_duration_ms)
+                            color: audioRow.foreground
+                            font: Kirigami.Theme.smallFont
+                        }
+                    }
+
                     Controls.BusyIndicator {
                         Layout.alignment: Qt.AlignHCenter
-                        visible: messageDelegate.is_media && messageDelegate.media_url.length === 0
+                        visible: messageDelegate.is_media && !messageDelegate.isAlbum && messageDelegate.media_url.length === 0
+                    }
+
+                    // ── Album (several attachments in one message) ──
+                    GridLayout {
+                        id: albumGrid
+
+                        readonly property real cellSize: (Math.min(messageCol.width * 0.6, Kirigami.Units.gridUnit * 16) - columnSpacing) / 2
+
+                        Layout.alignment: Qt.AlignHCenter
+                        visible: messageDelegate.isAlbum
+                        columns: 2
+                        columnSpacing: Kirigami.Units.smallSpacing
+                        rowSpacing: Kirigami.Units.smallSpacing
+
+                        Repeater {
+                            model: messageDelegate.isAlbum ? messageDelegate.albumItems : []
+
+                            Rectangle {
+                                id: albumCell
+                                required property var modelData
+
+                                readonly property bool cellIsVideo: modelData.mime_type.startsWith("video/")
+                                readonly property bool cellIsImage: modelData.mime_type.startsWith("image/")
+                                readonly property string preview: cellIsVideo ? modelData.thumbnail_url
+                                    : cellIsImage ? modelData.media_url : ""
+
+                                Layout.preferredWidth: albumGrid.cellSize
+                                Layout.preferredHeight: albumGrid.cellSize
+                                radius: Kirigami.Units.smallSpacing
+                                color: Qt.rgba(0, 0, 0, 0.3)
+                                clip: true
+
+                                Image {
+                                    id: albumImage
+                                    anchors.fill: parent
+                                    source: albumCell.preview
+                                    fillMode: Image.PreserveAspectCrop
+                                    sourceSize.width: 400
+                                    sourceSize.height: 400
+                                    asynchronous: true
+                                    visible: albumCell.preview.length > 0
+                                }
+
+                                Kirigami.Icon {
+                                    anchors.centerIn: parent
+                                    width: Kirigami.Units.iconSizes.medium
+                                    height: Kirigami.Units.iconSizes.medium
+                                    source: albumCell.cellIsVideo ? "media-playback-start"
+                                        : albumCell.modelData.mime_type.replace("/", "-")
+                                    fallback: "unknown"
+                                    color: "white"
+                                    visible: albumCell.cellIsVideo || !albumCell.cellIsImage
+                                }
+
+                                Controls.BusyIndicator {
+                                    anchors.centerIn: parent
+                                    visible: albumCell.modelData.media_url.length === 0 || albumImage.status === Image.Loading
+                                }
+
+                                MouseArea {
+                                    anchors.fill: parent
+                                    cursorShape: Qt.PointingHandCursor
+                                    enabled: albumCell.modelData.media_url.length > 0
+                                    onClicked: {
+                                        mediaViewerDialog.mimeType = albumCell.modelData.mime_type
+                                        mediaViewerDialog.fileName = albumCell.modelData.file_name
+                                        mediaViewerDialog.sourceUrl = albumCell.modelData.media_url
+                                        mediaViewerDialog.isActualSize = false
+                                        mediaViewerDialog.open()
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        visible: messageDelegate.isAlbum && messageDelegate.isSending
+
+                        Controls.ProgressBar {
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 1
+                            value: messageDelegate.upload_progress
+                            indeterminate: messageDelegate.upload_progress === 0.0
+                        }
+
+                        Controls.ToolButton {
+                            icon.name: "dialog-close"
+                            onClicked: messageListModel.delete_message(messageDelegate.message_id)
+
+                            Controls.ToolTip.text: "Cancel upload"
+                            Controls.ToolTip.visible: hovered
+                            Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+                        }
+                    }
+
+                    // ── Other attachments (documents, contact cards, …) ──
+                    Controls.AbstractButton {
+                        id: fileRow
+
+                        readonly property color foreground: messageDelegate.from_me
+                            ? Kirigami.Theme.highlightedTextColor
+                            : Kirigami.Theme.textColor
+
+                        Layout.maximumWidth: messageCol.width * 0.6
+                        Layout.preferredWidth: Kirigami.Units.gridUnit * 14
+                        visible: messageDelegate.isFile
+                        enabled: messageDelegate.media_url.length > 0 && !messageDelegate.isSending
+                        hoverEnabled: true
+
+                        Controls.ToolTip.text: "Save to Downloads"
+                        Controls.ToolTip.visible: hovered
+                        Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+
+                        // Reported through onMedia_saved in Main.qml
+                        onClicked: root.messageListModel.save_media(
+                            messageDelegate.media_url,
+                            messageDelegate.mime_type,
+                            messageDelegate.file_name
+                        )
+
+                        contentItem: RowLayout {
+                            spacing: Kirigami.Units.smallSpacing
+
+                            Kirigami.Icon {
+                                Layout.preferredWidth: Kirigami.Units.iconSizes.medium
+                                Layout.preferredHeight: Kirigami.Units.iconSizes.medium
+                                source: messageDelegate.mime_type.replace("/", "-")
+                                fallback: "unknown"
+                            }
+
+                            ColumnLayout {
+                                Layout.fillWidth: true
+                                spacing: 0
+
+                                Controls.Label {
+                                    Layout.fillWidth: true
+                                    text: messageDelegate.file_name.length > 0 ? messageDelegate.file_name : "Attachment"
+                                    color: fileRow.foreground
+                                    elide: Text.ElideMiddle
+                                }
+                                Controls.Label {
+                                    Layout.fillWidth: true
+                                    text: messageDelegate.media_url.length > 0
+                                        ? messageDelegate.formatFileSize(messageDelegate.file_size)
+                                        : "Downloading…"
+                                    visible: text.length > 0
+                                    color: fileRow.foreground
+                                    opacity: 0.7
+                                    font: Kirigami.Theme.smallFont
+                                }
+                            }
+
+                            Controls.BusyIndicator {
+                                Layout.preferredWidth: Kirigami.Units.iconSizes.small
+                                Layout.preferredHeight: Kirigami.Units.iconSizes.small
+                                running: visible
+                                visible: messageDelegate.media_url.length === 0 || messageDelegate.isSending
+                            }
+                        }
+                    }
+
+                    // ── Shared location ──
+                    Controls.AbstractButton {
+                        id: locationRow
+
+                        readonly property color foreground: messageDelegate.from_me
+                            ? Kirigami.Theme.highlightedTextColor
+                            : Kirigami.Theme.textColor
+
+                        Layout.maximumWidth: messageCol.width * 0.6
+                        Layout.preferredWidth: Kirigami.Units.gridUnit * 14
+                        visible: messageDelegate.has_location
+                        hoverEnabled: true
+
+                        Controls.ToolTip.text: "Open map"
+                        Controls.ToolTip.visible: hovered
+                        Controls.ToolTip.delay: Kirigami.Units.toolTipDelay
+
+                        onClicked: Qt.openUrlExternally(messageDelegate.location_url)
+
+                        contentItem: RowLayout {
+                            spacing: Kirigami.Units.smallSpacing
+
+                            Kirigami.Icon {
+                                Layout.preferredWidth: Kirigami.Units.iconSizes.medium
+                                Layout.preferredHeight: Kirigami.Units.iconSizes.medium
+                                source: "mark-location"
+                                color: locationRow.foreground
+                            }
+
+                            ColumnLayout {
+                                Layout.fillWidth: true
+                                spacing: 0
+
+                                Controls.Label {
+                                    Layout.fillWidth: true
+                                    text: messageDelegate.location_label.length > 0 ? messageDelegate.location_label : "Shared location"
+                                    color: locationRow.foreground
+                                    elide: Text.ElideRight
+                                }
+                                Controls.Label {
+                                    Layout.fillWidth: true
+                                    text: messageDelegate.latitude.toFixed(5) + ", " + messageDelegate.longitude.toFixed(5)
+                                    color: locationRow.foreground
+                                    opacity: 0.7
+                                    font: Kirigami.Theme.smallFont
+                                }
+                            }
+                        }
+                    }
+
+                    // ── Rich cards from business messages ──
+                    Repeater {
+                        model: messageDelegate.cardItems
+
+                        delegate: Rectangle {
+                            id: richCard
+                            required property var modelData
+
+                            Layout.fillWidth: true
+                            Layout.preferredWidth: Kirigami.Units.gridUnit * 14
+                            implicitHeight: richCardColumn.implicitHeight
+                            radius: Kirigami.Units.smallSpacing
+                            color: Qt.rgba(Kirigami.Theme.textColor.r,
+                                           Kirigami.Theme.textColor.g,
+                                           Kirigami.Theme.textColor.b, 0.06)
+                            border.width: 1
+                            border.color: Qt.rgba(Kirigami.Theme.textColor.r,
+                                                  Kirigami.Theme.textColor.g,
+                                                  Kirigami.Theme.textColor.b, 0.1)
+                            clip: true
+
+                            ColumnLayout {
+                                id: richCardColumn
+                                anchors.left: parent.left
+                                anchors.right: parent.right
+                                spacing: 0
+
+                                Image {
+                                    Layout.fillWidth: true
+                                    Layout.maximumHeight: Kirigami.Units.gridUnit * 10
+                                    fillMode: Image.PreserveAspectCrop
+                                    // Cached through the link preview policy, never loaded from the web here
+                                    source: richCard.modelData.image_url.startsWith("image://kourier/")
+                                        ? richCard.modelData.image_url
+                                        : ""
+                                    visible: source.toString().length > 0 && status === Image.Ready
+                                    asynchronous: true
+                                    sourceSize.width: 600
+                                }
+
+                                ColumnLayout {
+                                    Layout.fillWidth: true
+                                    Layout.margins: Kirigami.Units.smallSpacing * 1.5
+                                    spacing: Kirigami.Units.smallSpacing * 0.5
+
+                                    Controls.Label {
+                                        Layout.fillWidth: true
+                                        text: richCard.modelData.title
+                                        visible: text.length > 0
+                                        wrapMode: Text.WordWrap
+                                        font.weight: Font.Medium
+                                    }
+                                    Controls.Label {
+                                        Layout.fillWidth: true
+                                        text: richCard.modelData.description
+                                        visible: text.length > 0
+                                        wrapMode: Text.WordWrap
+                                        opacity: 0.8
+                                    }
+
+                                    Repeater {
+                                        model: richCard.modelData.suggestions
+
+                                        delegate: Controls.Button {
+                                            required property var modelData
+                                            Layout.fillWidth: true
+                                            text: modelData.text
+                                            icon.name: messageDelegate.suggestionIcon(modelData.kind)
+                                            flat: true
+                                            onClicked: messageDelegate.runSuggestion(modelData)
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     }
 
                     // ── Message body text with clickable links ──
@@ -382,7 +825,7 @@ Item {
                             ? Kirigami.Theme.backgroundColor
                             : Kirigami.Theme.highlightColor
                         font.pointSize: Kirigami.Theme.defaultFont.pointSize
-                        visible: messageDelegate.body.length > 0
+                        visible: messageDelegate.body.length > 0 && !messageDelegate.bodyIsLocationLink
                         onLinkActivated: function(link) {
                             Qt.openUrlExternally(link)
                         }
@@ -394,6 +837,18 @@ Item {
                         }
                     }
 
+                    // ── SIM used, only set on dual-SIM phones ──
+                    Controls.Label {
+                        Layout.alignment: Qt.AlignRight
+                        text: messageDelegate.sim_label
+                        visible: messageDelegate.sim_label.length > 0
+                        color: messageDelegate.from_me
+                            ? Kirigami.Theme.highlightedTextColor
+                            : Kirigami.Theme.textColor
+                        opacity: 0.7
+                        font: Kirigami.Theme.smallFont
+                    }
+
                     // ── Link preview card ──
                     Rectangle {
                         id: linkPreviewCard
@@ -506,6 +961,13 @@ Item {
                         }
                     }
 
+                    Controls.MenuItem {
+                        text: "Details"
+                        icon.name: "documentinfo"
+                        enabled: !messageDelegate.is_info
+                        onTriggered: messageDetailsDialog.showFor(messageDelegate.message_id)
+                    }
+
                     Controls.MenuItem {
                         text: "Delete message"
                         icon.name: "edit-delete"
@@ -521,6 +983,26 @@ Item {
             Item { Layout.fillWidth: true }
         }
 
+        // Suggested replies and actions, offered on the latest message only
+        Flow {
+            Layout.fillWidth: true
+            Layout.leftMargin: Kirigami.Units.largeSpacing
+            Layout.rightMargin: Kirigami.Units.largeSpacing
+            spacing: Kirigami.Units.smallSpacing
+            visible: messageDelegate.index === 0 && !messageDelegate.from_me && messageDelegate.suggestionItems.length > 0
+
+            Repeater {
+                model: messageDelegate.index === 0 ? messageDelegate.suggestionItems : []
+
+                delegate: Controls.Button {
+                    required property var modelData
+                    text: modelData.text
+                    icon.name: messageDelegate.suggestionIcon(modelData.kind)
+                    onClicked: messageDelegate.runSuggestion(modelData)
+                }
+            }
+        }
+
         // Status row (time + delivery icon)
         RowLayout {
             Layout.alignment: messageDelegate.from_me ? Qt.AlignRight : Qt.AlignLeft
@@ -534,6 +1016,11 @@ Item {
                 color: Kirigami.Theme.disabledTextColor
                 font: Kirigami.Theme.smallFont
             }
+            Controls.Label {
+                text: "\u00B7 " + messageDelegate.transport_label
+                color: Kirigami.Theme.disabledTextColor
+                font: Kirigami.Theme.smallFont
+            }
             Controls.Label {
                 text: "\u00B7"
                 color: Kirigami.Theme.disabledTextColor
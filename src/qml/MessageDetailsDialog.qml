@@ -0,0 +1,141 @@
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls as Controls
+import org.kde.kirigami as Kirigami
+import org.kde.kirigamiaddons.delegates as Delegates
+
+Controls.Dialog {
+    id: messageDetailsDialog
+
+    // Parsed result of MessageList.message_details
+    property var details: ({})
+
+    function showFor(messageId) {
+        details = JSON.parse(root.messageListModel.message_details(messageId))
+        open()
+    }
+
+    function stateLabel(state) {
+        switch (state) {
+        case "read": return "Read"
+        case "received": return "Delivered"
+        case "sending": return "Sending"
+        default: return "Sent"
+        }
+    }
+
+    function stateIcon(state) {
+        switch (state) {
+        case "read": return "qrc:/svg/readIcon.svg"
+        case "received": return "qrc:/svg/receivedIcon.svg"
+        case "sending": return "qrc:/svg/sendingIcon.svg"
+        default: return "qrc:/svg/sentIcon.svg"
+        }
+    }
+
+    title: "Message Details"
+    modal: true
+    standardButtons: Controls.Dialog.Close
+    width: Math.min(root.width * 0.80, Kirigami.Units.gridUnit * 22)
+    height: Math.min(root.height * 0.70, Kirigami.Units.gridUnit * 26)
+
+    onClosed: details = ({})
+
+    contentItem: ColumnLayout {
+        spacing: Kirigami.Units.smallSpacing
+
+        Kirigami.FormLayout {
+            Layout.fillWidth: true
+            visible: messageDetailsDialog.details.message_id !== undefined
+
+            Controls.Label {
+                Kirigami.FormData.label: "Sent via:"
+                text: messageDetailsDialog.details.transport || ""
+            }
+            Controls.Label {
+                Kirigami.FormData.label: messageDetailsDialog.details.from_me ? "Sent:" : "Received:"
+                text: messageDetailsDialog.details.time || ""
+            }
+            RowLayout {
+                Kirigami.FormData.label: "Status:"
+                visible: messageDetailsDialog.details.from_me === true
+                spacing: Kirigami.Units.smallSpacing
+
+                Image {
+                    Layout.preferredWidth: 18
+                    Layout.preferredHeight: 12
+                    fillMode: Image.PreserveAspectFit
+                    source: messageDetailsDialog.stateIcon(messageDetailsDialog.details.status)
+                }
+                Controls.Label {
+                    text: messageDetailsDialog.stateLabel(messageDetailsDialog.details.status)
+                        + " (" + messageDetailsDialog.details.status_code + ")"
+                }
+            }
+        }
+
+        Kirigami.Heading {
+            Layout.fillWidth: true
+            level: 4
+            text: "Recipients"
+            visible: recipientListView.count > 0
+        }
+
+        Controls.Label {
+            Layout.fillWidth: true
+            visible: recipientListView.count > 1
+            text: "The phone reports one status for the whole message, not for each recipient."
+            wrapMode: Text.WordWrap
+            opacity: 0.7
+            font: Kirigami.Theme.smallFont
+        }
+
+        Controls.ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            ListView {
+                id: recipientListView
+
+                model: messageDetailsDialog.details.recipients || []
+                clip: true
+
+                delegate: Delegates.RoundedItemDelegate {
+                    id: recipientDelegate
+
+                    required property var modelData
+
+                    width: recipientListView.width
+
+                    contentItem: ColumnLayout {
+                        spacing: 0
+
+                        Controls.Label {
+                            Layout.fillWidth: true
+                            text: recipientDelegate.modelData.name.length > 0
+                                ? recipientDelegate.modelData.name
+                                : recipientDelegate.modelData.number
+                            elide: Text.ElideRight
+                        }
+                        Controls.Label {
+                            Layout.fillWidth: true
+                            text: recipientDelegate.modelData.number
+                            visible: recipientDelegate.modelData.name.length > 0
+                                && recipientDelegate.modelData.number.length > 0
+                            opacity: 0.7
+                            font: Kirigami.Theme.smallFont
+                            elide: Text.ElideRight
+                        }
+                    }
+                }
+            }
+
+            Kirigami.PlaceholderMessage {
+                anchors.centerIn: parent
+                width: parent.width - Kirigami.Units.gridUnit * 4
+                visible: messageDetailsDialog.details.message_id === undefined
+                text: "No details available for this message yet"
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls as Controls
+import org.kde.kirigami as Kirigami
+import org.kde.kirigamiaddons.delegates as Delegates
+import org.kourier
+
+Controls.Dialog {
+    id: participantsDialog
+
+    readonly property ParticipantList participantList: ParticipantList {}
+    property string conversationId: ""
+
+    title: root.selectedConversationName
+    modal: true
+    standardButtons: Controls.Dialog.Close
+    width: Math.min(root.width * 0.80, Kirigami.Units.gridUnit * 22)
+    height: Math.min(root.height * 0.70, Kirigami.Units.gridUnit * 26)
+
+    onOpened: {
+        conversationId = root.conversationList.conversation_id(root.selectedConversationIndex)
+        nameField.text = root.selectedConversationName
+        participantList.load(conversationId)
+    }
+
+    Connections {
+        target: root.conversationList
+
+        function onGroup_action_finished(conversationId, action, error) {
+            if (conversationId !== participantsDialog.conversationId || error.length > 0) {
+                return
+            }
+            if (action === "add") {
+                participantsDialog.participantList.load(conversationId)
+            } else if (action === "leave") {
+                participantsDialog.close()
+            }
+        }
+    }
+
+    contentItem: ColumnLayout {
+        spacing: Kirigami.Units.smallSpacing
+
+        RowLayout {
+            Layout.fillWidth: true
+            visible: root.selectedIsGroupChat
+
+            Controls.TextField {
+                id: nameField
+                Layout.fillWidth: true
+                placeholderText: "Group name"
+                onAccepted: renameButton.clicked()
+            }
+            Controls.Button {
+                id: renameButton
+                text: "Rename"
+                enabled: nameField.text.trim().length > 0 && nameField.text.trim() !== root.selectedConversationName
+                onClicked: root.conversationList.rename_group(participantsDialog.conversationId, nameField.text)
+            }
+        }
+
+        Controls.ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            ListView {
+                id: participantListView
+
+                model: participantsDialog.participantList
+                clip: true
+
+                delegate: Delegates.RoundedItemDelegate {
+                    id: participantDelegate
+
+                    required property string name
+                    required property string number
+                    required property string avatar_url
+                    required property bool is_me
+                    required property string participant_id
+
+                    width: participantListView.width
+
+                    contentItem: RowLayout {
+                        spacing: Kirigami.Units.largeSpacing
+
+                        Rectangle {
+                            Layout.preferredWidth: Kirigami.Units.gridUnit * 2
+                            Layout.preferredHeight: Kirigami.Units.gridUnit * 2
+                            radius: width / 2
+                            color: Kirigami.Theme.alternateBackgroundColor
+                            clip: true
+
+                            Image {
+                                anchors.fill: parent
+                                source: participantDelegate.avatar_url
+                                fillMode: Image.PreserveAspectCrop
+                                visible: participantDelegate.avatar_url.length > 0
+                            }
+                        }
+
+                        ColumnLayout {
+                            Layout.fillWidth: true
+                            spacing: 0
+
+                            Controls.Label {
+                                Layout.fillWidth: true
+                                text: participantDelegate.is_me
+                                    ? "You"
+                                    : (participantDelegate.name.length > 0 ? participantDelegate.name : participantDelegate.number)
+                                elide: Text.ElideRight
+                            }
+                            Controls.Label {
+                                Layout.fillWidth: true
+                                text: participantDelegate.number
+                                visible: participantDelegate.number.length > 0
+                                opacity: 0.7
+                                font: Kirigami.Theme.smallFont
+                                elide: Text.ElideRight
+                            }
+                        }
+                    }
+                }
+
+                Kirigami.PlaceholderMessage {
+                    anchors.centerIn: parent
+                    width: parent.width - Kirigami.Units.gridUnit * 4
+                    visible: participantListView.count === 0
+                    text: participantsDialog.participantList.loading ? "Loading…" : "No participants"
+                }
+            }
+        }
+
+        Controls.Label {
+            Layout.fillWidth: true
+            visible: root.selectedIsGroupChat
+            text: "Participants can only be removed from the phone."
+            wrapMode: Text.WordWrap
+            opacity: 0.7
+            font: Kirigami.Theme.smallFont
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            visible: root.selectedIsGroupChat
+
+            Controls.Button {
+                text: "Add Participants"
+                icon.name: "list-add-user"
+                onClicked: {
+                    contactPickerDialog.targetConversationId = participantsDialog.conversationId
+                    contactPickerDialog.open()
+                }
+            }
+            Item { Layout.fillWidth: true }
+            Controls.Button {
+                text: "Leave Group"
+                icon.name: "im-kick-user"
+                onClicked: root.conversationList.leave_group(participantsDialog.conversationId)
+            }
+        }
+    }
+}